@@ -5,6 +5,7 @@
 //! ```no_run
 //! #![feature(async_await, await_macro, futures_api)]
 //! # use futures::executor::block_on;
+//! # #[cfg(feature = "hyper")]
 //! # use hyper::client::Client as HttpClient;
 //! # use std::path::PathBuf;
 //! # use tuf::{Result, Tuf};
@@ -21,6 +22,7 @@
 //!     "T5vfRrM1iHpgzGwAHe7MbJH_7r4chkOAphV3OPCCv0I=",
 //! ];
 //!
+//! # #[cfg(feature = "hyper")]
 //! # fn main() -> Result<()> {
 //! # block_on(async {
 //! let key_ids: Vec<KeyId> = TRUSTED_ROOT_KEY_IDS.iter()
@@ -47,22 +49,38 @@
 //! # Ok(())
 //! # })
 //! # }
+//! # #[cfg(not(feature = "hyper"))]
+//! # fn main() {}
 //! ```
 
 use chrono::offset::Utc;
-use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite};
+use futures::io::{AllowStdIo, AsyncRead, AsyncReadExt, AsyncWrite};
+use futures::stream::{self, StreamExt};
+use futures::task::LocalWaker;
+use futures::{try_ready, Future, Poll};
 use log::{error, warn};
-
-use crate::crypto::{self, KeyId};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Debug};
+use std::fs::{self, DirBuilder, File, OpenOptions};
+use std::io::{self, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::crypto::{self, HashAlgorithm, HashValue, KeyId};
 use crate::error::Error;
 use crate::interchange::DataInterchange;
 use crate::metadata::{
-    Metadata, MetadataPath, MetadataVersion, Role, SignedMetadata, SnapshotMetadata,
-    TargetDescription, TargetPath, TargetsMetadata, VirtualTargetPath,
+    Metadata, MetadataPath, MetadataVersion, Mirror, Role, RootMetadata, SignedMetadata,
+    SnapshotMetadata, TargetDescription, TargetPath, TargetsMetadata, VirtualTargetPath,
 };
-use crate::repository::Repository;
+use crate::repository::{Repository, SlowRetrievalPolicy};
 use crate::tuf::Tuf;
+use crate::util::{self, ReadAborted, SafeReader};
 use crate::{Result, TufFuture};
+use url::Url;
 
 /// Translates real paths (where a file is stored) into virtual paths (how it is addressed in TUF)
 /// and back.
@@ -109,6 +127,127 @@ impl PathTranslator for DefaultTranslator {
     }
 }
 
+/// Something a `Client` did, as reported to an `Observer`.
+#[derive(Debug)]
+pub enum Event<'a> {
+    /// Started updating the metadata for `role` from the remote repository.
+    MetadataFetchStarted {
+        /// The role of the metadata.
+        role: &'a Role,
+    },
+    /// Finished updating the metadata for `role`.
+    MetadataFetchFinished {
+        /// The role of the metadata.
+        role: &'a Role,
+        /// Whether newer metadata is now trusted.
+        updated: bool,
+    },
+    /// Updating the metadata for `role` failed, and the update was aborted.
+    MetadataFetchFailed {
+        /// The role of the metadata.
+        role: &'a Role,
+        /// Why it failed.
+        error: &'a Error,
+    },
+    /// The trusted root metadata was replaced by the next version, which may have new keys. This
+    /// happens once for every version between the trusted one and the latest one.
+    RootRotated {
+        /// The version that was trusted before.
+        from_version: u32,
+        /// The version that is trusted now.
+        to_version: u32,
+    },
+    /// Fetching metadata or a target from the remote repository, or from an earlier mirror, failed,
+    /// and it is now fetched from `mirror`.
+    MirrorFailover {
+        /// The metadata, if it was metadata that failed.
+        metadata: Option<&'a MetadataPath>,
+        /// The target, if it was a target that failed.
+        target: Option<&'a TargetPath>,
+        /// The base URL of the mirror.
+        mirror: &'a Url,
+        /// Why the last attempt failed.
+        error: &'a Error,
+    },
+    /// Started downloading a target.
+    TargetFetchStarted {
+        /// The target.
+        target: &'a TargetPath,
+        /// The size of the target in bytes.
+        total: u64,
+    },
+    /// More of a target has been downloaded.
+    TargetBytesReceived {
+        /// The target.
+        target: &'a TargetPath,
+        /// How many bytes have been downloaded so far.
+        received: u64,
+        /// The size of the target in bytes.
+        total: u64,
+    },
+    /// Finished downloading and verifying a target.
+    TargetFetchFinished {
+        /// The target.
+        target: &'a TargetPath,
+    },
+    /// Fetching a target failed. This may happen before `TargetFetchStarted` if the target could
+    /// not be found in the metadata.
+    TargetFetchFailed {
+        /// The target.
+        target: &'a TargetPath,
+        /// Why it failed.
+        error: &'a Error,
+    },
+    /// More of a target was downloaded than its size in the trusted metadata, and it was
+    /// rejected. This is followed by a `TargetFetchFailed`.
+    TargetTooLarge {
+        /// The target.
+        target: &'a TargetPath,
+        /// The size of the target in bytes.
+        total: u64,
+    },
+    /// A target was downloaded more slowly than a `SlowRetrievalPolicy` allows, and the download
+    /// was aborted. This is followed by a `TargetFetchFailed`.
+    SlowRetrieval {
+        /// The target.
+        target: &'a TargetPath,
+    },
+    /// Metadata or a target did not match what the trusted metadata says it should be, and was
+    /// rejected. This is followed by a `MetadataFetchFailed` or `TargetFetchFailed`.
+    VerificationFailed {
+        /// The role of the metadata, or `Role::Targets` for a target.
+        role: &'a Role,
+        /// The target, if it was a target that failed.
+        target: Option<&'a TargetPath>,
+        /// Why it failed.
+        error: &'a Error,
+    },
+}
+
+/// Receives the `Event`s of a `Client`, for example to show the progress of a download or to count
+/// failures.
+pub trait Observer: Send + Sync {
+    /// Called when `event` happens. This is called from within the `Client`'s futures, so it
+    /// should return quickly.
+    fn notify(&self, event: &Event);
+}
+
+/// A shared `Observer` that can be kept in a `Config`.
+#[derive(Clone)]
+struct ObserverHandle(Arc<dyn Observer>);
+
+impl Debug for ObserverHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Observer")
+    }
+}
+
+impl PartialEq for ObserverHandle {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
 /// A client that interacts with TUF repositories.
 pub struct Client<D, L, R, T>
 where
@@ -121,6 +260,9 @@ where
     config: Config<T>,
     local: L,
     remote: R,
+    mirrors: Vec<(Mirror, R)>,
+    /// The partial download files that downloads are using.
+    partial_files: Mutex<HashSet<PathBuf>>,
 }
 
 impl<D, L, R, T> Client<D, L, R, T>
@@ -149,6 +291,8 @@ where
             config,
             local,
             remote,
+            mirrors: Vec::new(),
+            partial_files: Mutex::new(HashSet::new()),
         })
     }
 
@@ -196,19 +340,329 @@ where
             config,
             local,
             remote,
+            mirrors: Vec::new(),
+            partial_files: Mutex::new(HashSet::new()),
         })
     }
 
     /// Update TUF metadata from the remote repository.
     ///
+    /// If the root metadata defines a mirrors role, the mirrors metadata is updated right after
+    /// the root metadata, and the rest of the metadata and targets are fetched from the mirrors it
+    /// lists whenever fetching them from the remote repository fails. Failing to update the
+    /// mirrors metadata does not fail the update, as the remote repository can still be used.
+    ///
     /// Returns `true` if an update occurred and `false` otherwise.
     pub async fn update(&mut self) -> Result<bool> {
-        let r = await!(self.update_root())?;
-        let ts = await!(self.update_timestamp())?;
-        let sn = await!(self.update_snapshot())?;
-        let ta = await!(self.update_targets())?;
+        self.notify(&Event::MetadataFetchStarted { role: &Role::Root });
+        let result = await!(self.update_root());
+        let r = self.metadata_fetched(&Role::Root, result)?;
+
+        let mi = if self.tuf.root().mirrors().is_some() {
+            self.notify(&Event::MetadataFetchStarted {
+                role: &Role::Mirrors,
+            });
+            let result = await!(self.update_mirrors());
+            match self.metadata_fetched(&Role::Mirrors, result) {
+                Ok(mi) => mi,
+                Err(err) => {
+                    warn!("Failed to update the mirrors metadata: {}", err);
+                    false
+                }
+            }
+        } else {
+            false
+        };
+        self.update_mirror_repositories();
+
+        self.notify(&Event::MetadataFetchStarted {
+            role: &Role::Timestamp,
+        });
+        let result = await!(self.update_timestamp());
+        let ts = self.metadata_fetched(&Role::Timestamp, result)?;
+
+        self.notify(&Event::MetadataFetchStarted {
+            role: &Role::Snapshot,
+        });
+        let result = await!(self.update_snapshot());
+        let sn = self.metadata_fetched(&Role::Snapshot, result)?;
+
+        self.notify(&Event::MetadataFetchStarted {
+            role: &Role::Targets,
+        });
+        let result = await!(self.update_targets());
+        let ta = self.metadata_fetched(&Role::Targets, result)?;
+
+        Ok(r || ts || sn || ta || mi)
+    }
+
+    /// Tell the `Observer`, if there is one, about `event`.
+    fn notify(&self, event: &Event) {
+        if let Some(ref observer) = self.config.observer {
+            observer.0.notify(event);
+        }
+    }
+
+    /// Report how updating the metadata for `role` went, and pass the result on.
+    fn metadata_fetched(&self, role: &Role, result: Result<bool>) -> Result<bool> {
+        match result {
+            Ok(updated) => self.notify(&Event::MetadataFetchFinished { role, updated }),
+            Err(ref error) => {
+                if is_verification_failure(error) {
+                    self.notify(&Event::VerificationFailed {
+                        role,
+                        target: None,
+                        error,
+                    });
+                }
+                self.notify(&Event::MetadataFetchFailed { role, error });
+            }
+        }
+        result
+    }
+
+    /// Report how fetching `target` went, and pass the result on.
+    fn target_fetched(&self, target: &TargetPath, result: Result<()>) -> Result<()> {
+        match result {
+            Ok(()) => self.notify(&Event::TargetFetchFinished { target }),
+            Err(ref error) => self.notify(&Event::TargetFetchFailed { target, error }),
+        }
+        result
+    }
+
+    /// The mirrors listed in the trusted mirrors metadata, in the order they should be tried. This
+    /// is empty if the root metadata does not define a mirrors role.
+    pub fn mirrors(&self) -> &[Mirror] {
+        match self.tuf.mirrors() {
+            Some(mirrors) => mirrors.mirrors(),
+            None => &[],
+        }
+    }
+
+    /// Set up a repository for each trusted mirror that the remote repository can reach. There are
+    /// none if the mirrors metadata has expired.
+    fn update_mirror_repositories(&mut self) {
+        let unexpired = self
+            .tuf
+            .mirrors()
+            .map_or(false, |mirrors| mirrors.expires() > &Utc::now());
+        if !unexpired {
+            self.mirrors.clear();
+            return;
+        }
+
+        let remote = &self.remote;
+        self.mirrors = self
+            .mirrors()
+            .iter()
+            .filter_map(|mirror| remote.mirror(mirror).map(|repo| (mirror.clone(), repo)))
+            .collect();
+    }
+
+    /// Tell the remote repository and the mirrors whether the metadata they last returned from
+    /// `fetch_metadata_if_changed` for `path` and `version` is now trusted.
+    fn metadata_verified(&self, path: &MetadataPath, version: &MetadataVersion, verified: bool) {
+        self.remote.metadata_verified(path, version, verified);
+        for (_, repo) in self.mirrors.iter() {
+            repo.metadata_verified(path, version, verified);
+        }
+    }
+
+    /// Fetch metadata from the remote repository, or from the first mirror that serves it if that
+    /// fails.
+    async fn fetch_remote_metadata<'a, M>(
+        &'a self,
+        path: &'a MetadataPath,
+        version: &'a MetadataVersion,
+        max_size: &'a Option<usize>,
+        hashes: Option<&'a HashMap<HashAlgorithm, HashValue>>,
+    ) -> Result<SignedMetadata<D, M>>
+    where
+        M: Metadata + 'static,
+    {
+        let mut result = await!(self.limit_metadata_fetch(
+            max_size,
+            self.remote.fetch_metadata(path, version, max_size, hashes),
+        ));
+        for (mirror, repo) in self.mirrors.iter() {
+            let err = match result {
+                Ok(metadata) => return Ok(metadata),
+                Err(err) => err,
+            };
+            if !mirror.serves_metadata::<D>(path) {
+                result = Err(err);
+                continue;
+            }
+            warn!(
+                "Failed to fetch {}, trying mirror {}: {}",
+                path.to_string(),
+                mirror.url_base(),
+                err
+            );
+            self.notify(&Event::MirrorFailover {
+                metadata: Some(path),
+                target: None,
+                mirror: mirror.url_base(),
+                error: &err,
+            });
+            result = await!(self.limit_metadata_fetch(
+                max_size,
+                repo.fetch_metadata(path, version, max_size, hashes),
+            ));
+        }
+        result
+    }
+
+    /// Like `fetch_remote_metadata`, but with `Repository::fetch_metadata_if_changed`.
+    async fn fetch_remote_metadata_if_changed<'a, M>(
+        &'a self,
+        path: &'a MetadataPath,
+        version: &'a MetadataVersion,
+        max_size: &'a Option<usize>,
+        hashes: Option<&'a HashMap<HashAlgorithm, HashValue>>,
+    ) -> Result<Option<SignedMetadata<D, M>>>
+    where
+        M: Metadata + 'static,
+    {
+        let mut result = await!(self.limit_metadata_fetch(
+            max_size,
+            self.remote
+                .fetch_metadata_if_changed(path, version, max_size, hashes),
+        ));
+        for (mirror, repo) in self.mirrors.iter() {
+            let err = match result {
+                Ok(metadata) => return Ok(metadata),
+                Err(err) => err,
+            };
+            if !mirror.serves_metadata::<D>(path) {
+                result = Err(err);
+                continue;
+            }
+            warn!(
+                "Failed to fetch {}, trying mirror {}: {}",
+                path.to_string(),
+                mirror.url_base(),
+                err
+            );
+            self.notify(&Event::MirrorFailover {
+                metadata: Some(path),
+                target: None,
+                mirror: mirror.url_base(),
+                error: &err,
+            });
+            result = await!(self.limit_metadata_fetch(
+                max_size,
+                repo.fetch_metadata_if_changed(path, version, max_size, hashes),
+            ));
+        }
+        result
+    }
+
+    /// Fail `fetch` with `Error::Timeout` if fetching metadata of at most `max_size` bytes takes
+    /// longer than the configured `SlowRetrievalPolicy` allows. Repositories parse metadata before
+    /// returning it, so rather than its rate, this checks that it takes no longer than the grace
+    /// period and `max_size` bytes at the minimum rate.
+    async fn limit_metadata_fetch<'a, M>(
+        &'a self,
+        max_size: &'a Option<usize>,
+        fetch: TufFuture<'a, Result<M>>,
+    ) -> Result<M>
+    where
+        M: 'a,
+    {
+        let time_limit = self
+            .config
+            .slow_retrieval_policy
+            .as_ref()
+            .and_then(|policy| policy.time_limit(max_size.map(|size| size as u64)));
+        match time_limit {
+            Some(time_limit) => match await!(util::timeout(fetch, time_limit)) {
+                Some(result) => result,
+                None => Err(Error::Timeout),
+            },
+            None => await!(fetch),
+        }
+    }
+
+    /// Fetch a target from the remote repository, or from the first mirror that serves it if that
+    /// fails.
+    async fn fetch_remote_target<'a>(
+        &'a self,
+        target: &'a TargetPath,
+        target_description: &'a TargetDescription,
+    ) -> Result<Box<dyn AsyncRead>> {
+        let mut result = await!(self.remote.fetch_target(target, target_description));
+        for (mirror, repo) in self.mirrors.iter() {
+            let err = match result {
+                Ok(read) => return Ok(read),
+                Err(err) => err,
+            };
+            if !mirror.serves_target(target) {
+                result = Err(err);
+                continue;
+            }
+            warn!(
+                "Failed to fetch target {}, trying mirror {}: {}",
+                target.value(),
+                mirror.url_base(),
+                err
+            );
+            self.notify(&Event::MirrorFailover {
+                metadata: None,
+                target: Some(target),
+                mirror: mirror.url_base(),
+                error: &err,
+            });
+            result = await!(repo.fetch_target(target, target_description));
+        }
+        result
+    }
 
-        Ok(r || ts || sn || ta)
+    /// Like `fetch_remote_target`, but resuming from the first `partial_len` bytes of the target
+    /// that are in the file at `partial_path`.
+    async fn fetch_remote_target_resume<'a>(
+        &'a self,
+        target: &'a TargetPath,
+        target_description: &'a TargetDescription,
+        partial_path: &'a Path,
+        partial_len: u64,
+        replayed: &'a Arc<AtomicUsize>,
+    ) -> Result<Box<dyn AsyncRead>> {
+        let mut result = await!(self.remote.fetch_target_resume(
+            target,
+            target_description,
+            CountingReader::new(AllowStdIo::new(File::open(partial_path)?), replayed.clone()),
+            partial_len,
+        ));
+        for (mirror, repo) in self.mirrors.iter() {
+            let err = match result {
+                Ok(read) => return Ok(read),
+                Err(err) => err,
+            };
+            if !mirror.serves_target(target) {
+                result = Err(err);
+                continue;
+            }
+            warn!(
+                "Failed to fetch target {}, trying mirror {}: {}",
+                target.value(),
+                mirror.url_base(),
+                err
+            );
+            self.notify(&Event::MirrorFailover {
+                metadata: None,
+                target: Some(target),
+                mirror: mirror.url_base(),
+                error: &err,
+            });
+            result = await!(repo.fetch_target_resume(
+                target,
+                target_description,
+                CountingReader::new(AllowStdIo::new(File::open(partial_path)?), replayed.clone()),
+                partial_len,
+            ));
+        }
+        result
     }
 
     /// Store the metadata in the local repository. This is juts a local cache, so we ignore if it
@@ -239,12 +693,28 @@ where
     async fn update_root(&mut self) -> Result<bool> {
         let root_path = MetadataPath::from_role(&Role::Root);
 
-        let latest_root = await!(self.remote.fetch_metadata(
+        let latest_root = match await!(self.fetch_remote_metadata_if_changed(
             &root_path,
             &MetadataVersion::None,
             &self.config.max_root_size,
             None,
-        ))?;
+        ))? {
+            Some(latest_root) => latest_root,
+            None => return Ok(false),
+        };
+
+        let result = await!(self.update_root_to(latest_root.clone()));
+        let verified = result.is_ok() && self.tuf.signed_root() == &latest_root;
+        self.metadata_verified(&root_path, &MetadataVersion::None, verified);
+        result
+    }
+
+    /// Update the root metadata to `latest_root`, walking through every version in between.
+    async fn update_root_to(
+        &mut self,
+        latest_root: SignedMetadata<D, RootMetadata>,
+    ) -> Result<bool> {
+        let root_path = MetadataPath::from_role(&Role::Root);
         let latest_version = latest_root.version();
 
         if latest_version < self.tuf.root().version() {
@@ -263,7 +733,7 @@ where
         for i in (self.tuf.root().version() + 1)..latest_version {
             let version = MetadataVersion::Number(i);
 
-            let signed_root = await!(self.remote.fetch_metadata(
+            let signed_root = await!(self.fetch_remote_metadata(
                 &root_path,
                 &version,
                 &self.config.max_root_size,
@@ -274,6 +744,10 @@ where
                 error!("{}", err_msg);
                 return Err(Error::Programming(err_msg.into()));
             }
+            self.notify(&Event::RootRotated {
+                from_version: i - 1,
+                to_version: i,
+            });
 
             await!(self.store_metadata(&root_path, &version, &signed_root));
         }
@@ -282,6 +756,10 @@ where
             error!("{}", err_msg);
             return Err(Error::Programming(err_msg.into()));
         }
+        self.notify(&Event::RootRotated {
+            from_version: latest_version - 1,
+            to_version: latest_version,
+        });
 
         let latest_version = MetadataVersion::Number(latest_version);
 
@@ -300,14 +778,37 @@ where
     async fn update_timestamp(&mut self) -> Result<bool> {
         let timestamp_path = MetadataPath::from_role(&Role::Timestamp);
 
-        let signed_timestamp = await!(self.remote.fetch_metadata(
-            &timestamp_path,
-            &MetadataVersion::None,
-            &self.config.max_timestamp_size,
-            None,
-        ))?;
+        let signed_timestamp = if let Some(timestamp) = self.tuf.timestamp() {
+            let expires = timestamp.expires().clone();
+            match await!(self.fetch_remote_metadata_if_changed(
+                &timestamp_path,
+                &MetadataVersion::None,
+                &self.config.max_timestamp_size,
+                None,
+            ))? {
+                Some(signed_timestamp) => signed_timestamp,
+                None => {
+                    // The trusted timestamp is still the latest one, but it may have expired.
+                    if expires <= Utc::now() {
+                        return Err(Error::ExpiredMetadata(Role::Timestamp));
+                    }
+                    return Ok(false);
+                }
+            }
+        } else {
+            await!(self.fetch_remote_metadata(
+                &timestamp_path,
+                &MetadataVersion::None,
+                &self.config.max_timestamp_size,
+                None,
+            ))?
+        };
+
+        let result = self.tuf.update_timestamp(signed_timestamp.clone());
+        let verified = result.is_ok() && self.tuf.signed_timestamp() == Some(&signed_timestamp);
+        self.metadata_verified(&timestamp_path, &MetadataVersion::None, verified);
 
-        if self.tuf.update_timestamp(signed_timestamp.clone())? {
+        if result? {
             let latest_version = signed_timestamp.version();
             let latest_version = MetadataVersion::Number(latest_version);
 
@@ -334,7 +835,7 @@ where
             return Ok(false);
         }
 
-        let (alg, value) = crypto::hash_preference(snapshot_description.hashes())?;
+        let hashes = self.hashes_to_verify(snapshot_description.hashes())?;
 
         let version = if self.tuf.root().consistent_snapshot() {
             MetadataVersion::Number(snapshot_description.version())
@@ -345,11 +846,11 @@ where
         let snapshot_path = MetadataPath::from_role(&Role::Snapshot);
         let snapshot_size = Some(snapshot_description.size());
 
-        let signed_snapshot = await!(self.remote.fetch_metadata(
+        let signed_snapshot = await!(self.fetch_remote_metadata(
             &snapshot_path,
             &version,
             &snapshot_size,
-            Some((alg, value.clone())),
+            Some(&hashes),
         ))?;
 
         if self.tuf.update_snapshot(signed_snapshot.clone())? {
@@ -380,7 +881,11 @@ where
             return Ok(false);
         }
 
-        let (alg, value) = crypto::hash_preference(targets_description.hashes())?;
+        let (_, value) = crypto::hash_preference_with(
+            targets_description.hashes(),
+            &self.config.hash_algorithms,
+        )?;
+        let hashes = self.hashes_to_verify(targets_description.hashes())?;
 
         let version = if self.tuf.root().consistent_snapshot() {
             MetadataVersion::Hash(value.clone())
@@ -391,11 +896,11 @@ where
         let targets_path = MetadataPath::from_role(&Role::Targets);
         let targets_size = Some(targets_description.size());
 
-        let signed_targets = await!(self.remote.fetch_metadata(
+        let signed_targets = await!(self.fetch_remote_metadata(
             &targets_path,
             &version,
             &targets_size,
-            Some((alg, value.clone())),
+            Some(&hashes),
         ))?;
 
         if self.tuf.update_targets(signed_targets.clone())? {
@@ -407,25 +912,271 @@ where
         }
     }
 
+    /// Returns `true` if an update occurred and `false` otherwise. The mirrors metadata is only
+    /// fetched if the root metadata defines a mirrors role.
+    async fn update_mirrors(&mut self) -> Result<bool> {
+        if self.tuf.root().mirrors().is_none() {
+            return Ok(false);
+        }
+
+        let mirrors_path = MetadataPath::from_role(&Role::Mirrors);
+
+        let signed_mirrors = await!(self.fetch_remote_metadata(
+            &mirrors_path,
+            &MetadataVersion::None,
+            &self.config.max_mirrors_size,
+            None,
+        ))?;
+
+        if self.tuf.update_mirrors(signed_mirrors.clone())? {
+            await!(self.store_metadata(&mirrors_path, &MetadataVersion::None, &signed_mirrors));
+
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
     /// Fetch a target from the remote repo and write it to the local repo.
+    ///
+    /// If the config has a `partial_download_dir`, an interrupted download is resumed from where
+    /// it left off the next time this is called.
     pub async fn fetch_target<'a>(&'a mut self, target: &'a TargetPath) -> Result<()> {
+        let result = await!(self.fetch_target_to_local(target));
+        self.target_fetched(target, result)
+    }
+
+    async fn fetch_target_to_local<'a>(&'a mut self, target: &'a TargetPath) -> Result<()> {
+        if let Some(dir) = self.config.partial_download_dir.clone() {
+            let target_description = await!(self.target_description(target))?;
+            if let Some(claim) = self.claim_partial_file(&dir, &target_description)? {
+                return await!(self.fetch_target_resumable(
+                    target,
+                    &target_description,
+                    &claim.path,
+                    None,
+                ));
+            }
+        }
+
         let read = await!(self._fetch_target(target))?;
         await!(self.local.store_target(read, target))
     }
 
+    /// Fetch several targets from the remote repo and write them to the local repo.
+    ///
+    /// The descriptions of all the targets are looked up first, and then up to
+    /// `max_concurrent_downloads` of them are downloaded at once, sharing the
+    /// `max_download_bytes_per_second` limit. Each target is only written to the local repo once
+    /// all of it has been downloaded and verified. The results are in the same order as `targets`,
+    /// and a target that fails does not stop the others.
+    pub async fn fetch_targets<'a>(&'a mut self, targets: &'a [TargetPath]) -> Vec<Result<()>> {
+        let mut target_descriptions = Vec::with_capacity(targets.len());
+        for target in targets {
+            target_descriptions.push(await!(self.target_description(target)));
+        }
+
+        let this = &*self;
+        let limiter = self
+            .config
+            .max_download_bytes_per_second
+            .map(BandwidthLimiter::new);
+        let limiter = limiter.as_ref();
+
+        let downloads = targets.iter().zip(target_descriptions).enumerate().map(
+            |(i, (target, target_description))| {
+                async move {
+                    let result = match target_description {
+                        Ok(target_description) => {
+                            await!(this.download_target(target, &target_description, limiter))
+                        }
+                        Err(err) => Err(err),
+                    };
+                    (i, this.target_fetched(target, result))
+                }
+            },
+        );
+
+        let mut results = await!(stream::iter(downloads)
+            .buffer_unordered(self.config.max_concurrent_downloads)
+            .collect::<Vec<_>>());
+        results.sort_by_key(|(i, _)| *i);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+
+    /// Download a target and write it to the local repo once it has been verified.
+    async fn download_target<'a>(
+        &'a self,
+        target: &'a TargetPath,
+        target_description: &'a TargetDescription,
+        limiter: Option<&'a BandwidthLimiter>,
+    ) -> Result<()> {
+        if let Some(ref dir) = self.config.partial_download_dir {
+            // Targets with the same contents share a partial file, so while one of them is being
+            // downloaded into it, the others are downloaded without one.
+            if let Some(claim) = self.claim_partial_file(dir, target_description)? {
+                return await!(self.fetch_target_resumable(
+                    target,
+                    target_description,
+                    &claim.path,
+                    limiter,
+                ));
+            }
+        }
+
+        let read = await!(self.fetch_remote_target(target, target_description))?;
+        let read = self.monitor_download(read, target, target_description)?;
+        let mut read = LimitedReader::new(read, limiter, None);
+
+        let mut temp_file = tempfile::tempfile()?;
+        {
+            let mut write = AllowStdIo::new(&mut temp_file);
+            let _ = await!(read.copy_into(&mut write))?;
+        }
+        drop(read);
+        let _ = temp_file.seek(SeekFrom::Start(0))?;
+
+        await!(self.local.store_target(AllowStdIo::new(temp_file), target))
+    }
+
     /// Fetch a target from the remote repo and write it to the provided writer.
     pub async fn fetch_target_to_writer<'a, W: AsyncWrite + 'a>(
         &'a mut self,
         target: &'a TargetPath,
         mut write: W,
     ) -> Result<()> {
-        let mut read = await!(self._fetch_target(&target))?;
-        await!(read.copy_into(&mut write))?;
-        Ok(())
+        let result = match await!(self._fetch_target(&target)) {
+            Ok(mut read) => await!(read.copy_into(&mut write))
+                .map(|_| ())
+                .map_err(Error::from),
+            Err(err) => Err(err),
+        };
+        self.target_fetched(target, result)
     }
 
     // TODO this should check the local repo first
     async fn _fetch_target<'a>(&'a mut self, target: &'a TargetPath) -> Result<Box<dyn AsyncRead>> {
+        let target_description = await!(self.target_description(target))?;
+        let read = await!(self.fetch_remote_target(target, &target_description))?;
+        self.monitor_download(read, target, &target_description)
+    }
+
+    /// Hold a target download to the configured `SlowRetrievalPolicy`, if there is one, and report
+    /// its progress to the `Observer`, if there is one. The remote repository already checks the
+    /// hashes in `target_description`, so they are not checked again here.
+    fn monitor_download(
+        &self,
+        read: Box<dyn AsyncRead>,
+        target: &TargetPath,
+        target_description: &TargetDescription,
+    ) -> Result<Box<dyn AsyncRead>> {
+        let read: Box<dyn AsyncRead> = match self.config.slow_retrieval_policy.clone() {
+            Some(policy) => Box::new(SafeReader::new(
+                read,
+                target_description.size(),
+                policy,
+                None,
+            )?),
+            None => read,
+        };
+
+        match self.config.observer {
+            Some(ref observer) => {
+                observer.0.notify(&Event::TargetFetchStarted {
+                    target,
+                    total: target_description.size(),
+                });
+                Ok(Box::new(ProgressReader {
+                    inner: read,
+                    observer: observer.0.clone(),
+                    target: target.clone(),
+                    received: 0,
+                    total: target_description.size(),
+                }))
+            }
+            None => Ok(read),
+        }
+    }
+
+    /// Claim the file in `dir` that a download of `target_description` is resumed from, or return
+    /// `None` if another download of the same contents is using it.
+    fn claim_partial_file<'a>(
+        &'a self,
+        dir: &Path,
+        target_description: &TargetDescription,
+    ) -> Result<Option<PartialFileClaim<'a>>> {
+        let (_, hash) = crypto::hash_preference_with(
+            target_description.hashes(),
+            &self.config.hash_algorithms,
+        )?;
+        let path = dir.join(hash.to_string());
+
+        if !self.partial_files.lock().unwrap().insert(path.clone()) {
+            return Ok(None);
+        }
+        Ok(Some(PartialFileClaim {
+            partial_files: &self.partial_files,
+            path,
+        }))
+    }
+
+    /// Download a target into the file at `partial_path`, continuing from whatever an earlier
+    /// attempt left there, and only store it in the local repo once all of it has been verified.
+    async fn fetch_target_resumable<'a>(
+        &'a self,
+        target: &'a TargetPath,
+        target_description: &'a TargetDescription,
+        partial_path: &'a Path,
+        limiter: Option<&'a BandwidthLimiter>,
+    ) -> Result<()> {
+        if let Some(dir) = partial_path.parent() {
+            DirBuilder::new().recursive(true).create(dir)?;
+        }
+        let partial = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .open(partial_path)?;
+        let partial_len = partial.metadata()?.len();
+
+        // The bytes that are read back from the partial file were already downloaded, so they do
+        // not count towards the bandwidth limit.
+        let replayed = Arc::new(AtomicUsize::new(0));
+        let read = await!(self.fetch_remote_target_resume(
+            target,
+            target_description,
+            partial_path,
+            partial_len,
+            &replayed,
+        ))?;
+        let read = self.monitor_download(read, target, target_description)?;
+
+        // The reader yields the whole target, including the bytes already in the partial file, and
+        // it is all written back as it is read so that the file only ever holds bytes the reader
+        // produced. The local repo is given the same bytes, rather than reading them back from the
+        // file, so it only ever sees bytes that were verified.
+        let corrupt = Arc::new(AtomicBool::new(false));
+        let read = PartialFileWriter {
+            inner: LimitedReader::new(read, limiter, Some(replayed)),
+            partial,
+            corrupt: corrupt.clone(),
+        };
+        if let Err(err) = await!(self.local.store_target(read, target)) {
+            // The data is wrong, not just incomplete, so it must not be resumed from.
+            if corrupt.load(Ordering::SeqCst) {
+                fs::remove_file(partial_path)?;
+            }
+            return Err(err);
+        }
+
+        fs::remove_file(partial_path)?;
+        Ok(())
+    }
+
+    /// Look up the description of a target, limited to the hashes that are to be verified.
+    async fn target_description<'a>(
+        &'a mut self,
+        target: &'a TargetPath,
+    ) -> Result<TargetDescription> {
         let virt = self.config.path_translator.real_to_virtual(target)?;
 
         let snapshot = self
@@ -437,7 +1188,25 @@ where
             await!(self.lookup_target_description(false, 0, &virt, &snapshot, None));
         let target_description = target_description?;
 
-        await!(self.remote.fetch_target(target, &target_description))
+        let hashes = self.hashes_to_verify(target_description.hashes())?;
+        TargetDescription::new(target_description.size(), hashes)
+    }
+
+    /// The subset of `hashes` that fetched data must match. In strict mode that is every listed
+    /// hash, otherwise only the most preferred one.
+    fn hashes_to_verify(
+        &self,
+        hashes: &HashMap<HashAlgorithm, HashValue>,
+    ) -> Result<HashMap<HashAlgorithm, HashValue>> {
+        let (alg, value) = crypto::hash_preference_with(hashes, &self.config.hash_algorithms)?;
+
+        if self.config.strict_hashes {
+            return Ok(hashes.clone());
+        }
+
+        let mut preferred = HashMap::new();
+        let _ = preferred.insert(alg.clone(), value.clone());
+        Ok(preferred)
     }
 
     async fn lookup_target_description<'a>(
@@ -495,7 +1264,14 @@ where
                 None => return (true, Err(Error::NotFound)),
             };
 
-            let (alg, value) = match crypto::hash_preference(role_meta.hashes()) {
+            let (_, value) = match crypto::hash_preference_with(
+                role_meta.hashes(),
+                &self.config.hash_algorithms,
+            ) {
+                Ok(h) => h,
+                Err(e) => return (delegation.terminating(), Err(e)),
+            };
+            let hashes = match self.hashes_to_verify(role_meta.hashes()) {
                 Ok(h) => h,
                 Err(e) => return (delegation.terminating(), Err(e)),
             };
@@ -511,17 +1287,17 @@ where
                 delegation.role(),
                 &MetadataVersion::None,
                 &role_size,
-                Some((alg, value.clone())),
+                Some(&hashes),
             ));
 
             let signed_meta = match signed_meta {
                 Ok(signed_meta) => signed_meta,
                 Err(_) => {
-                    match await!(self.remote.fetch_metadata::<TargetsMetadata>(
+                    match await!(self.fetch_remote_metadata::<TargetsMetadata>(
                         delegation.role(),
                         &version,
                         &role_size,
-                        Some((alg, value.clone())),
+                        Some(&hashes),
                     )) {
                         Ok(m) => m,
                         Err(ref e) if !delegation.terminating() => {
@@ -584,6 +1360,215 @@ where
     }
 }
 
+/// Whether `error` means that metadata was rejected because it could not be verified.
+fn is_verification_failure(error: &Error) -> bool {
+    match error {
+        Error::BadSignature | Error::ExpiredMetadata(_) | Error::VerificationFailure(_) => true,
+        _ => false,
+    }
+}
+
+/// Reports the progress of a target download to an `Observer` as it is read.
+struct ProgressReader {
+    inner: Box<dyn AsyncRead>,
+    observer: Arc<dyn Observer>,
+    target: TargetPath,
+    received: u64,
+    total: u64,
+}
+
+impl AsyncRead for ProgressReader {
+    fn poll_read(&mut self, lw: &LocalWaker, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        let result = self.inner.poll_read(lw, buf);
+        match result {
+            Poll::Ready(Ok(read_bytes)) if read_bytes > 0 => {
+                self.received += read_bytes as u64;
+                self.observer.notify(&Event::TargetBytesReceived {
+                    target: &self.target,
+                    received: self.received,
+                    total: self.total,
+                });
+            }
+            Poll::Ready(Err(ref err)) => match ReadAborted::of(err) {
+                Some(ReadAborted::TooLarge) => {
+                    self.observer.notify(&Event::TargetTooLarge {
+                        target: &self.target,
+                        total: self.total,
+                    });
+                }
+                Some(ReadAborted::HashMismatch) => {
+                    let error = Error::VerificationFailure(err.to_string());
+                    self.observer.notify(&Event::VerificationFailed {
+                        role: &Role::Targets,
+                        target: Some(&self.target),
+                        error: &error,
+                    });
+                }
+                Some(ReadAborted::TooSlow) | Some(ReadAborted::TookTooLong) => {
+                    self.observer.notify(&Event::SlowRetrieval {
+                        target: &self.target,
+                    });
+                }
+                None => (),
+            },
+            _ => (),
+        }
+        result
+    }
+}
+
+/// Shares a bandwidth limit between concurrent downloads by giving each chunk of data that is
+/// read a slot of time to be read in, after the slots already handed out.
+struct BandwidthLimiter {
+    bytes_per_second: u64,
+    next_slot: Mutex<Instant>,
+}
+
+impl BandwidthLimiter {
+    fn new(bytes_per_second: u64) -> Self {
+        BandwidthLimiter {
+            bytes_per_second,
+            next_slot: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Reserve a slot for `bytes` more bytes, and return how long to wait before reading them to
+    /// stay within the limit.
+    fn reserve(&self, bytes: usize) -> Duration {
+        let nanos = (bytes as u64).saturating_mul(1_000_000_000) / self.bytes_per_second.max(1);
+        let now = Instant::now();
+        let slot = {
+            let mut next_slot = self.next_slot.lock().unwrap();
+            let slot = if *next_slot > now { *next_slot } else { now };
+            *next_slot = slot + Duration::from_nanos(nanos);
+            slot
+        };
+
+        if slot > now {
+            slot - now
+        } else {
+            Duration::from_secs(0)
+        }
+    }
+}
+
+/// Keeps the reads of `inner` to a `BandwidthLimiter`, if there is one, by waiting after each read
+/// for as long as the limit requires. Bytes counted by `replayed` since the last read were not
+/// downloaded, and are left out.
+struct LimitedReader<'a, R> {
+    inner: R,
+    limiter: Option<&'a BandwidthLimiter>,
+    replayed: Option<Arc<AtomicUsize>>,
+    wait: Option<Box<dyn Future<Output = ()> + Unpin>>,
+}
+
+impl<'a, R> LimitedReader<'a, R> {
+    fn new(
+        inner: R,
+        limiter: Option<&'a BandwidthLimiter>,
+        replayed: Option<Arc<AtomicUsize>>,
+    ) -> Self {
+        LimitedReader {
+            inner,
+            limiter,
+            replayed,
+            wait: None,
+        }
+    }
+}
+
+impl<'a, R> AsyncRead for LimitedReader<'a, R>
+where
+    R: AsyncRead,
+{
+    fn poll_read(&mut self, lw: &LocalWaker, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        if let Some(ref mut wait) = self.wait {
+            if let Poll::Pending = Pin::new(&mut **wait).poll(lw) {
+                return Poll::Pending;
+            }
+        }
+        self.wait = None;
+
+        let read_bytes = try_ready!(self.inner.poll_read(lw, buf));
+        if let Some(limiter) = self.limiter {
+            let replayed = match self.replayed {
+                Some(ref replayed) => replayed.swap(0, Ordering::SeqCst),
+                None => 0,
+            };
+            let wait = limiter.reserve(read_bytes.saturating_sub(replayed));
+            if wait > Duration::from_secs(0) {
+                self.wait = Some(Box::new(util::sleep(wait)));
+            }
+        }
+        Poll::Ready(Ok(read_bytes))
+    }
+}
+
+/// Counts the bytes read from `inner`.
+struct CountingReader<R> {
+    inner: R,
+    count: Arc<AtomicUsize>,
+}
+
+impl<R> CountingReader<R> {
+    fn new(inner: R, count: Arc<AtomicUsize>) -> Self {
+        CountingReader { inner, count }
+    }
+}
+
+impl<R> AsyncRead for CountingReader<R>
+where
+    R: AsyncRead,
+{
+    fn poll_read(&mut self, lw: &LocalWaker, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        let read_bytes = try_ready!(self.inner.poll_read(lw, buf));
+        let _ = self.count.fetch_add(read_bytes, Ordering::SeqCst);
+        Poll::Ready(Ok(read_bytes))
+    }
+}
+
+/// The use of a partial download file by a single download, which ends when this is dropped.
+struct PartialFileClaim<'a> {
+    partial_files: &'a Mutex<HashSet<PathBuf>>,
+    path: PathBuf,
+}
+
+impl<'a> Drop for PartialFileClaim<'a> {
+    fn drop(&mut self) {
+        let _ = self.partial_files.lock().unwrap().remove(&self.path);
+    }
+}
+
+/// Writes everything that is read through it to a partial download, so that a later attempt can
+/// resume from it. `corrupt` is set if the data turns out to be wrong.
+struct PartialFileWriter<R> {
+    inner: R,
+    partial: File,
+    corrupt: Arc<AtomicBool>,
+}
+
+impl<R> AsyncRead for PartialFileWriter<R>
+where
+    R: AsyncRead,
+{
+    fn poll_read(&mut self, lw: &LocalWaker, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        match self.inner.poll_read(lw, buf) {
+            Poll::Ready(Ok(read_bytes)) => Poll::Ready(
+                self.partial
+                    .write_all(&buf[..read_bytes])
+                    .map(|()| read_bytes),
+            ),
+            Poll::Ready(Err(err)) => {
+                if err.kind() == io::ErrorKind::InvalidData {
+                    self.corrupt.store(true, Ordering::SeqCst);
+                }
+                Poll::Ready(Err(err))
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
 /// Configuration for a TUF `Client`.
 ///
 /// # Defaults
@@ -594,10 +1579,23 @@ where
 ///
 /// ```
 /// # use tuf::client::{Config, DefaultTranslator};
+/// # use tuf::crypto::HashAlgorithm;
 /// let config = Config::default();
 /// assert_eq!(config.max_root_size(), &Some(1024 * 1024));
 /// assert_eq!(config.max_timestamp_size(), &Some(32 * 1024));
 /// assert_eq!(config.max_delegation_depth(), 8);
+/// assert!(!config.strict_hashes());
+/// assert_eq!(config.slow_retrieval_policy(), None);
+/// assert_eq!(config.max_concurrent_downloads(), 4);
+/// assert_eq!(config.max_download_bytes_per_second(), None);
+/// assert!(config.observer().is_none());
+/// assert_eq!(config.hash_algorithms(), &[
+///     HashAlgorithm::Sha512,
+///     HashAlgorithm::Sha384,
+///     HashAlgorithm::Sha512Trunc256,
+///     HashAlgorithm::Sha256,
+///     HashAlgorithm::Blake2b,
+/// ]);
 /// let _: &DefaultTranslator = config.path_translator();
 /// ```
 #[derive(Debug)]
@@ -607,7 +1605,15 @@ where
 {
     max_root_size: Option<usize>,
     max_timestamp_size: Option<usize>,
+    max_mirrors_size: Option<usize>,
     max_delegation_depth: u32,
+    hash_algorithms: Vec<HashAlgorithm>,
+    strict_hashes: bool,
+    partial_download_dir: Option<PathBuf>,
+    slow_retrieval_policy: Option<SlowRetrievalPolicy>,
+    max_concurrent_downloads: usize,
+    max_download_bytes_per_second: Option<u64>,
+    observer: Option<ObserverHandle>,
     path_translator: T,
 }
 
@@ -632,11 +1638,53 @@ where
         &self.max_timestamp_size
     }
 
+    /// Return the optional maximum mirrors metadata size.
+    pub fn max_mirrors_size(&self) -> &Option<usize> {
+        &self.max_mirrors_size
+    }
+
     /// The maximum number of steps used when walking the delegation graph.
     pub fn max_delegation_depth(&self) -> u32 {
         self.max_delegation_depth
     }
 
+    /// The hash algorithms used to verify metadata and targets, most preferred first.
+    pub fn hash_algorithms(&self) -> &[HashAlgorithm] {
+        &self.hash_algorithms
+    }
+
+    /// Whether every hash listed for metadata and targets is verified, instead of only the most
+    /// preferred one.
+    pub fn strict_hashes(&self) -> bool {
+        self.strict_hashes
+    }
+
+    /// The directory where targets are downloaded to before they are verified, if downloads are
+    /// resumable.
+    pub fn partial_download_dir(&self) -> Option<&Path> {
+        self.partial_download_dir.as_ref().map(|dir| dir.as_path())
+    }
+
+    /// The `SlowRetrievalPolicy` that downloads must follow, if any.
+    pub fn slow_retrieval_policy(&self) -> Option<&SlowRetrievalPolicy> {
+        self.slow_retrieval_policy.as_ref()
+    }
+
+    /// The maximum number of targets `Client::fetch_targets` downloads at once.
+    pub fn max_concurrent_downloads(&self) -> usize {
+        self.max_concurrent_downloads
+    }
+
+    /// The optional limit on the combined rate `Client::fetch_targets` downloads targets at.
+    pub fn max_download_bytes_per_second(&self) -> Option<u64> {
+        self.max_download_bytes_per_second
+    }
+
+    /// The `Observer` that is told what the client is doing, if any.
+    pub fn observer(&self) -> Option<&dyn Observer> {
+        self.observer.as_ref().map(|observer| &*observer.0)
+    }
+
     /// The `PathTranslator`.
     pub fn path_translator(&self) -> &T {
         &self.path_translator
@@ -648,7 +1696,15 @@ impl Default for Config<DefaultTranslator> {
         Config {
             max_root_size: Some(1024 * 1024),
             max_timestamp_size: Some(32 * 1024),
+            max_mirrors_size: Some(1024 * 1024),
             max_delegation_depth: 8,
+            hash_algorithms: crypto::HASH_ALG_PREFS.to_vec(),
+            strict_hashes: false,
+            partial_download_dir: None,
+            slow_retrieval_policy: None,
+            max_concurrent_downloads: 4,
+            max_download_bytes_per_second: None,
+            observer: None,
             path_translator: DefaultTranslator::new(),
         }
     }
@@ -662,7 +1718,15 @@ where
 {
     max_root_size: Option<usize>,
     max_timestamp_size: Option<usize>,
+    max_mirrors_size: Option<usize>,
     max_delegation_depth: u32,
+    hash_algorithms: Vec<HashAlgorithm>,
+    strict_hashes: bool,
+    partial_download_dir: Option<PathBuf>,
+    slow_retrieval_policy: Option<SlowRetrievalPolicy>,
+    max_concurrent_downloads: usize,
+    max_download_bytes_per_second: Option<u64>,
+    observer: Option<ObserverHandle>,
     path_translator: T,
 }
 
@@ -672,10 +1736,45 @@ where
 {
     /// Validate this builder return a `Config` if validation succeeds.
     pub fn finish(self) -> Result<Config<T>> {
+        if self.hash_algorithms.is_empty() {
+            return Err(Error::IllegalArgument(
+                "At least one hash algorithm must be configured".into(),
+            ));
+        }
+
+        if self.max_concurrent_downloads == 0 {
+            return Err(Error::IllegalArgument(
+                "At least one download must be allowed at a time".into(),
+            ));
+        }
+
+        if self.max_download_bytes_per_second == Some(0) {
+            return Err(Error::IllegalArgument(
+                "The download rate limit must be above 0".into(),
+            ));
+        }
+
+        for alg in self.hash_algorithms.iter() {
+            if let HashAlgorithm::Unknown(ref s) = alg {
+                return Err(Error::IllegalArgument(format!(
+                    "Unknown hash algorithm: {}",
+                    s
+                )));
+            }
+        }
+
         Ok(Config {
             max_root_size: self.max_root_size,
             max_timestamp_size: self.max_timestamp_size,
+            max_mirrors_size: self.max_mirrors_size,
             max_delegation_depth: self.max_delegation_depth,
+            hash_algorithms: self.hash_algorithms,
+            strict_hashes: self.strict_hashes,
+            partial_download_dir: self.partial_download_dir,
+            slow_retrieval_policy: self.slow_retrieval_policy,
+            max_concurrent_downloads: self.max_concurrent_downloads,
+            max_download_bytes_per_second: self.max_download_bytes_per_second,
+            observer: self.observer,
             path_translator: self.path_translator,
         })
     }
@@ -692,12 +1791,71 @@ where
         self
     }
 
+    /// Set the optional maximum download size for mirrors metadata.
+    pub fn max_mirrors_size(mut self, max: Option<usize>) -> Self {
+        self.max_mirrors_size = max;
+        self
+    }
+
     /// Set the maximum number of steps used when walking the delegation graph.
     pub fn max_delegation_depth(mut self, max: u32) -> Self {
         self.max_delegation_depth = max;
         self
     }
 
+    /// Set the hash algorithms the client will use, most preferred first. Metadata and targets
+    /// that are not described by any of these algorithms can not be fetched.
+    pub fn hash_algorithms(mut self, hash_algorithms: Vec<HashAlgorithm>) -> Self {
+        self.hash_algorithms = hash_algorithms;
+        self
+    }
+
+    /// Set whether every hash listed for metadata and targets must match, instead of only the
+    /// most preferred one. Hashes with unsupported algorithms are still ignored.
+    pub fn strict_hashes(mut self, strict_hashes: bool) -> Self {
+        self.strict_hashes = strict_hashes;
+        self
+    }
+
+    /// Set a directory to download targets to before they are verified, which makes downloads
+    /// resumable. Each target is kept in a file named after its hash until it has been fully
+    /// downloaded and verified, and only then is it stored in the local repository. While a
+    /// client is downloading into a file, other downloads of the same contents by that client are
+    /// not resumable. The directory should not be shared with other clients.
+    pub fn partial_download_dir(mut self, dir: PathBuf) -> Self {
+        self.partial_download_dir = Some(dir);
+        self
+    }
+
+    /// Set a `SlowRetrievalPolicy` that downloads must follow, whichever `Repository` they come
+    /// from. This is checked on top of any limits the remote repository itself enforces. Target
+    /// downloads are held to all of it, while metadata, which repositories read before returning
+    /// it, only has as long as reading its maximum size at the minimum rate would take.
+    pub fn slow_retrieval_policy(mut self, policy: SlowRetrievalPolicy) -> Self {
+        self.slow_retrieval_policy = Some(policy);
+        self
+    }
+
+    /// Set the maximum number of targets `Client::fetch_targets` downloads at once.
+    pub fn max_concurrent_downloads(mut self, max: usize) -> Self {
+        self.max_concurrent_downloads = max;
+        self
+    }
+
+    /// Set the optional limit on the combined rate `Client::fetch_targets` downloads targets at.
+    /// This slows down each download, so it should leave room for any minimum rate that the
+    /// repositories or the `SlowRetrievalPolicy` require.
+    pub fn max_download_bytes_per_second(mut self, max: Option<u64>) -> Self {
+        self.max_download_bytes_per_second = max;
+        self
+    }
+
+    /// Set an `Observer` to tell about metadata updates and target downloads as they happen.
+    pub fn observer(mut self, observer: Arc<dyn Observer>) -> Self {
+        self.observer = Some(ObserverHandle(observer));
+        self
+    }
+
     /// Set the `PathTranslator`.
     pub fn path_translator<TT>(self, path_translator: TT) -> ConfigBuilder<TT>
     where
@@ -706,7 +1864,15 @@ where
         ConfigBuilder {
             max_root_size: self.max_root_size,
             max_timestamp_size: self.max_timestamp_size,
+            max_mirrors_size: self.max_mirrors_size,
             max_delegation_depth: self.max_delegation_depth,
+            hash_algorithms: self.hash_algorithms,
+            strict_hashes: self.strict_hashes,
+            partial_download_dir: self.partial_download_dir,
+            slow_retrieval_policy: self.slow_retrieval_policy,
+            max_concurrent_downloads: self.max_concurrent_downloads,
+            max_download_bytes_per_second: self.max_download_bytes_per_second,
+            observer: self.observer,
             path_translator,
         }
     }
@@ -718,7 +1884,15 @@ impl Default for ConfigBuilder<DefaultTranslator> {
         ConfigBuilder {
             max_root_size: cfg.max_root_size,
             max_timestamp_size: cfg.max_timestamp_size,
+            max_mirrors_size: cfg.max_mirrors_size,
             max_delegation_depth: cfg.max_delegation_depth,
+            hash_algorithms: cfg.hash_algorithms,
+            strict_hashes: cfg.strict_hashes,
+            partial_download_dir: cfg.partial_download_dir,
+            slow_retrieval_policy: cfg.slow_retrieval_policy,
+            max_concurrent_downloads: cfg.max_concurrent_downloads,
+            max_download_bytes_per_second: cfg.max_download_bytes_per_second,
+            observer: cfg.observer,
             path_translator: cfg.path_translator,
         }
     }
@@ -727,16 +1901,20 @@ impl Default for ConfigBuilder<DefaultTranslator> {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::crypto::{HashAlgorithm, PrivateKey, SignatureScheme};
+    use crate::crypto::{PrivateKey, SignatureScheme};
     use crate::interchange::Json;
     use crate::metadata::{
-        MetadataPath, MetadataVersion, RootMetadata, RootMetadataBuilder, SnapshotMetadataBuilder,
-        TargetsMetadataBuilder, TimestampMetadataBuilder,
+        MetadataPath, MetadataVersion, MirrorsMetadataBuilder, RootMetadata, RootMetadataBuilder,
+        SnapshotMetadataBuilder, TargetsMetadataBuilder, TimestampMetadataBuilder,
     };
-    use crate::repository::EphemeralRepository;
+    use crate::repository::{EphemeralRepository, HttpRepositoryBuilder, HttpTransport};
     use chrono::prelude::*;
     use futures::executor::block_on;
+    use futures::future;
+    use http::{Request, Response, StatusCode};
     use lazy_static::lazy_static;
+    use std::io::Cursor;
+    use url::Url;
 
     lazy_static! {
         static ref KEYS: Vec<PrivateKey> = {
@@ -754,73 +1932,531 @@ mod test {
         };
     }
 
+    /// An `EphemeralRepository` whose mirrors are other `EphemeralRepository`s, looked up by their
+    /// URL.
+    struct MirroredRepository {
+        repo: Arc<EphemeralRepository<Json>>,
+        mirrors: Arc<HashMap<Url, Arc<EphemeralRepository<Json>>>>,
+    }
+
+    impl Repository<Json> for MirroredRepository {
+        fn store_metadata<'a, M>(
+            &'a self,
+            meta_path: &'a MetadataPath,
+            version: &'a MetadataVersion,
+            metadata: &'a SignedMetadata<Json, M>,
+        ) -> TufFuture<'a, Result<()>>
+        where
+            M: Metadata + 'static,
+        {
+            self.repo.store_metadata(meta_path, version, metadata)
+        }
+
+        fn fetch_metadata<'a, M>(
+            &'a self,
+            meta_path: &'a MetadataPath,
+            version: &'a MetadataVersion,
+            max_size: &'a Option<usize>,
+            hashes: Option<&'a HashMap<HashAlgorithm, HashValue>>,
+        ) -> TufFuture<'a, Result<SignedMetadata<Json, M>>>
+        where
+            M: Metadata + 'static,
+        {
+            self.repo
+                .fetch_metadata(meta_path, version, max_size, hashes)
+        }
+
+        fn store_target<'a, R>(
+            &'a self,
+            read: R,
+            target_path: &'a TargetPath,
+        ) -> TufFuture<'a, Result<()>>
+        where
+            R: AsyncRead + 'a,
+        {
+            self.repo.store_target(read, target_path)
+        }
+
+        fn fetch_target<'a>(
+            &'a self,
+            target_path: &'a TargetPath,
+            target_description: &'a TargetDescription,
+        ) -> TufFuture<'a, Result<Box<dyn AsyncRead>>> {
+            self.repo.fetch_target(target_path, target_description)
+        }
+
+        fn mirror(&self, mirror: &Mirror) -> Option<Self> {
+            self.mirrors
+                .get(mirror.url_base())
+                .map(|repo| MirroredRepository {
+                    repo: repo.clone(),
+                    mirrors: self.mirrors.clone(),
+                })
+        }
+    }
+
+    /// Records a summary of the events it is told about.
+    #[derive(Default)]
+    struct RecordingObserver(Mutex<Vec<String>>);
+
+    impl Observer for RecordingObserver {
+        fn notify(&self, event: &Event) {
+            let event = match *event {
+                Event::RootRotated {
+                    from_version,
+                    to_version,
+                } => format!("root rotated from {} to {}", from_version, to_version),
+                Event::MirrorFailover {
+                    metadata: Some(path),
+                    mirror,
+                    ..
+                } => format!("failover to {} for {}", mirror, path.to_string()),
+                Event::MirrorFailover {
+                    target: Some(target),
+                    mirror,
+                    ..
+                } => format!("failover to {} for {}", mirror, target.value()),
+                Event::TargetTooLarge { target, total } => {
+                    format!("{} is larger than {}", target.value(), total)
+                }
+                Event::SlowRetrieval { target } => format!("{} is too slow", target.value()),
+                Event::VerificationFailed {
+                    target: Some(target),
+                    ..
+                } => format!("{} failed to verify", target.value()),
+                _ => return,
+            };
+            self.0.lock().unwrap().push(event);
+        }
+    }
+
+    #[test]
+    fn download_failure_events() {
+        let data: &[u8] = b"like tears in the rain";
+        let target = TargetPath::new("batty".into()).unwrap();
+        let events = |read: Box<dyn AsyncRead>| {
+            let observer = Arc::new(RecordingObserver::default());
+            let mut read = ProgressReader {
+                inner: read,
+                observer: observer.clone(),
+                target: target.clone(),
+                received: 0,
+                total: 10,
+            };
+            let mut buf = Vec::new();
+            assert!(block_on(read.read_to_end(&mut buf)).is_err());
+            let recorded = observer.0.lock().unwrap();
+            recorded.clone()
+        };
+
+        let too_large = SafeReader::new(data, 10, SlowRetrievalPolicy::new(0), None).unwrap();
+        assert_eq!(events(Box::new(too_large)), vec!["batty is larger than 10"]);
+
+        let wrong_hash = SafeReader::new(
+            data,
+            data.len() as u64,
+            SlowRetrievalPolicy::new(0),
+            Some((&HashAlgorithm::Sha256, HashValue::new(vec![0x00; 32]))),
+        )
+        .unwrap();
+        assert_eq!(events(Box::new(wrong_hash)), vec!["batty failed to verify"]);
+
+        let policy = SlowRetrievalPolicy::new(0).max_duration(Duration::from_millis(10));
+        let too_slow = SafeReader::new(StalledBody, 10, policy, None).unwrap();
+        assert_eq!(events(Box::new(too_slow)), vec!["batty is too slow"]);
+    }
+
+    #[test]
+    fn mirror_failover() {
+        let data: &[u8] = b"like tears in the rain";
+        let path = TargetPath::new("batty".into()).unwrap();
+
+        let root = block_on(
+            RootMetadataBuilder::new()
+                .root_key(KEYS[0].public().clone())
+                .snapshot_key(KEYS[0].public().clone())
+                .targets_key(KEYS[0].public().clone())
+                .timestamp_key(KEYS[0].public().clone())
+                .mirrors_key(KEYS[0].public().clone())
+                .signed::<Json>(&KEYS[0]),
+        )
+        .unwrap();
+        let targets = block_on(
+            TargetsMetadataBuilder::new()
+                .insert_target_from_reader(
+                    VirtualTargetPath::new("batty".into()).unwrap(),
+                    data,
+                    &[HashAlgorithm::Sha256],
+                )
+                .unwrap()
+                .signed::<Json>(&KEYS[0]),
+        )
+        .unwrap();
+        let snapshot = block_on(
+            SnapshotMetadataBuilder::new()
+                .insert_metadata(&targets, &[HashAlgorithm::Sha256])
+                .unwrap()
+                .signed::<Json>(&KEYS[0]),
+        )
+        .unwrap();
+        let timestamp = block_on(
+            TimestampMetadataBuilder::from_snapshot(&snapshot, &[HashAlgorithm::Sha256])
+                .unwrap()
+                .signed::<Json>(&KEYS[0]),
+        )
+        .unwrap();
+
+        // The first mirror does not serve the target, so its corrupt copy must not be fetched.
+        let first_url = Url::parse("http://first.invalid/").unwrap();
+        let first = Arc::new(EphemeralRepository::new());
+        block_on(first.store_target(&b"like tears in the snow"[..], &path)).unwrap();
+        let second_url = Url::parse("http://second.invalid/").unwrap();
+        let second = Arc::new(EphemeralRepository::new());
+        block_on(second.store_target(data, &path)).unwrap();
+        let mut mirror_repos = HashMap::new();
+        let _ = mirror_repos.insert(first_url.clone(), first);
+        let _ = mirror_repos.insert(second_url.clone(), second);
+
+        // The remote repository has all of the metadata, but not the target.
+        let remote = MirroredRepository {
+            repo: Arc::new(EphemeralRepository::new()),
+            mirrors: Arc::new(mirror_repos),
+        };
+        let root_path = MetadataPath::from_role(&Role::Root);
+        let targets_path = MetadataPath::from_role(&Role::Targets);
+        let snapshot_path = MetadataPath::from_role(&Role::Snapshot);
+        let timestamp_path = MetadataPath::from_role(&Role::Timestamp);
+        block_on(
+            async {
+                await!(remote.store_metadata(&root_path, &MetadataVersion::Number(1), &root))?;
+                await!(remote.store_metadata(&root_path, &MetadataVersion::None, &root))?;
+                await!(remote.store_metadata(&targets_path, &MetadataVersion::None, &targets))?;
+                await!(remote.store_metadata(&snapshot_path, &MetadataVersion::None, &snapshot))?;
+                await!(remote.store_metadata(&timestamp_path, &MetadataVersion::None, &timestamp))
+            },
+        )
+        .unwrap();
+
+        let local_repo = Arc::new(EphemeralRepository::new());
+        let local = MirroredRepository {
+            repo: local_repo.clone(),
+            mirrors: Arc::new(HashMap::new()),
+        };
+        let key_ids = [KEYS[0].public().key_id().clone()];
+        let observer = Arc::new(RecordingObserver::default());
+        let mut client = block_on(Client::with_root_pinned(
+            &key_ids,
+            Config::build().observer(observer.clone()).finish().unwrap(),
+            local,
+            remote,
+        ))
+        .unwrap();
+
+        // The mirrors metadata is missing, which does not stop the update.
+        assert_eq!(block_on(client.update()), Ok(true));
+        assert!(client.mirrors().is_empty());
+        assert!(block_on(client.fetch_target(&path)).is_err());
+
+        let mirrors = block_on(
+            MirrorsMetadataBuilder::new()
+                .mirror(
+                    Mirror::new(
+                        first_url,
+                        "".into(),
+                        "".into(),
+                        vec!["*".into()],
+                        vec!["releases/*".into()],
+                    )
+                    .unwrap(),
+                )
+                .mirror(
+                    Mirror::new(
+                        second_url,
+                        "".into(),
+                        "".into(),
+                        vec!["*".into()],
+                        vec!["*".into()],
+                    )
+                    .unwrap(),
+                )
+                .signed::<Json>(&KEYS[0]),
+        )
+        .unwrap();
+        block_on(client.remote.store_metadata(
+            &MetadataPath::from_role(&Role::Mirrors),
+            &MetadataVersion::None,
+            &mirrors,
+        ))
+        .unwrap();
+
+        assert_eq!(block_on(client.update()), Ok(true));
+        assert_eq!(client.mirrors().len(), 2);
+
+        block_on(client.fetch_target(&path)).unwrap();
+        assert_eq!(
+            *observer.0.lock().unwrap(),
+            vec!["failover to http://second.invalid/ for batty"]
+        );
+        let target_description =
+            TargetDescription::from_reader(data, &[HashAlgorithm::Sha256]).unwrap();
+        let buf = block_on(
+            async {
+                let mut read = await!(local_repo.fetch_target(&path, &target_description))?;
+                let mut buf = Vec::new();
+                await!(read.read_to_end(&mut buf))?;
+                Ok::<_, Error>(buf)
+            },
+        )
+        .unwrap();
+        assert_eq!(buf.as_slice(), data);
+    }
+
+    /// Serves `responses` in turn as the root metadata, with an `ETag`, and answers
+    /// `304 Not Modified` whenever it is asked whether the root metadata has changed.
+    struct RootTransport {
+        responses: Mutex<Vec<Vec<u8>>>,
+        requests: Arc<Mutex<Vec<http::HeaderMap>>>,
+    }
+
+    impl HttpTransport for RootTransport {
+        fn request<'a>(
+            &'a self,
+            request: Request<Option<Box<dyn AsyncRead + 'a>>>,
+        ) -> TufFuture<'a, Result<Response<Box<dyn AsyncRead>>>> {
+            let conditional = request.headers().contains_key("If-None-Match");
+            self.requests
+                .lock()
+                .unwrap()
+                .push(request.headers().clone());
+            let resp = if conditional {
+                Response::builder()
+                    .status(StatusCode::NOT_MODIFIED)
+                    .body(Vec::new())
+            } else {
+                Response::builder()
+                    .header("ETag", "\"v2\"")
+                    .body(self.responses.lock().unwrap().remove(0))
+            }
+            .unwrap();
+            let resp = resp.map(|body| Box::new(Cursor::new(body)) as Box<dyn AsyncRead>);
+            Box::pin(future::ready(Ok(resp)))
+        }
+    }
+
+    #[test]
+    fn refetch_unverified_root() {
+        let root1 = block_on(
+            RootMetadataBuilder::new()
+                .version(1)
+                .root_key(KEYS[0].public().clone())
+                .snapshot_key(KEYS[0].public().clone())
+                .targets_key(KEYS[0].public().clone())
+                .timestamp_key(KEYS[0].public().clone())
+                .signed::<Json>(&KEYS[0]),
+        )
+        .unwrap();
+        let mut root2 = block_on(
+            RootMetadataBuilder::new()
+                .version(2)
+                .root_key(KEYS[1].public().clone())
+                .snapshot_key(KEYS[1].public().clone())
+                .targets_key(KEYS[1].public().clone())
+                .timestamp_key(KEYS[1].public().clone())
+                .signed::<Json>(&KEYS[1]),
+        )
+        .unwrap();
+
+        // Version 2 is only trusted once it is also signed by version 1's keys.
+        let unverifiable_root2 = root2.clone();
+        block_on(root2.add_signature(&KEYS[0])).unwrap();
+
+        let mut responses = Vec::new();
+        for root in &[&unverifiable_root2, &root2] {
+            let mut buf = Vec::new();
+            Json::to_writer(&mut buf, root).unwrap();
+            responses.push(buf);
+        }
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let remote = HttpRepositoryBuilder::<_, Json>::new(
+            Url::parse("http://tuf.invalid/").unwrap(),
+            RootTransport {
+                responses: Mutex::new(responses),
+                requests: requests.clone(),
+            },
+        )
+        .build();
+
+        let local = EphemeralRepository::new();
+        block_on(local.store_metadata(
+            &MetadataPath::from_role(&Role::Root),
+            &MetadataVersion::Number(1),
+            &root1,
+        ))
+        .unwrap();
+        let mut client = block_on(Client::new(Config::default(), local, remote)).unwrap();
+
+        // the server would answer that the root metadata is unchanged, but the client does not
+        // trust it yet, so it must be fetched again
+        assert!(block_on(client.update_root()).is_err());
+        assert_eq!(block_on(client.update_root()), Ok(true));
+        assert_eq!(client.tuf.root().version(), 2);
+        assert_eq!(block_on(client.update_root()), Ok(false));
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 3);
+        assert!(!requests[0].contains_key("If-None-Match"));
+        assert!(!requests[1].contains_key("If-None-Match"));
+        assert_eq!(requests[2]["If-None-Match"], "\"v2\"");
+    }
+
+    /// A response body that never arrives.
+    struct StalledBody;
+
+    impl AsyncRead for StalledBody {
+        fn poll_read(&mut self, _: &LocalWaker, _: &mut [u8]) -> Poll<io::Result<usize>> {
+            Poll::Pending
+        }
+    }
+
+    /// Answers every request with a `StalledBody`.
+    struct StalledTransport;
+
+    impl HttpTransport for StalledTransport {
+        fn request<'a>(
+            &'a self,
+            _: Request<Option<Box<dyn AsyncRead + 'a>>>,
+        ) -> TufFuture<'a, Result<Response<Box<dyn AsyncRead>>>> {
+            let resp = Response::new(Box::new(StalledBody) as Box<dyn AsyncRead>);
+            Box::pin(future::ready(Ok(resp)))
+        }
+    }
+
+    #[test]
+    fn slow_metadata_is_caught() {
+        let root = block_on(
+            RootMetadataBuilder::new()
+                .root_key(KEYS[0].public().clone())
+                .snapshot_key(KEYS[0].public().clone())
+                .targets_key(KEYS[0].public().clone())
+                .timestamp_key(KEYS[0].public().clone())
+                .signed::<Json>(&KEYS[0]),
+        )
+        .unwrap();
+        let local = EphemeralRepository::new();
+        block_on(local.store_metadata(
+            &MetadataPath::from_role(&Role::Root),
+            &MetadataVersion::Number(1),
+            &root,
+        ))
+        .unwrap();
+        let remote = HttpRepositoryBuilder::<_, Json>::new(
+            Url::parse("http://tuf.invalid/").unwrap(),
+            StalledTransport,
+        )
+        .build();
+        let config = Config::build()
+            .slow_retrieval_policy(
+                SlowRetrievalPolicy::new(0).max_duration(Duration::from_millis(50)),
+            )
+            .finish()
+            .unwrap();
+        let mut client = block_on(Client::new(config, local, remote)).unwrap();
+
+        assert_eq!(block_on(client.update_root()), Err(Error::Timeout));
+    }
+
+    #[test]
+    fn replayed_bytes_are_not_limited() {
+        let data = vec![0x00; 1000];
+        let limiter = BandwidthLimiter::new(10);
+        let replayed = Arc::new(AtomicUsize::new(0));
+        let mut read = LimitedReader::new(
+            CountingReader::new(Cursor::new(data.clone()), replayed.clone()),
+            Some(&limiter),
+            Some(replayed),
+        );
+        let mut buf = Vec::new();
+        block_on(read.read_to_end(&mut buf)).unwrap();
+        assert_eq!(buf, data);
+
+        // at 10 bytes per second, the 1000 bytes would have used the next 100 seconds
+        assert!(limiter.reserve(0) < Duration::from_secs(1));
+    }
+
     #[test]
     fn root_chain_update() {
         let repo = EphemeralRepository::new();
 
         //// First, create the root metadata.
-        let root1 = RootMetadataBuilder::new()
-            .version(1)
-            .expires(Utc.ymd(2038, 1, 1).and_hms(0, 0, 0))
-            .root_key(KEYS[0].public().clone())
-            .snapshot_key(KEYS[0].public().clone())
-            .targets_key(KEYS[0].public().clone())
-            .timestamp_key(KEYS[0].public().clone())
-            .signed::<Json>(&KEYS[0])
-            .unwrap();
+        let root1 = block_on(
+            RootMetadataBuilder::new()
+                .version(1)
+                .expires(Utc.ymd(2038, 1, 1).and_hms(0, 0, 0))
+                .root_key(KEYS[0].public().clone())
+                .snapshot_key(KEYS[0].public().clone())
+                .targets_key(KEYS[0].public().clone())
+                .timestamp_key(KEYS[0].public().clone())
+                .signed::<Json>(&KEYS[0]),
+        )
+        .unwrap();
 
-        let mut root2 = RootMetadataBuilder::new()
-            .version(2)
-            .expires(Utc.ymd(2038, 1, 1).and_hms(0, 0, 0))
-            .root_key(KEYS[1].public().clone())
-            .snapshot_key(KEYS[1].public().clone())
-            .targets_key(KEYS[1].public().clone())
-            .timestamp_key(KEYS[1].public().clone())
-            .signed::<Json>(&KEYS[1])
-            .unwrap();
+        let mut root2 = block_on(
+            RootMetadataBuilder::new()
+                .version(2)
+                .expires(Utc.ymd(2038, 1, 1).and_hms(0, 0, 0))
+                .root_key(KEYS[1].public().clone())
+                .snapshot_key(KEYS[1].public().clone())
+                .targets_key(KEYS[1].public().clone())
+                .timestamp_key(KEYS[1].public().clone())
+                .signed::<Json>(&KEYS[1]),
+        )
+        .unwrap();
 
-        root2.add_signature(&KEYS[0]).unwrap();
+        block_on(root2.add_signature(&KEYS[0])).unwrap();
 
         // Make sure the version 2 is signed by version 1's keys.
-        root2.add_signature(&KEYS[0]).unwrap();
-
-        let mut root3 = RootMetadataBuilder::new()
-            .version(3)
-            .expires(Utc.ymd(2038, 1, 1).and_hms(0, 0, 0))
-            .root_key(KEYS[2].public().clone())
-            .snapshot_key(KEYS[2].public().clone())
-            .targets_key(KEYS[2].public().clone())
-            .timestamp_key(KEYS[2].public().clone())
-            .signed::<Json>(&KEYS[2])
-            .unwrap();
+        block_on(root2.add_signature(&KEYS[0])).unwrap();
+
+        let mut root3 = block_on(
+            RootMetadataBuilder::new()
+                .version(3)
+                .expires(Utc.ymd(2038, 1, 1).and_hms(0, 0, 0))
+                .root_key(KEYS[2].public().clone())
+                .snapshot_key(KEYS[2].public().clone())
+                .targets_key(KEYS[2].public().clone())
+                .timestamp_key(KEYS[2].public().clone())
+                .signed::<Json>(&KEYS[2]),
+        )
+        .unwrap();
 
         // Make sure the version 3 is signed by version 2's keys.
-        root3.add_signature(&KEYS[1]).unwrap();
+        block_on(root3.add_signature(&KEYS[1])).unwrap();
 
-        let mut targets = TargetsMetadataBuilder::new()
-            .signed::<Json>(&KEYS[0])
-            .unwrap();
+        let mut targets = block_on(TargetsMetadataBuilder::new().signed::<Json>(&KEYS[0])).unwrap();
 
-        targets.add_signature(&KEYS[1]).unwrap();
-        targets.add_signature(&KEYS[2]).unwrap();
+        block_on(targets.add_signature(&KEYS[1])).unwrap();
+        block_on(targets.add_signature(&KEYS[2])).unwrap();
 
-        let mut snapshot = SnapshotMetadataBuilder::new()
-            .insert_metadata(&targets, &[HashAlgorithm::Sha256])
-            .unwrap()
-            .signed::<Json>(&KEYS[0])
-            .unwrap();
+        let mut snapshot = block_on(
+            SnapshotMetadataBuilder::new()
+                .insert_metadata(&targets, &[HashAlgorithm::Sha256])
+                .unwrap()
+                .signed::<Json>(&KEYS[0]),
+        )
+        .unwrap();
 
-        snapshot.add_signature(&KEYS[1]).unwrap();
-        snapshot.add_signature(&KEYS[2]).unwrap();
+        block_on(snapshot.add_signature(&KEYS[1])).unwrap();
+        block_on(snapshot.add_signature(&KEYS[2])).unwrap();
 
-        let mut timestamp =
+        let mut timestamp = block_on(
             TimestampMetadataBuilder::from_snapshot(&snapshot, &[HashAlgorithm::Sha256])
                 .unwrap()
-                .signed::<Json>(&KEYS[0])
-                .unwrap();
+                .signed::<Json>(&KEYS[0]),
+        )
+        .unwrap();
 
-        timestamp.add_signature(&KEYS[1]).unwrap();
-        timestamp.add_signature(&KEYS[2]).unwrap();
+        block_on(timestamp.add_signature(&KEYS[1])).unwrap();
+        block_on(timestamp.add_signature(&KEYS[2])).unwrap();
 
         ////
         // Now register the metadata.
@@ -885,9 +2521,10 @@ mod test {
         // Now, make sure that the local metadata got version 1.
 
         let key_ids = [KEYS[0].public().key_id().clone()];
+        let observer = Arc::new(RecordingObserver::default());
         let mut client = block_on(Client::with_root_pinned(
             &key_ids,
-            Config::build().finish().unwrap(),
+            Config::build().observer(observer.clone()).finish().unwrap(),
             EphemeralRepository::new(),
             repo,
         ))
@@ -943,6 +2580,10 @@ mod test {
 
         assert_eq!(block_on(client.update()), Ok(true));
         assert_eq!(client.tuf.root().version(), 3);
+        assert_eq!(
+            *observer.0.lock().unwrap(),
+            vec!["root rotated from 1 to 2", "root rotated from 2 to 3"]
+        );
 
         assert_eq!(
             root3,
@@ -955,4 +2596,29 @@ mod test {
             .unwrap(),
         );
     }
+
+    #[test]
+    fn config_hash_algorithms() {
+        let config = ConfigBuilder::default()
+            .hash_algorithms(vec![HashAlgorithm::Sha256])
+            .finish()
+            .unwrap();
+        assert_eq!(config.hash_algorithms(), &[HashAlgorithm::Sha256]);
+        assert!(!config.strict_hashes());
+
+        let config = ConfigBuilder::default()
+            .strict_hashes(true)
+            .finish()
+            .unwrap();
+        assert!(config.strict_hashes());
+
+        assert!(ConfigBuilder::default()
+            .hash_algorithms(vec![])
+            .finish()
+            .is_err());
+        assert!(ConfigBuilder::default()
+            .hash_algorithms(vec![HashAlgorithm::Unknown("md5".into())])
+            .finish()
+            .is_err());
+    }
 }
@@ -0,0 +1,364 @@
+//! Streaming decoders for the HTTP `Content-Encoding`s enabled by the `gzip` and `zstd` features.
+
+use futures::io::AsyncRead;
+use futures::task::LocalWaker;
+use futures::{try_ready, Poll};
+use std::io::{self, ErrorKind};
+
+/// How many bytes of encoded input are read from the inner reader at a time.
+const INPUT_BUFFER_LEN: usize = 8 * 1024;
+
+/// A decompressor that can be fed its input in pieces of any size.
+pub(crate) trait Decompress {
+    /// Decompress a prefix of `input` into `output`, returning how many bytes of each were used.
+    /// `input` is only empty once the encoded stream has ended.
+    fn decompress(&mut self, input: &[u8], output: &mut [u8]) -> io::Result<(usize, usize)>;
+
+    /// Whether the encoded stream may end at this point.
+    fn is_complete(&self) -> bool;
+}
+
+/// Wraps an `AsyncRead` of encoded bytes to read them decoded by a `Decompress`.
+pub(crate) struct DecodingReader<R, D> {
+    inner: R,
+    decompress: D,
+    input: Box<[u8]>,
+    pos: usize,
+    cap: usize,
+    eof: bool,
+}
+
+impl<R: AsyncRead, D: Decompress> DecodingReader<R, D> {
+    pub(crate) fn new(inner: R, decompress: D) -> Self {
+        DecodingReader {
+            inner,
+            decompress,
+            input: vec![0; INPUT_BUFFER_LEN].into_boxed_slice(),
+            pos: 0,
+            cap: 0,
+            eof: false,
+        }
+    }
+}
+
+impl<R: AsyncRead, D: Decompress> AsyncRead for DecodingReader<R, D> {
+    fn poll_read(&mut self, lw: &LocalWaker, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        loop {
+            if self.pos == self.cap && !self.eof {
+                self.cap = try_ready!(self.inner.poll_read(lw, &mut self.input));
+                self.pos = 0;
+                self.eof = self.cap == 0;
+            }
+
+            let (used, written) = self
+                .decompress
+                .decompress(&self.input[self.pos..self.cap], buf)?;
+            self.pos += used;
+
+            if written > 0 {
+                return Poll::Ready(Ok(written));
+            }
+            if self.eof {
+                if self.decompress.is_complete() {
+                    return Poll::Ready(Ok(0));
+                }
+                return Poll::Ready(Err(io::Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "Encoded stream ended early",
+                )));
+            }
+            if used == 0 && self.pos < self.cap {
+                return Poll::Ready(Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    "Decompressor made no progress",
+                )));
+            }
+        }
+    }
+}
+
+/// Decompresses a gzip stream (RFC 1952) of one or more members.
+#[cfg(feature = "gzip")]
+pub(crate) enum GzipDecompress {
+    /// Buffering a member's header until all of it has arrived.
+    Header(Vec<u8>),
+    /// Inflating a member's deflate stream, tracking the checksum of the output.
+    Body(flate2::Decompress, flate2::Crc),
+    /// Buffering a member's trailer to check it against the output.
+    Trailer(Vec<u8>, flate2::Crc),
+    /// Between members, or at the end of the stream.
+    Done,
+}
+
+#[cfg(feature = "gzip")]
+impl GzipDecompress {
+    /// The largest gzip header that will be buffered. Headers can hold an arbitrarily long file
+    /// name and comment, and up to 64 KiB of extra fields.
+    const MAX_HEADER_LEN: usize = 128 * 1024;
+
+    pub(crate) fn new() -> Self {
+        GzipDecompress::Header(Vec::new())
+    }
+
+    /// The length of the gzip header at the start of `header`, or `None` if it's incomplete.
+    fn header_len(header: &[u8]) -> io::Result<Option<usize>> {
+        const FHCRC: u8 = 0x02;
+        const FEXTRA: u8 = 0x04;
+        const FNAME: u8 = 0x08;
+        const FCOMMENT: u8 = 0x10;
+        const RESERVED: u8 = 0xe0;
+
+        if header.len() < 10 {
+            return Ok(None);
+        }
+        if header[..3] != [0x1f, 0x8b, 0x08] || header[3] & RESERVED != 0 {
+            return Err(io::Error::new(ErrorKind::InvalidData, "Bad gzip header"));
+        }
+
+        let flags = header[3];
+        let mut len = 10;
+        if flags & FEXTRA != 0 {
+            if header.len() < len + 2 {
+                return Ok(None);
+            }
+            len += 2 + (header[len] as usize | (header[len + 1] as usize) << 8);
+        }
+        for flag in &[FNAME, FCOMMENT] {
+            if flags & flag != 0 {
+                match header
+                    .get(len..)
+                    .and_then(|rest| rest.iter().position(|b| *b == 0))
+                {
+                    Some(end) => len += end + 1,
+                    None => return Ok(None),
+                }
+            }
+        }
+        if flags & FHCRC != 0 {
+            len += 2;
+        }
+
+        if header.len() < len {
+            Ok(None)
+        } else {
+            Ok(Some(len))
+        }
+    }
+}
+
+#[cfg(feature = "gzip")]
+impl Decompress for GzipDecompress {
+    fn decompress(&mut self, input: &[u8], output: &mut [u8]) -> io::Result<(usize, usize)> {
+        match self {
+            GzipDecompress::Header(header) => {
+                header.extend_from_slice(input);
+                match Self::header_len(header)? {
+                    Some(len) => {
+                        let used = input.len() - (header.len() - len);
+                        *self = GzipDecompress::Body(
+                            flate2::Decompress::new(false),
+                            flate2::Crc::new(),
+                        );
+                        Ok((used, 0))
+                    }
+                    None if header.len() > Self::MAX_HEADER_LEN => Err(io::Error::new(
+                        ErrorKind::InvalidData,
+                        "gzip header is too long",
+                    )),
+                    None => Ok((input.len(), 0)),
+                }
+            }
+            GzipDecompress::Body(inflate, crc) => {
+                let (total_in, total_out) = (inflate.total_in(), inflate.total_out());
+                let status = inflate.decompress(input, output, flate2::FlushDecompress::None)?;
+                let used = (inflate.total_in() - total_in) as usize;
+                let written = (inflate.total_out() - total_out) as usize;
+                crc.update(&output[..written]);
+
+                if status == flate2::Status::StreamEnd {
+                    let crc = ::std::mem::replace(crc, flate2::Crc::new());
+                    *self = GzipDecompress::Trailer(Vec::with_capacity(8), crc);
+                }
+                Ok((used, written))
+            }
+            GzipDecompress::Trailer(trailer, crc) => {
+                let used = input.len().min(8 - trailer.len());
+                trailer.extend_from_slice(&input[..used]);
+                if trailer.len() == 8 {
+                    let le_u32 = |b: &[u8]| {
+                        u32::from(b[0])
+                            | u32::from(b[1]) << 8
+                            | u32::from(b[2]) << 16
+                            | u32::from(b[3]) << 24
+                    };
+                    if le_u32(&trailer[..4]) != crc.sum() || le_u32(&trailer[4..]) != crc.amount() {
+                        return Err(io::Error::new(
+                            ErrorKind::InvalidData,
+                            "gzip checksum mismatch",
+                        ));
+                    }
+                    *self = GzipDecompress::Done;
+                }
+                Ok((used, 0))
+            }
+            GzipDecompress::Done if input.is_empty() => Ok((0, 0)),
+            GzipDecompress::Done => {
+                *self = GzipDecompress::new();
+                self.decompress(input, output)
+            }
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        match self {
+            GzipDecompress::Done => true,
+            _ => false,
+        }
+    }
+}
+
+/// Decompresses a zstd stream of one or more frames.
+#[cfg(feature = "zstd")]
+pub(crate) struct ZstdDecompress {
+    decoder: zstd::stream::raw::Decoder,
+    frame_done: bool,
+}
+
+#[cfg(feature = "zstd")]
+impl ZstdDecompress {
+    pub(crate) fn new() -> io::Result<Self> {
+        Ok(ZstdDecompress {
+            decoder: zstd::stream::raw::Decoder::new()?,
+            frame_done: false,
+        })
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl Decompress for ZstdDecompress {
+    fn decompress(&mut self, input: &[u8], output: &mut [u8]) -> io::Result<(usize, usize)> {
+        use zstd::stream::raw::Operation;
+
+        if self.frame_done {
+            if input.is_empty() {
+                return Ok((0, 0));
+            }
+            self.decoder.reinit()?;
+            self.frame_done = false;
+        }
+
+        let status = self.decoder.run_on_buffers(input, output)?;
+        self.frame_done = status.remaining == 0;
+        Ok((status.bytes_read, status.bytes_written))
+    }
+
+    fn is_complete(&self) -> bool {
+        self.frame_done
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use futures::executor::block_on;
+    use futures::io::AsyncReadExt;
+
+    /// An `AsyncRead` that returns at most one byte per read, to split every part of the encoded
+    /// stream across reads.
+    struct OneByte<'a>(&'a [u8]);
+
+    impl<'a> AsyncRead for OneByte<'a> {
+        fn poll_read(&mut self, _: &LocalWaker, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+            match self.0.split_first() {
+                Some((b, rest)) if !buf.is_empty() => {
+                    buf[0] = *b;
+                    self.0 = rest;
+                    Poll::Ready(Ok(1))
+                }
+                _ => Poll::Ready(Ok(0)),
+            }
+        }
+    }
+
+    fn decode<D: Decompress>(encoded: &[u8], decompress: D) -> io::Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        let mut reader = DecodingReader::new(OneByte(encoded), decompress);
+        block_on(reader.read_to_end(&mut buf))?;
+        Ok(buf)
+    }
+
+    #[test]
+    #[cfg(feature = "gzip")]
+    fn gzip_split_reads() {
+        use flate2::write::GzEncoder;
+        use flate2::{Compression, GzBuilder};
+        use std::io::Write;
+
+        let data = b"like tears in rain"
+            .iter()
+            .cycle()
+            .take(1800)
+            .cloned()
+            .collect::<Vec<u8>>();
+        let mut encoder = GzBuilder::new()
+            .filename("data")
+            .comment("some data")
+            .extra(vec![1, 2, 3])
+            .write(Vec::new(), Compression::default());
+        encoder.write_all(&data).unwrap();
+        let mut encoded = encoder.finish().unwrap();
+
+        assert_eq!(decode(&encoded, GzipDecompress::new()).unwrap(), data);
+
+        // members are concatenated
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"!").unwrap();
+        let mut two = encoded.clone();
+        two.extend(encoder.finish().unwrap());
+        let mut expected = data.clone();
+        expected.push(b'!');
+        assert_eq!(decode(&two, GzipDecompress::new()).unwrap(), expected);
+
+        let truncated = &encoded[..encoded.len() - 1];
+        assert_eq!(
+            decode(truncated, GzipDecompress::new()).unwrap_err().kind(),
+            ErrorKind::UnexpectedEof
+        );
+
+        let len = encoded.len();
+        encoded[len - 8] ^= 1;
+        assert_eq!(
+            decode(&encoded, GzipDecompress::new()).unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "zstd")]
+    fn zstd_split_reads() {
+        let data = b"like tears in rain"
+            .iter()
+            .cycle()
+            .take(1800)
+            .cloned()
+            .collect::<Vec<u8>>();
+        let encoded = zstd::encode_all(&data[..], 0).unwrap();
+
+        assert_eq!(
+            decode(&encoded, ZstdDecompress::new().unwrap()).unwrap(),
+            data
+        );
+
+        let truncated = &encoded[..encoded.len() - 1];
+        assert_eq!(
+            decode(truncated, ZstdDecompress::new().unwrap())
+                .unwrap_err()
+                .kind(),
+            ErrorKind::UnexpectedEof
+        );
+    }
+}
@@ -0,0 +1,1759 @@
+//! Cryptographic structures and functions.
+
+use data_encoding::BASE64URL;
+use derp::{self, Der, Tag};
+#[cfg(feature = "rsa")]
+use num_bigint_dig::BigUint;
+#[cfg(feature = "rsa")]
+use rand::rngs::OsRng;
+#[cfg(feature = "rsa")]
+use rsa::{PublicKey as RsaPublicKey, RSAPrivateKey};
+use serde::de::{Deserialize, Deserializer, Error as DeserializeError};
+use serde::ser::{Error as SerializeError, Serialize, Serializer};
+use serde_derive::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Display};
+use std::future::Future;
+use std::hash;
+use std::io::Read;
+use std::pin::Pin;
+use std::str::FromStr;
+use untrusted::Input;
+
+use crate::error::Error;
+use crate::metadata::ExtensionValue;
+use crate::shims;
+use crate::Result;
+
+mod pem;
+mod pkcs5;
+pub mod provider;
+#[cfg(feature = "ring")]
+mod ring_provider;
+#[cfg(feature = "rust-crypto")]
+mod rust_provider;
+
+use self::provider::{default_provider, KeyPair};
+
+/// The default order of preference for hash algorithms, most preferred first.
+pub(crate) const HASH_ALG_PREFS: &[HashAlgorithm] = &[
+    HashAlgorithm::Sha512,
+    HashAlgorithm::Sha384,
+    HashAlgorithm::Sha512Trunc256,
+    HashAlgorithm::Sha256,
+    HashAlgorithm::Blake2b,
+];
+
+/// 1.2.840.113549.1.1.1 rsaEncryption(PKCS #1)
+const RSA_SPKI_OID: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01];
+
+/// 1.3.101.112 curveEd25519(EdDSA 25519 signature algorithm)
+const ED25519_SPKI_OID: &[u8] = &[0x2b, 0x65, 0x70];
+
+/// 1.2.840.10045.2.1 ecPublicKey(ANSI X9.62 public key type)
+const ECDSA_SPKI_OID: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+
+/// 1.2.840.10045.3.1.7 prime256v1(ANSI X9.62 named elliptic curve)
+const ECDSA_P256_CURVE_OID: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+
+/// 1.3.132.0.34 secp384r1(SECG (Certicom) named elliptic curve)
+const ECDSA_P384_CURVE_OID: &[u8] = &[0x2b, 0x81, 0x04, 0x00, 0x22];
+
+const SPKI_PEM_LABEL: &str = "PUBLIC KEY";
+const PKCS8_PEM_LABEL: &str = "PRIVATE KEY";
+const ENCRYPTED_PKCS8_PEM_LABEL: &str = "ENCRYPTED PRIVATE KEY";
+
+/// The modulus size, in bits, of RSA keys generated by `PrivateKey::new`.
+#[cfg(feature = "rsa")]
+const DEFAULT_RSA_KEY_BITS: usize = 4096;
+
+/// Given a map of hash algorithms and their values, get the prefered algorithm and the hash
+/// calculated by it. Returns an `Err` if there is no match.
+///
+/// ```
+/// use std::collections::HashMap;
+/// use tuf::crypto::{hash_preference, HashValue, HashAlgorithm};
+///
+/// let mut map = HashMap::new();
+/// assert!(hash_preference(&map).is_err());
+///
+/// let _ = map.insert(HashAlgorithm::Sha512, HashValue::new(vec![0x00, 0x01]));
+/// assert_eq!(hash_preference(&map).unwrap().0, &HashAlgorithm::Sha512);
+///
+/// let _ = map.insert(HashAlgorithm::Sha256, HashValue::new(vec![0x02, 0x03]));
+/// assert_eq!(hash_preference(&map).unwrap().0, &HashAlgorithm::Sha512);
+/// ```
+pub fn hash_preference<'a>(
+    hashes: &'a HashMap<HashAlgorithm, HashValue>,
+) -> Result<(&'static HashAlgorithm, &'a HashValue)> {
+    hash_preference_with(hashes, HASH_ALG_PREFS)
+}
+
+/// Like `hash_preference`, but with a caller supplied order of preference.
+///
+/// ```
+/// use std::collections::HashMap;
+/// use tuf::crypto::{hash_preference_with, HashValue, HashAlgorithm};
+///
+/// let mut map = HashMap::new();
+/// let _ = map.insert(HashAlgorithm::Sha512, HashValue::new(vec![0x00, 0x01]));
+/// let _ = map.insert(HashAlgorithm::Sha256, HashValue::new(vec![0x02, 0x03]));
+///
+/// let prefs = &[HashAlgorithm::Sha256, HashAlgorithm::Sha512];
+/// assert_eq!(hash_preference_with(&map, prefs).unwrap().0, &HashAlgorithm::Sha256);
+///
+/// let prefs = &[HashAlgorithm::Sha384];
+/// assert!(hash_preference_with(&map, prefs).is_err());
+/// ```
+pub fn hash_preference_with<'a, 'b>(
+    hashes: &'a HashMap<HashAlgorithm, HashValue>,
+    prefs: &'b [HashAlgorithm],
+) -> Result<(&'b HashAlgorithm, &'a HashValue)> {
+    for alg in prefs {
+        match hashes.get(alg) {
+            Some(v) => return Ok((alg, v)),
+            None => continue,
+        }
+    }
+    Err(Error::NoSupportedHashAlgorithm)
+}
+
+/// Calculate the size and hash digest from a given `Read`.
+pub fn calculate_hashes<R: Read>(
+    mut read: R,
+    hash_algs: &[HashAlgorithm],
+) -> Result<(u64, HashMap<HashAlgorithm, HashValue>)> {
+    if hash_algs.is_empty() {
+        return Err(Error::IllegalArgument(
+            "Cannot provide empty set of hash algorithms".into(),
+        ));
+    }
+
+    let mut size = 0;
+    let mut hashes = HashMap::new();
+    for alg in hash_algs {
+        let _ = hashes.insert(alg, default_provider().digest(alg)?);
+    }
+
+    let mut buf = vec![0; 1024];
+    loop {
+        match read.read(&mut buf) {
+            Ok(read_bytes) => {
+                if read_bytes == 0 {
+                    break;
+                }
+
+                size += read_bytes as u64;
+
+                for context in hashes.values_mut() {
+                    context.update(&buf[0..read_bytes]);
+                }
+            }
+            e @ Err(_) => e.map(|_| ())?,
+        }
+    }
+
+    let hashes = hashes
+        .drain()
+        .map(|(k, v)| (k.clone(), HashValue::new(v.finish())))
+        .collect();
+    Ok((size, hashes))
+}
+
+fn calculate_key_id(public_key: &[u8]) -> Result<KeyId> {
+    let mut context = default_provider().digest(&HashAlgorithm::Sha256)?;
+    context.update(&public_key);
+    Ok(KeyId(context.finish()))
+}
+
+/// Wrapper type for public key's ID.
+///
+/// # Calculating
+/// A `KeyId` is calculated as `sha256(spki(pub_key_bytes))` where `spki` is a function that takes
+/// any encoding for a public key an converts it into the `SubjectPublicKeyInfo` (SPKI) DER
+/// encoding as defined in [RFC 3280 Appendix A](https://www.ietf.org/rfc/rfc3280.txt).
+///
+/// Note: Historically the TUF spec says that a key's ID should be calculated with
+/// `sha256(cjson(encoded(pub_key_bytes)))`, but since there could be multiple supported data
+/// interchange formats, relying on an encoding that uses JSON does not make sense.
+///
+/// # ASN.1
+/// ```bash
+/// PublicKey ::= CHOICE {
+///     -- This field is checked for consistency against `subjectPublicKey`.
+///     -- The OID determines how we attempt to parse the `BIT STRING`.
+///     algorithm        AlgorithmIdentifier,
+///     -- Either:
+///     --   1. Encapsulates an `RsaPublicKey`
+///     --   2. Equals an `Ed25519PublicKey`
+///     --   3. Equals an `EcdsaPublicKey`
+///     subjectPublicKey BIT STRING
+/// }
+///
+/// AlgorithmIdentifier ::= SEQUENCE {
+///     -- Either:
+///     --   1. 1.2.840.113549.1.1.1 rsaEncryption(PKCS #1)
+///     --   2. 1.3.101.112 curveEd25519(EdDSA 25519 signature algorithm)
+///     --   3. 1.2.840.10045.2.1 ecPublicKey(ANSI X9.62 public key type)
+///     algorithm  OBJECT IDENTIFIER,
+///     -- For RSA and Ed25519, this is always `NULL`. For ECDSA, this is the OID of the named
+///     -- curve, either:
+///     --   1. 1.2.840.10045.3.1.7 prime256v1(ANSI X9.62 named elliptic curve)
+///     --   2. 1.3.132.0.34 secp384r1(SECG (Certicom) named elliptic curve)
+///     parameters ANY DEFINED BY algorithm OPTIONAL
+/// }
+///
+/// RsaPublicKey ::= SEQUENCE {
+///     modulus  INTEGER (1..MAX),
+///     exponent INTEGER (1..MAX)
+/// }
+///
+/// Ed25519PublicKey ::= BIT STRING
+///
+/// -- The uncompressed elliptic curve point as defined in SEC 1 section 2.3.3.
+/// EcdsaPublicKey ::= BIT STRING
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct KeyId(Vec<u8>);
+
+impl KeyId {
+    /// Parse a key ID from a base64url string.
+    pub fn from_string(string: &str) -> Result<Self> {
+        if string.len() != 44 {
+            return Err(Error::IllegalArgument(
+                "Base64 key ID must be 44 characters long".into(),
+            ));
+        }
+        Ok(KeyId(BASE64URL.decode(string.as_bytes())?))
+    }
+}
+
+impl Debug for KeyId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "KeyId {{ \"{}\" }}", BASE64URL.encode(&self.0))
+    }
+}
+
+impl Serialize for KeyId {
+    fn serialize<S>(&self, ser: S) -> ::std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        BASE64URL.encode(&self.0).serialize(ser)
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyId {
+    fn deserialize<D: Deserializer<'de>>(de: D) -> ::std::result::Result<Self, D::Error> {
+        let string: String = Deserialize::deserialize(de)?;
+        KeyId::from_string(&string).map_err(|e| DeserializeError::custom(format!("{:?}", e)))
+    }
+}
+
+/// Cryptographic signature schemes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SignatureScheme {
+    /// [Ed25519](https://ed25519.cr.yp.to/)
+    #[serde(rename = "ed25519")]
+    Ed25519,
+    /// [RSASSA-PSS](https://tools.ietf.org/html/rfc5756) calculated over SHA256
+    #[serde(rename = "rsassa-pss-sha256")]
+    RsaSsaPssSha256,
+    /// [RSASSA-PSS](https://tools.ietf.org/html/rfc5756) calculated over SHA512
+    #[serde(rename = "rsassa-pss-sha512")]
+    RsaSsaPssSha512,
+    /// [RSASSA-PKCS1-v1_5](https://tools.ietf.org/html/rfc8017#section-8.2) calculated over
+    /// SHA256
+    #[serde(rename = "rsassa-pkcs1v15-sha256")]
+    RsaSsaPkcs1v15Sha256,
+    /// [RSASSA-PKCS1-v1_5](https://tools.ietf.org/html/rfc8017#section-8.2) calculated over
+    /// SHA512
+    #[serde(rename = "rsassa-pkcs1v15-sha512")]
+    RsaSsaPkcs1v15Sha512,
+    /// [ECDSA](https://tools.ietf.org/html/rfc5480) on the NIST P-256 curve calculated over
+    /// SHA256
+    #[serde(rename = "ecdsa-sha2-nistp256")]
+    EcdsaP256Sha256,
+    /// [ECDSA](https://tools.ietf.org/html/rfc5480) on the NIST P-384 curve calculated over
+    /// SHA384
+    #[serde(rename = "ecdsa-sha2-nistp384")]
+    EcdsaP384Sha384,
+    /// Placeholder for an unknown scheme.
+    Unknown(String),
+}
+
+/// Wrapper type for the value of a cryptographic signature.
+#[derive(Clone, PartialEq)]
+pub struct SignatureValue(Vec<u8>);
+
+impl SignatureValue {
+    /// Create a new `SignatureValue` from the given bytes.
+    ///
+    /// Note: It is unlikely that you ever want to do this manually.
+    pub fn new(bytes: Vec<u8>) -> Self {
+        SignatureValue(bytes)
+    }
+
+    /// Create a new `SignatureValue` from the given base64url string.
+    ///
+    /// Note: It is unlikely that you ever want to do this manually.
+    pub fn from_string(string: &str) -> Result<Self> {
+        Ok(SignatureValue(BASE64URL.decode(string.as_bytes())?))
+    }
+}
+
+impl Debug for SignatureValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SignatureValue {{ \"{}\" }}", BASE64URL.encode(&self.0))
+    }
+}
+
+impl Serialize for SignatureValue {
+    fn serialize<S>(&self, ser: S) -> ::std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        BASE64URL.encode(&self.0).serialize(ser)
+    }
+}
+
+impl<'de> Deserialize<'de> for SignatureValue {
+    fn deserialize<D: Deserializer<'de>>(de: D) -> ::std::result::Result<Self, D::Error> {
+        let string: String = Deserialize::deserialize(de)?;
+        SignatureValue::from_string(&string).map_err(|e| {
+            DeserializeError::custom(format!("Signature value was not valid base64url: {:?}", e))
+        })
+    }
+}
+
+/// Types of public keys.
+#[derive(Clone, PartialEq, Debug, Eq)]
+pub enum KeyType {
+    /// [Ed25519](https://ed25519.cr.yp.to/)
+    Ed25519,
+    /// [RSA](https://en.wikipedia.org/wiki/RSA_%28cryptosystem%29)
+    Rsa,
+    /// [ECDSA](https://tools.ietf.org/html/rfc5480) on the NIST P-256 curve
+    EcdsaP256,
+    /// [ECDSA](https://tools.ietf.org/html/rfc5480) on the NIST P-384 curve
+    EcdsaP384,
+    /// Placeholder for an unknown key type.
+    Unknown(String),
+}
+
+impl KeyType {
+    fn from_oid(oid: &[u8]) -> Result<Self> {
+        match oid {
+            x if x == RSA_SPKI_OID => Ok(KeyType::Rsa),
+            x if x == ED25519_SPKI_OID => Ok(KeyType::Ed25519),
+            x => Err(Error::Encoding(format!(
+                "Unknown OID: {}",
+                x.iter().map(|b| format!("{:x}", b)).collect::<String>()
+            ))),
+        }
+    }
+
+    fn from_curve_oid(oid: &[u8]) -> Result<Self> {
+        match oid {
+            x if x == ECDSA_P256_CURVE_OID => Ok(KeyType::EcdsaP256),
+            x if x == ECDSA_P384_CURVE_OID => Ok(KeyType::EcdsaP384),
+            x => Err(Error::Encoding(format!(
+                "Unknown curve OID: {}",
+                x.iter().map(|b| format!("{:x}", b)).collect::<String>()
+            ))),
+        }
+    }
+
+    fn as_oid(&self) -> Result<&'static [u8]> {
+        match *self {
+            KeyType::Rsa => Ok(RSA_SPKI_OID),
+            KeyType::Ed25519 => Ok(ED25519_SPKI_OID),
+            KeyType::EcdsaP256 | KeyType::EcdsaP384 => Ok(ECDSA_SPKI_OID),
+            KeyType::Unknown(ref s) => Err(Error::UnknownKeyType(s.clone())),
+        }
+    }
+
+    fn curve_oid(&self) -> Option<&'static [u8]> {
+        match *self {
+            KeyType::EcdsaP256 => Some(ECDSA_P256_CURVE_OID),
+            KeyType::EcdsaP384 => Some(ECDSA_P384_CURVE_OID),
+            _ => None,
+        }
+    }
+
+    fn supports(&self, scheme: &SignatureScheme) -> bool {
+        match (self, scheme) {
+            (&KeyType::Ed25519, &SignatureScheme::Ed25519)
+            | (&KeyType::Rsa, &SignatureScheme::RsaSsaPssSha256)
+            | (&KeyType::Rsa, &SignatureScheme::RsaSsaPssSha512)
+            | (&KeyType::Rsa, &SignatureScheme::RsaSsaPkcs1v15Sha256)
+            | (&KeyType::Rsa, &SignatureScheme::RsaSsaPkcs1v15Sha512)
+            | (&KeyType::EcdsaP256, &SignatureScheme::EcdsaP256Sha256)
+            | (&KeyType::EcdsaP384, &SignatureScheme::EcdsaP384Sha384) => true,
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for KeyType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> ::std::result::Result<Self, Self::Err> {
+        match s {
+            "ed25519" => Ok(KeyType::Ed25519),
+            "rsa" => Ok(KeyType::Rsa),
+            "ecdsa-sha2-nistp256" => Ok(KeyType::EcdsaP256),
+            "ecdsa-sha2-nistp384" => Ok(KeyType::EcdsaP384),
+            typ => Err(Error::Encoding(typ.into())),
+        }
+    }
+}
+
+impl ToString for KeyType {
+    fn to_string(&self) -> String {
+        match *self {
+            KeyType::Ed25519 => "ed25519".to_string(),
+            KeyType::Rsa => "rsa".to_string(),
+            KeyType::EcdsaP256 => "ecdsa-sha2-nistp256".to_string(),
+            KeyType::EcdsaP384 => "ecdsa-sha2-nistp384".to_string(),
+            KeyType::Unknown(ref s) => s.to_string(),
+        }
+    }
+}
+
+impl Serialize for KeyType {
+    fn serialize<S>(&self, ser: S) -> ::std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        ser.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyType {
+    fn deserialize<D: Deserializer<'de>>(de: D) -> ::std::result::Result<Self, D::Error> {
+        let string: String = Deserialize::deserialize(de)?;
+        string
+            .parse()
+            .map_err(|e| DeserializeError::custom(format!("{:?}", e)))
+    }
+}
+
+/// A structure containing information about a private key.
+pub struct PrivateKey {
+    private: Box<dyn KeyPair>,
+    public: PublicKey,
+}
+
+impl PrivateKey {
+    /// Generate a new `PrivateKey` and return it as PKCS#8 DER bytes.
+    ///
+    /// RSA keys are generated with a 4096 bit modulus. Use `PrivateKey::new_rsa` to pick a
+    /// different size. Generating RSA keys requires the `rsa` feature.
+    pub fn new(key_type: KeyType) -> Result<Vec<u8>> {
+        match key_type {
+            #[cfg(feature = "rsa")]
+            KeyType::Rsa => Self::new_rsa(DEFAULT_RSA_KEY_BITS),
+            #[cfg(not(feature = "rsa"))]
+            KeyType::Rsa => Err(Error::IllegalArgument(
+                "Generating RSA keys requires the `rsa` feature".into(),
+            )),
+            KeyType::Unknown(s) => Err(Error::IllegalArgument(format!("Unknown key type: {}", s))),
+            typ => default_provider().generate_pkcs8(&typ),
+        }
+    }
+
+    /// Generate a new RSA key with a modulus of `bits` bits and return it as PKCS#8 DER bytes.
+    ///
+    /// The modulus must be between 2048 and 4096 bits and a multiple of 8. The public exponent is
+    /// always 65537.
+    #[cfg(feature = "rsa")]
+    pub fn new_rsa(bits: usize) -> Result<Vec<u8>> {
+        if bits < 2048 || bits > 4096 || bits % 8 != 0 {
+            return Err(Error::IllegalArgument(format!(
+                "RSA modulus must be a multiple of 8 between 2048 and 4096 bits. Found {}",
+                bits
+            )));
+        }
+
+        let mut rng =
+            OsRng::new().map_err(|e| Error::Opaque(format!("Could not open OS RNG: {:?}", e)))?;
+        let key = RSAPrivateKey::new(&mut rng, bits)
+            .map_err(|e| Error::Opaque(format!("Failed to generate RSA key: {:?}", e)))?;
+
+        let (p, q) = match key.primes() {
+            [p, q] => (p, q),
+            _ => return Err(Error::Programming("RSA key did not have two primes".into())),
+        };
+
+        let one = BigUint::from(1u32);
+        let dp = key.d() % (p - &one);
+        let dq = key.d() % (q - &one);
+        // p is prime, so q^(p - 2) is the inverse of q mod p
+        let qinv = q.modpow(&(p - BigUint::from(2u32)), p);
+
+        Ok(write_rsa_pkcs8(&[
+            key.n(),
+            key.e(),
+            key.d(),
+            p,
+            q,
+            &dp,
+            &dq,
+            &qinv,
+        ])?)
+    }
+
+    /// Create a private key from PKCS#8 v1 or v2 DER bytes.
+    ///
+    /// # Generating Keys
+    ///
+    /// If you use `cargo install tuf`, you will have access to the TUF CLI tool that will allow
+    /// you to generate keys. If you do not want to do this, the following can be used instead.
+    ///
+    /// ## Ed25519
+    ///
+    /// ```bash
+    /// $ touch ed25519-private-key.pk8
+    /// $ chmod 0600 ed25519-private-key.pk8
+    /// ```
+    ///
+    /// ```no_run
+    /// # use std::fs::File;
+    /// # use std::io::Write;
+    /// # use tuf::crypto::{KeyType, PrivateKey};
+    /// # fn main() {
+    /// let mut file = File::open("ed25519-private-key.pk8").unwrap();
+    /// let key = PrivateKey::new(KeyType::Ed25519).unwrap();
+    /// file.write_all(&key).unwrap()
+    /// # }
+    /// ```
+    ///
+    /// ## RSA
+    ///
+    /// ```bash
+    /// $ umask 077
+    /// $ openssl genpkey -algorithm RSA \
+    ///     -pkeyopt rsa_keygen_bits:4096 \
+    ///     -pkeyopt rsa_keygen_pubexp:65537 | \
+    ///     openssl pkcs8 -topk8 -nocrypt -outform der > rsa-4096-private-key.pk8
+    /// ```
+    ///
+    /// ## ECDSA
+    ///
+    /// ```bash
+    /// $ umask 077
+    /// $ openssl genpkey -algorithm EC \
+    ///     -pkeyopt ec_paramgen_curve:P-256 \
+    ///     -pkeyopt ec_param_enc:named_curve | \
+    ///     openssl pkcs8 -topk8 -nocrypt -outform der > ecdsa-p256-private-key.pk8
+    /// ```
+    pub fn from_pkcs8(der_key: &[u8], scheme: SignatureScheme) -> Result<Self> {
+        let typ = pkcs8_key_type(der_key).map_err(|e| {
+            Error::Opaque(format!("Key was neither Ed25519, RSA, nor ECDSA: {:?}", e))
+        })?;
+
+        if !typ.supports(&scheme) {
+            return Err(Error::IllegalArgument(format!(
+                "Cannot use signature scheme {:?} with {:?} keys",
+                scheme, typ
+            )));
+        }
+
+        let private = default_provider().key_pair_from_pkcs8(&typ, der_key)?;
+        let public = PublicKey {
+            key_id: calculate_key_id(&write_spki(private.public_key(), &typ)?)?,
+            value: PublicKeyValue(private.public_key().to_vec()),
+            typ,
+            scheme,
+            extensions: HashMap::new(),
+        };
+
+        Ok(PrivateKey { private, public })
+    }
+
+    /// Create a private key from a PEM encoded, unencrypted PKCS#8 key (`-----BEGIN PRIVATE
+    /// KEY-----`).
+    ///
+    /// See `PrivateKey::from_pkcs8` for more information.
+    pub fn from_pem(pem: &str, scheme: SignatureScheme) -> Result<Self> {
+        Self::from_pkcs8(&pem::decode(PKCS8_PEM_LABEL, pem)?, scheme)
+    }
+
+    /// Create a private key from a PBES2 encrypted PKCS#8 key (`EncryptedPrivateKeyInfo`) in DER
+    /// format.
+    ///
+    /// Keys derived with PBKDF2 using HMAC-SHA1, HMAC-SHA256, or HMAC-SHA512 and encrypted with
+    /// AES-128-CBC or AES-256-CBC are supported. These can be generated with:
+    ///
+    /// ```bash
+    /// $ openssl pkcs8 -topk8 -inform der -in private-key.pk8 \
+    ///     -v2 aes-256-cbc -v2prf hmacWithSHA256 -outform der > private-key.pk8.enc
+    /// ```
+    pub fn from_encrypted_pkcs8(
+        der_key: &[u8],
+        passphrase: &[u8],
+        scheme: SignatureScheme,
+    ) -> Result<Self> {
+        Self::from_pkcs8(&pkcs5::decrypt(der_key, passphrase)?, scheme)
+    }
+
+    /// Create a private key from a PEM encoded, PBES2 encrypted PKCS#8 key (`-----BEGIN
+    /// ENCRYPTED PRIVATE KEY-----`).
+    ///
+    /// See `PrivateKey::from_encrypted_pkcs8` for more information.
+    pub fn from_encrypted_pem(
+        pem: &str,
+        passphrase: &[u8],
+        scheme: SignatureScheme,
+    ) -> Result<Self> {
+        let der_key = pem::decode(ENCRYPTED_PKCS8_PEM_LABEL, pem)?;
+        Self::from_encrypted_pkcs8(&der_key, passphrase, scheme)
+    }
+
+    /// Encode an unencrypted PKCS#8 key, such as one returned by `PrivateKey::new`, as PEM.
+    pub fn pkcs8_to_pem(der_key: &[u8]) -> String {
+        pem::encode(PKCS8_PEM_LABEL, der_key)
+    }
+
+    /// Encrypt an unencrypted PKCS#8 key, such as one returned by `PrivateKey::new`, with the
+    /// given passphrase. The key is encrypted with PBES2 using PBKDF2-HMAC-SHA256 and AES-256-CBC
+    /// and returned as an `EncryptedPrivateKeyInfo` in DER format.
+    pub fn encrypt_pkcs8(der_key: &[u8], passphrase: &[u8]) -> Result<Vec<u8>> {
+        pkcs5::encrypt(der_key, passphrase)
+    }
+
+    /// Encrypt an unencrypted PKCS#8 key with the given passphrase and encode it as PEM.
+    ///
+    /// See `PrivateKey::encrypt_pkcs8` for more information.
+    pub fn encrypt_pkcs8_to_pem(der_key: &[u8], passphrase: &[u8]) -> Result<String> {
+        let encrypted = pkcs5::encrypt(der_key, passphrase)?;
+        Ok(pem::encode(ENCRYPTED_PKCS8_PEM_LABEL, &encrypted))
+    }
+
+    /// Sign a message.
+    pub fn sign(&self, msg: &[u8]) -> Result<Signature> {
+        let value = self.private.sign(&self.public.scheme, msg)?;
+        Ok(Signature {
+            key_id: self.key_id().clone(),
+            value: SignatureValue(value),
+        })
+    }
+
+    /// Return the public component of the key.
+    pub fn public(&self) -> &PublicKey {
+        &self.public
+    }
+
+    /// Return the key ID of the public key.
+    pub fn key_id(&self) -> &KeyId {
+        &self.public.key_id
+    }
+}
+
+/// A source of signatures over TUF metadata.
+///
+/// This abstracts over where the private key material lives, so that metadata can be signed by
+/// keys held in an HSM, a remote signing service, or a hardware token as well as by a local
+/// `PrivateKey`.
+///
+/// Signers must be `Send` and `Sync`, and so must the futures they return, so that signing can be
+/// driven from a multithreaded executor.
+pub trait Signer: Send + Sync {
+    /// The public key corresponding to the signing key.
+    fn public(&self) -> &PublicKey;
+
+    /// The key ID of the signing key.
+    fn key_id(&self) -> &KeyId {
+        self.public().key_id()
+    }
+
+    /// The signature scheme used by this signer.
+    fn scheme(&self) -> &SignatureScheme {
+        self.public().scheme()
+    }
+
+    /// Sign a message.
+    fn sign<'a>(&'a self, msg: &'a [u8]) -> SignFuture<'a>;
+}
+
+/// The future returned by `Signer::sign`.
+pub type SignFuture<'a> = Pin<Box<dyn Future<Output = Result<Signature>> + Send + 'a>>;
+
+impl Signer for PrivateKey {
+    fn public(&self) -> &PublicKey {
+        &self.public
+    }
+
+    fn sign<'a>(&'a self, msg: &'a [u8]) -> SignFuture<'a> {
+        Box::pin(async move { PrivateKey::sign(self, msg) })
+    }
+}
+
+/// A structure containing information about a public key.
+#[derive(Clone, Debug)]
+pub struct PublicKey {
+    typ: KeyType,
+    key_id: KeyId,
+    scheme: SignatureScheme,
+    value: PublicKeyValue,
+    extensions: HashMap<String, ExtensionValue>,
+}
+
+impl PublicKey {
+    /// Parse DER bytes as an SPKI key.
+    ///
+    /// See the documentation on `KeyValue` for more information on SPKI.
+    pub fn from_spki(der_bytes: &[u8], scheme: SignatureScheme) -> Result<Self> {
+        let input = Input::from(der_bytes);
+
+        let (typ, value) = input.read_all(derp::Error::Read, |input| {
+            derp::nested(input, Tag::Sequence, |input| {
+                let typ = derp::nested(input, Tag::Sequence, |input| {
+                    let typ = derp::expect_tag_and_get_value(input, Tag::Oid)?;
+
+                    if typ.as_slice_less_safe() == ECDSA_SPKI_OID {
+                        // for ECDSA this is the named curve
+                        let curve = derp::expect_tag_and_get_value(input, Tag::Oid)?;
+                        return KeyType::from_curve_oid(curve.as_slice_less_safe())
+                            .map_err(|_| derp::Error::WrongValue);
+                    }
+
+                    let typ = KeyType::from_oid(typ.as_slice_less_safe())
+                        .map_err(|_| derp::Error::WrongValue)?;
+
+                    // for RSA / ed25519 this is null, so don't both parsing it. RFC 8410 says
+                    // it is absent for ed25519, which is what openssl emits.
+                    if !input.at_end() {
+                        derp::read_null(input)?;
+                    }
+                    Ok(typ)
+                })?;
+                let value = derp::bit_string_with_no_unused_bits(input)?;
+                Ok((typ, value.as_slice_less_safe().to_vec()))
+            })
+        })?;
+
+        if !typ.supports(&scheme) {
+            return Err(Error::IllegalArgument(format!(
+                "Cannot use signature scheme {:?} with {:?} keys",
+                scheme, typ
+            )));
+        }
+
+        let key_id = calculate_key_id(der_bytes)?;
+        Ok(PublicKey {
+            typ,
+            key_id,
+            scheme,
+            value: PublicKeyValue(value),
+            extensions: HashMap::new(),
+        })
+    }
+
+    /// Write the public key as SPKI DER bytes.
+    ///
+    /// See the documentation on `KeyValue` for more information on SPKI.
+    pub fn as_spki(&self) -> Result<Vec<u8>> {
+        Ok(write_spki(&self.value.0, &self.typ)?)
+    }
+
+    /// Parse a PEM encoded SPKI key (`-----BEGIN PUBLIC KEY-----`).
+    pub fn from_pem(pem: &str, scheme: SignatureScheme) -> Result<Self> {
+        Self::from_spki(&pem::decode(SPKI_PEM_LABEL, pem)?, scheme)
+    }
+
+    /// Write the public key as PEM encoded SPKI.
+    pub fn as_pem(&self) -> Result<String> {
+        Ok(pem::encode(SPKI_PEM_LABEL, &self.as_spki()?))
+    }
+
+    /// An immutable reference to the key's type.
+    pub fn typ(&self) -> &KeyType {
+        &self.typ
+    }
+
+    /// An immutable referece to the key's authorized signing scheme.
+    pub fn scheme(&self) -> &SignatureScheme {
+        &self.scheme
+    }
+
+    /// An immutable reference to the key's ID.
+    pub fn key_id(&self) -> &KeyId {
+        &self.key_id
+    }
+
+    /// Fields of the key's metadata that this library has no model for, such as
+    /// `keyid_hash_algorithms`. They do not take part in comparing keys.
+    pub fn extensions(&self) -> &HashMap<String, ExtensionValue> {
+        &self.extensions
+    }
+
+    /// Replace the unmodeled fields written out alongside this key.
+    pub fn with_extensions(mut self, extensions: HashMap<String, ExtensionValue>) -> Self {
+        self.extensions = extensions;
+        self
+    }
+
+    /// Use this key to verify a message with a signature.
+    pub fn verify(&self, msg: &[u8], sig: &Signature) -> Result<()> {
+        default_provider().verify(&self.scheme, &self.value.0, msg, &sig.value.0)
+    }
+
+    /// Fail if the key's scheme is known but the crypto provider cannot verify it, so that
+    /// metadata listing such a key is rejected up front rather than failing every signature
+    /// check. Unknown schemes are left for signature verification to reject.
+    pub(crate) fn check_supported(&self) -> Result<()> {
+        match self.scheme {
+            SignatureScheme::Unknown(_) => Ok(()),
+            ref scheme if default_provider().supports(scheme) => Ok(()),
+            ref scheme => Err(Error::UnsupportedKeyFormat(format!(
+                "Key {:?} uses signature scheme {:?}, which the crypto provider does not support",
+                self.key_id, scheme
+            ))),
+        }
+    }
+}
+
+impl PartialEq for PublicKey {
+    fn eq(&self, other: &Self) -> bool {
+        // the other two fields are derived from this one, we ignore them
+        self.value == other.value
+    }
+}
+
+impl Eq for PublicKey {}
+
+impl Ord for PublicKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key_id.cmp(&other.key_id)
+    }
+}
+
+impl PartialOrd for PublicKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.key_id.cmp(&other.key_id))
+    }
+}
+
+impl hash::Hash for PublicKey {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        // the other two fields are derived from this one, we ignore them
+        self.value.hash(state);
+    }
+}
+
+impl Serialize for PublicKey {
+    fn serialize<S>(&self, ser: S) -> ::std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let bytes = self
+            .as_spki()
+            .map_err(|e| SerializeError::custom(format!("Couldn't write key as SPKI: {:?}", e)))?;
+        shims::PublicKey::new(
+            self.typ.clone(),
+            self.scheme.clone(),
+            &bytes,
+            &self.extensions,
+        )
+        .map_err(|e| SerializeError::custom(format!("{:?}", e)))?
+        .serialize(ser)
+    }
+}
+
+impl<'de> Deserialize<'de> for PublicKey {
+    fn deserialize<D: Deserializer<'de>>(de: D) -> ::std::result::Result<Self, D::Error> {
+        let intermediate: shims::PublicKey = Deserialize::deserialize(de)?;
+        let bytes = BASE64URL
+            .decode(intermediate.public_key().as_bytes())
+            .map_err(|e| DeserializeError::custom(format!("{:?}", e)))?;
+
+        let key = PublicKey::from_spki(&bytes, intermediate.scheme().clone()).map_err(|e| {
+            DeserializeError::custom(format!("Couldn't parse key as SPKI: {:?}", e))
+        })?;
+
+        if intermediate.typ() != &key.typ {
+            return Err(DeserializeError::custom(format!(
+                "Key type listed in the metadata did not match the type extrated \
+                 from the key. {:?} vs. {:?}",
+                intermediate.typ(),
+                key.typ,
+            )));
+        }
+
+        Ok(key.with_extensions(intermediate.into_extensions()))
+    }
+}
+
+#[derive(Clone, PartialEq, Hash, Eq)]
+struct PublicKeyValue(Vec<u8>);
+
+impl Debug for PublicKeyValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "PublicKeyValue {{ \"{}\" }}", BASE64URL.encode(&self.0))
+    }
+}
+
+/// A structure that contains a `Signature` and associated data for verifying it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Signature {
+    key_id: KeyId,
+    value: SignatureValue,
+}
+
+impl Signature {
+    /// An immutable reference to the `KeyId` of the key that produced the signature.
+    pub fn key_id(&self) -> &KeyId {
+        &self.key_id
+    }
+
+    /// An immutable reference to the `SignatureValue`.
+    pub fn value(&self) -> &SignatureValue {
+        &self.value
+    }
+}
+
+/// The available hash algorithms.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HashAlgorithm {
+    /// SHA256 as describe in [RFC-6234](https://tools.ietf.org/html/rfc6234)
+    #[serde(rename = "sha256")]
+    Sha256,
+    /// SHA384 as describe in [RFC-6234](https://tools.ietf.org/html/rfc6234)
+    #[serde(rename = "sha384")]
+    Sha384,
+    /// SHA512 as describe in [RFC-6234](https://tools.ietf.org/html/rfc6234)
+    #[serde(rename = "sha512")]
+    Sha512,
+    /// SHA512/256 as describe in [FIPS 180-4](https://doi.org/10.6028/NIST.FIPS.180-4)
+    #[serde(rename = "sha512_256")]
+    Sha512Trunc256,
+    /// BLAKE2b with a 512 bit digest as described in
+    /// [RFC-7693](https://tools.ietf.org/html/rfc7693)
+    #[serde(rename = "blake2b")]
+    Blake2b,
+    /// Placeholder for an unknown hash algorithm.
+    Unknown(String),
+}
+
+/// Wrapper for the value of a hash digest.
+#[derive(Clone, Eq, PartialEq, Hash)]
+pub struct HashValue(Vec<u8>);
+
+impl HashValue {
+    /// Create a new `HashValue` from the given digest bytes.
+    pub fn new(bytes: Vec<u8>) -> Self {
+        HashValue(bytes)
+    }
+
+    /// An immutable reference to the bytes of the hash value.
+    pub fn value(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Serialize for HashValue {
+    fn serialize<S>(&self, ser: S) -> ::std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        BASE64URL.encode(&self.0).serialize(ser)
+    }
+}
+
+impl<'de> Deserialize<'de> for HashValue {
+    fn deserialize<D: Deserializer<'de>>(de: D) -> ::std::result::Result<Self, D::Error> {
+        let s: String = Deserialize::deserialize(de)?;
+        let bytes = BASE64URL
+            .decode(s.as_bytes())
+            .map_err(|e| DeserializeError::custom(format!("Base64: {:?}", e)))?;
+        Ok(HashValue(bytes))
+    }
+}
+
+impl Debug for HashValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "HashValue {{ \"{}\" }}", BASE64URL.encode(&self.0))
+    }
+}
+
+impl Display for HashValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", BASE64URL.encode(&self.0))
+    }
+}
+
+fn write_spki(public: &[u8], key_type: &KeyType) -> ::std::result::Result<Vec<u8>, derp::Error> {
+    let mut output = Vec::new();
+    {
+        let mut der = Der::new(&mut output);
+        der.sequence(|der| {
+            der.sequence(|der| match (key_type.as_oid().ok(), key_type.curve_oid()) {
+                (Some(tag), Some(curve)) => {
+                    der.element(Tag::Oid, tag)?;
+                    der.element(Tag::Oid, curve)
+                }
+                (Some(tag), None) => {
+                    der.element(Tag::Oid, tag)?;
+                    der.null()
+                }
+                (None, _) => Err(derp::Error::WrongValue),
+            })?;
+            der.bit_string(0, public)
+        })?;
+    }
+
+    Ok(output)
+}
+
+/// Read the key type from the `privateKeyAlgorithm` of a PKCS#8 key.
+fn pkcs8_key_type(der_key: &[u8]) -> Result<KeyType> {
+    let input = Input::from(der_key);
+    let typ = input.read_all(derp::Error::Read, |input| {
+        derp::nested(input, Tag::Sequence, |input| {
+            // v1 or v2
+            if derp::small_nonnegative_integer(input)? > 1 {
+                return Err(derp::Error::WrongValue);
+            }
+
+            let typ = derp::nested(input, Tag::Sequence, |input| {
+                let typ = derp::expect_tag_and_get_value(input, Tag::Oid)?;
+                if typ.as_slice_less_safe() == ECDSA_SPKI_OID {
+                    let curve = derp::expect_tag_and_get_value(input, Tag::Oid)?;
+                    return Ok(KeyType::from_curve_oid(curve.as_slice_less_safe()));
+                }
+
+                let _ = input.skip_to_end();
+                Ok(KeyType::from_oid(typ.as_slice_less_safe()))
+            })?;
+
+            let _ = input.skip_to_end();
+            Ok(typ)
+        })
+    })?;
+    typ
+}
+
+/// Read the version of a PKCS#8 document. Version 0 (v1) documents do not carry the public key.
+#[cfg_attr(not(feature = "ring"), allow(dead_code))]
+fn pkcs8_version(der_key: &[u8]) -> ::std::result::Result<u8, derp::Error> {
+    let input = Input::from(der_key);
+    input.read_all(derp::Error::Read, |input| {
+        derp::nested(input, Tag::Sequence, |input| {
+            let version = derp::small_nonnegative_integer(input)?;
+            let _ = input.skip_to_end();
+            Ok(version)
+        })
+    })
+}
+
+#[cfg_attr(not(feature = "ring"), allow(dead_code))]
+fn extract_rsa_pub_from_pkcs8(der_key: &[u8]) -> ::std::result::Result<Vec<u8>, derp::Error> {
+    let input = Input::from(der_key);
+    input.read_all(derp::Error::Read, |input| {
+        derp::nested(input, Tag::Sequence, |input| {
+            if derp::small_nonnegative_integer(input)? != 0 {
+                return Err(derp::Error::WrongValue);
+            }
+
+            derp::nested(input, Tag::Sequence, |input| {
+                let actual_alg_id = derp::expect_tag_and_get_value(input, Tag::Oid)?;
+                if actual_alg_id.as_slice_less_safe() != RSA_SPKI_OID {
+                    return Err(derp::Error::WrongValue);
+                }
+                let _ = derp::expect_tag_and_get_value(input, Tag::Null)?;
+                Ok(())
+            })?;
+
+            derp::nested(input, Tag::OctetString, |input| {
+                derp::nested(input, Tag::Sequence, |input| {
+                    if derp::small_nonnegative_integer(input)? != 0 {
+                        return Err(derp::Error::WrongValue);
+                    }
+
+                    let n = derp::positive_integer(input)?;
+                    let e = derp::positive_integer(input)?;
+                    let _ = input.skip_to_end();
+                    write_pkcs1(n.as_slice_less_safe(), e.as_slice_less_safe())
+                })
+            })
+        })
+    })
+}
+
+#[cfg_attr(not(feature = "ring"), allow(dead_code))]
+fn extract_ecdsa_pub_from_pkcs8(
+    der_key: &[u8],
+    key_type: &KeyType,
+) -> ::std::result::Result<Vec<u8>, derp::Error> {
+    let expected_curve = key_type.curve_oid().ok_or(derp::Error::WrongValue)?;
+    let input = Input::from(der_key);
+    input.read_all(derp::Error::Read, |input| {
+        derp::nested(input, Tag::Sequence, |input| {
+            if derp::small_nonnegative_integer(input)? != 0 {
+                return Err(derp::Error::WrongValue);
+            }
+
+            derp::nested(input, Tag::Sequence, |input| {
+                let actual_alg_id = derp::expect_tag_and_get_value(input, Tag::Oid)?;
+                if actual_alg_id.as_slice_less_safe() != ECDSA_SPKI_OID {
+                    return Err(derp::Error::WrongValue);
+                }
+                let actual_curve = derp::expect_tag_and_get_value(input, Tag::Oid)?;
+                if actual_curve.as_slice_less_safe() != expected_curve {
+                    return Err(derp::Error::WrongValue);
+                }
+                Ok(())
+            })?;
+
+            derp::nested(input, Tag::OctetString, |input| {
+                derp::nested(input, Tag::Sequence, |input| {
+                    if derp::small_nonnegative_integer(input)? != 1 {
+                        return Err(derp::Error::WrongValue);
+                    }
+
+                    let _ = derp::expect_tag_and_get_value(input, Tag::OctetString)?;
+
+                    // the curve parameters are optional, and were already checked above
+                    if input.peek(Tag::ContextSpecificConstructed0 as u8) {
+                        let _ = derp::expect_tag_and_get_value(
+                            input,
+                            Tag::ContextSpecificConstructed0,
+                        )?;
+                    }
+
+                    derp::nested(input, Tag::ContextSpecificConstructed1, |input| {
+                        let public = derp::bit_string_with_no_unused_bits(input)?;
+                        Ok(public.as_slice_less_safe().to_vec())
+                    })
+                })
+            })
+        })
+    })
+}
+
+/// Write an RSA private key as PKCS#8 DER, where `values` are the `RSAPrivateKey` fields `n`,
+/// `e`, `d`, `p`, `q`, `d mod (p - 1)`, `d mod (q - 1)`, and `q^-1 mod p` in that order.
+#[cfg(feature = "rsa")]
+fn write_rsa_pkcs8(values: &[&BigUint]) -> ::std::result::Result<Vec<u8>, derp::Error> {
+    let mut private_key = Vec::new();
+    {
+        let mut der = Der::new(&mut private_key);
+        der.sequence(|der| {
+            // two-prime version
+            der.integer(&[0])?;
+            for value in values {
+                der.positive_integer(&value.to_bytes_be())?;
+            }
+            Ok(())
+        })?;
+    }
+
+    let mut output = Vec::new();
+    {
+        let mut der = Der::new(&mut output);
+        der.sequence(|der| {
+            der.integer(&[0])?;
+            der.sequence(|der| {
+                der.element(Tag::Oid, RSA_SPKI_OID)?;
+                der.null()
+            })?;
+            der.octet_string(&private_key)
+        })?;
+    }
+
+    Ok(output)
+}
+
+fn write_pkcs1(n: &[u8], e: &[u8]) -> ::std::result::Result<Vec<u8>, derp::Error> {
+    let mut output = Vec::new();
+    {
+        let mut der = Der::new(&mut output);
+        der.sequence(|der| {
+            der.positive_integer(n)?;
+            der.positive_integer(e)
+        })?;
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use data_encoding::HEXLOWER;
+    use futures::executor::block_on;
+    use serde_json::{self, json};
+
+    const RSA_2048_PK8: &'static [u8] = include_bytes!("../../tests/rsa/rsa-2048.pk8.der");
+    const RSA_2048_SPKI: &'static [u8] = include_bytes!("../../tests/rsa/rsa-2048.spki.der");
+    const RSA_2048_PKCS1: &'static [u8] = include_bytes!("../../tests/rsa/rsa-2048.pkcs1.der");
+
+    const RSA_4096_PK8: &'static [u8] = include_bytes!("../../tests/rsa/rsa-4096.pk8.der");
+    const RSA_4096_SPKI: &'static [u8] = include_bytes!("../../tests/rsa/rsa-4096.spki.der");
+    const RSA_4096_PKCS1: &'static [u8] = include_bytes!("../../tests/rsa/rsa-4096.pkcs1.der");
+
+    const ED25519_PK8: &'static [u8] = include_bytes!("../../tests/ed25519/ed25519-1.pk8.der");
+    const ED25519_SPKI: &'static [u8] = include_bytes!("../../tests/ed25519/ed25519-1.spki.der");
+
+    const ECDSA_P256_PK8: &'static [u8] = include_bytes!("../../tests/ecdsa/ecdsa-p256.pk8.der");
+    const ECDSA_P256_SPKI: &'static [u8] = include_bytes!("../../tests/ecdsa/ecdsa-p256.spki.der");
+
+    #[cfg(feature = "ring")]
+    const ECDSA_P384_PK8: &'static [u8] = include_bytes!("../../tests/ecdsa/ecdsa-p384.pk8.der");
+    const ECDSA_P384_SPKI: &'static [u8] = include_bytes!("../../tests/ecdsa/ecdsa-p384.spki.der");
+
+    const PEM_PASSPHRASE: &[u8] = b"correct horse battery staple";
+
+    const RSA_2048_SPKI_PEM: &str = include_str!("../../tests/pem/rsa-2048.spki.pem");
+    #[cfg(feature = "ring")]
+    const RSA_2048_PK8_PEM: &str = include_str!("../../tests/pem/rsa-2048.pk8.pem");
+    #[cfg(feature = "ring")]
+    const RSA_2048_PK8_ENC_PEM: &str = include_str!("../../tests/pem/rsa-2048.pk8.enc.pem");
+    #[cfg(feature = "ring")]
+    const RSA_2048_PK8_ENC_AES128_SHA1_PEM: &str =
+        include_str!("../../tests/pem/rsa-2048.pk8.enc-aes128-sha1.pem");
+
+    const ED25519_SPKI_PEM: &str = include_str!("../../tests/pem/ed25519-1.spki.pem");
+    #[cfg(feature = "ring")]
+    const ED25519_PK8_PEM: &str = include_str!("../../tests/pem/ed25519-1.pk8.pem");
+    #[cfg(feature = "ring")]
+    const ED25519_PK8_ENC_PEM: &str = include_str!("../../tests/pem/ed25519-1.pk8.enc.pem");
+
+    const ECDSA_P256_SPKI_PEM: &str = include_str!("../../tests/pem/ecdsa-p256.spki.pem");
+    const ECDSA_P256_PK8_PEM: &str = include_str!("../../tests/pem/ecdsa-p256.pk8.pem");
+    #[cfg(feature = "ring")]
+    const ECDSA_P256_PK8_ENC_PEM: &str = include_str!("../../tests/pem/ecdsa-p256.pk8.enc.pem");
+
+    #[test]
+    fn parse_rsa_2048_spki() {
+        let key = PublicKey::from_spki(RSA_2048_SPKI, SignatureScheme::RsaSsaPssSha256).unwrap();
+        assert_eq!(key.typ, KeyType::Rsa);
+    }
+
+    #[test]
+    fn parse_rsa_4096_spki() {
+        let key = PublicKey::from_spki(RSA_4096_SPKI, SignatureScheme::RsaSsaPssSha256).unwrap();
+        assert_eq!(key.typ, KeyType::Rsa);
+    }
+
+    #[test]
+    #[cfg(feature = "ring")]
+    fn rsa_2048_read_pkcs8_and_sign() {
+        let msg = b"test";
+
+        let key = PrivateKey::from_pkcs8(RSA_2048_PK8, SignatureScheme::RsaSsaPssSha256).unwrap();
+        let sig = key.sign(msg).unwrap();
+        key.public.verify(msg, &sig).unwrap();
+
+        let key = PrivateKey::from_pkcs8(RSA_2048_PK8, SignatureScheme::RsaSsaPssSha512).unwrap();
+        let sig = key.sign(msg).unwrap();
+        key.public.verify(msg, &sig).unwrap();
+
+        let key =
+            PrivateKey::from_pkcs8(RSA_2048_PK8, SignatureScheme::RsaSsaPkcs1v15Sha256).unwrap();
+        let sig = key.sign(msg).unwrap();
+        key.public.verify(msg, &sig).unwrap();
+
+        let key =
+            PrivateKey::from_pkcs8(RSA_2048_PK8, SignatureScheme::RsaSsaPkcs1v15Sha512).unwrap();
+        let sig = key.sign(msg).unwrap();
+        key.public.verify(msg, &sig).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "ring")]
+    fn rsa_4096_read_pkcs8_and_sign() {
+        let msg = b"test";
+
+        let key = PrivateKey::from_pkcs8(RSA_4096_PK8, SignatureScheme::RsaSsaPssSha256).unwrap();
+        let sig = key.sign(msg).unwrap();
+        key.public.verify(msg, &sig).unwrap();
+
+        let key = PrivateKey::from_pkcs8(RSA_4096_PK8, SignatureScheme::RsaSsaPssSha512).unwrap();
+        let sig = key.sign(msg).unwrap();
+        key.public.verify(msg, &sig).unwrap();
+
+        let key =
+            PrivateKey::from_pkcs8(RSA_4096_PK8, SignatureScheme::RsaSsaPkcs1v15Sha256).unwrap();
+        let sig = key.sign(msg).unwrap();
+        key.public.verify(msg, &sig).unwrap();
+
+        let key =
+            PrivateKey::from_pkcs8(RSA_4096_PK8, SignatureScheme::RsaSsaPkcs1v15Sha512).unwrap();
+        let sig = key.sign(msg).unwrap();
+        key.public.verify(msg, &sig).unwrap();
+    }
+
+    #[test]
+    fn rsa_pkcs1v15_verify_with_spki() {
+        let msg = b"test";
+
+        let key =
+            PrivateKey::from_pkcs8(RSA_2048_PK8, SignatureScheme::RsaSsaPkcs1v15Sha256).unwrap();
+        let sig = key.sign(msg).unwrap();
+
+        // PKCS#1 v1.5 signatures are deterministic
+        assert_eq!(sig, key.sign(msg).unwrap());
+
+        let public =
+            PublicKey::from_spki(RSA_2048_SPKI, SignatureScheme::RsaSsaPkcs1v15Sha256).unwrap();
+        assert_eq!(public.key_id(), key.key_id());
+        public.verify(msg, &sig).unwrap();
+
+        // the same signature must not verify under a different scheme
+        let public =
+            PublicKey::from_spki(RSA_2048_SPKI, SignatureScheme::RsaSsaPkcs1v15Sha512).unwrap();
+        assert!(public.verify(msg, &sig).is_err());
+        let public = PublicKey::from_spki(RSA_2048_SPKI, SignatureScheme::RsaSsaPssSha256).unwrap();
+        assert!(public.verify(msg, &sig).is_err());
+    }
+
+    #[test]
+    fn extract_pkcs1_from_rsa_2048_pkcs8() {
+        let res = extract_rsa_pub_from_pkcs8(RSA_2048_PK8).unwrap();
+        assert_eq!(res.as_slice(), RSA_2048_PKCS1);
+    }
+
+    #[test]
+    fn extract_pkcs1_from_rsa_4096_pkcs8() {
+        let res = extract_rsa_pub_from_pkcs8(RSA_4096_PK8).unwrap();
+        assert_eq!(res.as_slice(), RSA_4096_PKCS1);
+    }
+
+    #[test]
+    fn ed25519_read_pkcs8_and_sign() {
+        let key = PrivateKey::from_pkcs8(ED25519_PK8, SignatureScheme::Ed25519).unwrap();
+        let msg = b"test";
+
+        let sig = key.sign(msg).unwrap();
+
+        let public =
+            PublicKey::from_spki(&key.public.as_spki().unwrap(), SignatureScheme::Ed25519).unwrap();
+        public.verify(msg, &sig).unwrap();
+    }
+
+    #[test]
+    fn parse_ecdsa_p256_spki() {
+        let key = PublicKey::from_spki(ECDSA_P256_SPKI, SignatureScheme::EcdsaP256Sha256).unwrap();
+        assert_eq!(key.typ, KeyType::EcdsaP256);
+        assert_eq!(key.as_spki().unwrap().as_slice(), ECDSA_P256_SPKI);
+    }
+
+    #[test]
+    fn parse_ecdsa_p384_spki() {
+        let key = PublicKey::from_spki(ECDSA_P384_SPKI, SignatureScheme::EcdsaP384Sha384).unwrap();
+        assert_eq!(key.typ, KeyType::EcdsaP384);
+        assert_eq!(key.as_spki().unwrap().as_slice(), ECDSA_P384_SPKI);
+    }
+
+    #[test]
+    #[cfg(feature = "ring")]
+    fn ecdsa_p256_read_pkcs8_and_sign() {
+        let key = PrivateKey::from_pkcs8(ECDSA_P256_PK8, SignatureScheme::EcdsaP256Sha256).unwrap();
+        let msg = b"test";
+
+        let sig = key.sign(msg).unwrap();
+
+        let public =
+            PublicKey::from_spki(ECDSA_P256_SPKI, SignatureScheme::EcdsaP256Sha256).unwrap();
+        assert_eq!(key.key_id(), public.key_id());
+        public.verify(msg, &sig).unwrap();
+        assert!(public.verify(b"tset", &sig).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "ring")]
+    fn ecdsa_p384_read_pkcs8_and_sign() {
+        let key = PrivateKey::from_pkcs8(ECDSA_P384_PK8, SignatureScheme::EcdsaP384Sha384).unwrap();
+        let msg = b"test";
+
+        let sig = key.sign(msg).unwrap();
+
+        let public =
+            PublicKey::from_spki(ECDSA_P384_SPKI, SignatureScheme::EcdsaP384Sha384).unwrap();
+        assert_eq!(key.key_id(), public.key_id());
+        public.verify(msg, &sig).unwrap();
+        assert!(public.verify(b"tset", &sig).is_err());
+    }
+
+    #[test]
+    fn ecdsa_wrong_scheme() {
+        assert!(PrivateKey::from_pkcs8(ECDSA_P256_PK8, SignatureScheme::EcdsaP384Sha384).is_err());
+        assert!(PrivateKey::from_pkcs8(ECDSA_P256_PK8, SignatureScheme::Ed25519).is_err());
+        assert!(PrivateKey::from_pkcs8(RSA_2048_PK8, SignatureScheme::EcdsaP256Sha256).is_err());
+    }
+
+    #[test]
+    fn ecdsa_spki_wrong_scheme() {
+        assert!(PublicKey::from_spki(ECDSA_P256_SPKI, SignatureScheme::EcdsaP384Sha384).is_err());
+        assert!(PublicKey::from_spki(ECDSA_P384_SPKI, SignatureScheme::EcdsaP256Sha256).is_err());
+        assert!(PublicKey::from_spki(ECDSA_P256_SPKI, SignatureScheme::Ed25519).is_err());
+        assert!(PublicKey::from_spki(RSA_2048_SPKI, SignatureScheme::EcdsaP256Sha256).is_err());
+    }
+
+    #[test]
+    fn private_key_as_signer() {
+        let key = PrivateKey::from_pkcs8(ED25519_PK8, SignatureScheme::Ed25519).unwrap();
+        let signer: &dyn Signer = &key;
+        let msg = b"test";
+
+        assert_eq!(Signer::key_id(signer), key.key_id());
+        assert_eq!(signer.scheme(), &SignatureScheme::Ed25519);
+
+        let sig = block_on(signer.sign(msg)).unwrap();
+        assert_eq!(sig, key.sign(msg).unwrap());
+        signer.public().verify(msg, &sig).unwrap();
+
+        fn assert_send<T: Send>(_: &T) {}
+        assert_send(&signer.sign(msg));
+    }
+
+    #[test]
+    fn serde_key_id() {
+        let s = "T5vfRrM1iHpgzGwAHe7MbJH_7r4chkOAphV3OPCCv0I=";
+        let jsn = json!(s);
+        let parsed: KeyId = serde_json::from_str(&format!("\"{}\"", s)).unwrap();
+        assert_eq!(parsed, KeyId::from_string(s).unwrap());
+        let encoded = serde_json::to_value(&parsed).unwrap();
+        assert_eq!(encoded, jsn);
+    }
+
+    #[test]
+    fn serde_signature_value() {
+        let s = "T5vfRrM1iHpgzGwAHe7MbJH_7r4chkOAphV3OPCCv0I=";
+        let jsn = json!(s);
+        let parsed: SignatureValue = serde_json::from_str(&format!("\"{}\"", s)).unwrap();
+        assert_eq!(parsed, SignatureValue::from_string(s).unwrap());
+        let encoded = serde_json::to_value(&parsed).unwrap();
+        assert_eq!(encoded, jsn);
+    }
+
+    #[test]
+    fn serde_hash_algorithm() {
+        for (alg, name) in &[
+            (HashAlgorithm::Sha256, "sha256"),
+            (HashAlgorithm::Sha384, "sha384"),
+            (HashAlgorithm::Sha512, "sha512"),
+            (HashAlgorithm::Sha512Trunc256, "sha512_256"),
+            (HashAlgorithm::Blake2b, "blake2b"),
+        ] {
+            let encoded = serde_json::to_value(alg).unwrap();
+            assert_eq!(encoded, json!(name));
+            let decoded: HashAlgorithm = serde_json::from_value(encoded).unwrap();
+            assert_eq!(&decoded, alg);
+        }
+    }
+
+    #[test]
+    fn calculate_hashes_known_answers() {
+        let expected = &[
+            (
+                HashAlgorithm::Sha256,
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            ),
+            (
+                HashAlgorithm::Sha384,
+                "cb00753f45a35e8bb5a03d699ac65007272c32ab0eded1631a8b605a43ff5bed\
+                 8086072ba1e7cc2358baeca134c825a7",
+            ),
+            (
+                HashAlgorithm::Sha512,
+                "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a\
+                 2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f",
+            ),
+            (
+                HashAlgorithm::Sha512Trunc256,
+                "53048e2681941ef99b2e29b76b4c7dabe4c2d0c634fc6d46e0e2f13107e7af23",
+            ),
+            (
+                HashAlgorithm::Blake2b,
+                "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+                 7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923",
+            ),
+        ];
+
+        let algs = expected
+            .iter()
+            .map(|(alg, _)| alg.clone())
+            .collect::<Vec<_>>();
+        let (size, hashes) = calculate_hashes(&b"abc"[..], &algs).unwrap();
+        assert_eq!(size, 3);
+
+        for (alg, hex) in expected {
+            let value = HashValue::new(HEXLOWER.decode(hex.as_bytes()).unwrap());
+            assert_eq!(hashes.get(alg), Some(&value), "{:?}", alg);
+        }
+
+        assert!(calculate_hashes(&b"abc"[..], &[HashAlgorithm::Unknown("md5".into())]).is_err());
+    }
+
+    #[test]
+    fn hash_preference_order() {
+        let hashes = HASH_ALG_PREFS
+            .iter()
+            .map(|alg| (alg.clone(), HashValue::new(vec![])))
+            .collect::<HashMap<_, _>>();
+        assert_eq!(hash_preference(&hashes).unwrap().0, &HashAlgorithm::Sha512);
+
+        let prefs = &[HashAlgorithm::Blake2b, HashAlgorithm::Sha512];
+        assert_eq!(
+            hash_preference_with(&hashes, prefs).unwrap().0,
+            &HashAlgorithm::Blake2b
+        );
+    }
+
+    #[test]
+    fn serde_rsa_public_key() {
+        let der = RSA_2048_SPKI;
+        let pub_key = PublicKey::from_spki(der, SignatureScheme::RsaSsaPssSha256).unwrap();
+        let encoded = serde_json::to_value(&pub_key).unwrap();
+        let jsn = json!({
+            "type": "rsa",
+            "scheme": "rsassa-pss-sha256",
+            "public_key": BASE64URL.encode(der),
+        });
+        assert_eq!(encoded, jsn);
+        let decoded: PublicKey = serde_json::from_value(encoded).unwrap();
+        assert_eq!(decoded, pub_key);
+    }
+
+    #[test]
+    fn serde_rsa_pkcs1v15_public_key() {
+        let der = RSA_2048_SPKI;
+        let pub_key = PublicKey::from_spki(der, SignatureScheme::RsaSsaPkcs1v15Sha512).unwrap();
+        let encoded = serde_json::to_value(&pub_key).unwrap();
+        let jsn = json!({
+            "type": "rsa",
+            "scheme": "rsassa-pkcs1v15-sha512",
+            "public_key": BASE64URL.encode(der),
+        });
+        assert_eq!(encoded, jsn);
+        let decoded: PublicKey = serde_json::from_value(encoded).unwrap();
+        assert_eq!(decoded, pub_key);
+        assert_eq!(decoded.scheme(), &SignatureScheme::RsaSsaPkcs1v15Sha512);
+    }
+
+    #[test]
+    fn serde_ed25519_public_key() {
+        let der = ED25519_SPKI;
+        let pub_key = PublicKey::from_spki(der, SignatureScheme::Ed25519).unwrap();
+        let encoded = serde_json::to_value(&pub_key).unwrap();
+        let jsn = json!({
+            "type": "ed25519",
+            "scheme": "ed25519",
+            "public_key": BASE64URL.encode(der),
+        });
+        assert_eq!(encoded, jsn);
+        let decoded: PublicKey = serde_json::from_value(encoded).unwrap();
+        assert_eq!(decoded, pub_key);
+    }
+
+    #[test]
+    fn serde_ecdsa_public_key() {
+        let der = ECDSA_P256_SPKI;
+        let pub_key = PublicKey::from_spki(der, SignatureScheme::EcdsaP256Sha256).unwrap();
+        let encoded = serde_json::to_value(&pub_key).unwrap();
+        let jsn = json!({
+            "type": "ecdsa-sha2-nistp256",
+            "scheme": "ecdsa-sha2-nistp256",
+            "public_key": BASE64URL.encode(der),
+        });
+        assert_eq!(encoded, jsn);
+        let decoded: PublicKey = serde_json::from_value(encoded).unwrap();
+        assert_eq!(decoded, pub_key);
+    }
+
+    #[test]
+    fn serde_signature() {
+        let key = PrivateKey::from_pkcs8(ED25519_PK8, SignatureScheme::Ed25519).unwrap();
+        let msg = b"test";
+        let sig = key.sign(msg).unwrap();
+        let encoded = serde_json::to_value(&sig).unwrap();
+        let jsn = json!({
+        "key_id": "qfrfBrkB4lBBSDEBlZgaTGS_SrE6UfmON9kP4i3dJFY=",
+        "value": "_k0Tsqc8Azod5_UQeyBfx7oOFWbLlbkjScrmqkU4lWATv-D3v5d8sHK7Z\
+            eh4K18zoFc_54gWKZoBfKW6VZ45DA==",
+        });
+        assert_eq!(encoded, jsn);
+
+        let decoded: Signature = serde_json::from_value(encoded).unwrap();
+        assert_eq!(decoded, sig);
+    }
+
+    #[test]
+    #[cfg(feature = "rsa")]
+    #[ignore] // generating a 4096 bit key takes a long time in debug builds
+    fn new_rsa_key() {
+        let bytes = PrivateKey::new(KeyType::Rsa).unwrap();
+        let key = PrivateKey::from_pkcs8(&bytes, SignatureScheme::RsaSsaPssSha256).unwrap();
+        assert_eq!(key.public().as_spki().unwrap().len(), RSA_4096_SPKI.len());
+    }
+
+    #[test]
+    #[cfg(feature = "rsa")]
+    fn new_rsa_key_with_size() {
+        let bytes = PrivateKey::new_rsa(2048).unwrap();
+        let key = PrivateKey::from_pkcs8(&bytes, SignatureScheme::RsaSsaPkcs1v15Sha256).unwrap();
+        assert_eq!(key.public().as_spki().unwrap().len(), RSA_2048_SPKI.len());
+
+        let msg = b"test";
+        let sig = key.sign(msg).unwrap();
+        key.public().verify(msg, &sig).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "rsa")]
+    fn new_rsa_key_bad_size() {
+        assert!(PrivateKey::new_rsa(1024).is_err());
+        assert!(PrivateKey::new_rsa(2047).is_err());
+        assert!(PrivateKey::new_rsa(8192).is_err());
+    }
+
+    #[test]
+    fn new_ed25519_key() {
+        let bytes = PrivateKey::new(KeyType::Ed25519).unwrap();
+        let _ = PrivateKey::from_pkcs8(&bytes, SignatureScheme::Ed25519).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "ring")]
+    fn new_ecdsa_key() {
+        let bytes = PrivateKey::new(KeyType::EcdsaP256).unwrap();
+        let _ = PrivateKey::from_pkcs8(&bytes, SignatureScheme::EcdsaP256Sha256).unwrap();
+
+        let bytes = PrivateKey::new(KeyType::EcdsaP384).unwrap();
+        let _ = PrivateKey::from_pkcs8(&bytes, SignatureScheme::EcdsaP384Sha384).unwrap();
+    }
+
+    #[test]
+    fn public_key_pem() {
+        let fixtures = &[
+            (
+                RSA_2048_SPKI_PEM,
+                RSA_2048_SPKI,
+                SignatureScheme::RsaSsaPssSha256,
+            ),
+            (ED25519_SPKI_PEM, ED25519_SPKI, SignatureScheme::Ed25519),
+            (
+                ECDSA_P256_SPKI_PEM,
+                ECDSA_P256_SPKI,
+                SignatureScheme::EcdsaP256Sha256,
+            ),
+        ];
+
+        for (pem, spki, scheme) in fixtures {
+            let key = PublicKey::from_pem(pem, scheme.clone()).unwrap();
+            let expected = PublicKey::from_spki(spki, scheme.clone()).unwrap();
+            assert_eq!(key, expected);
+
+            let round_trip = PublicKey::from_pem(&key.as_pem().unwrap(), scheme.clone()).unwrap();
+            assert_eq!(round_trip, key);
+        }
+
+        // openssl omits the NULL parameters for ed25519, so only these are byte-for-byte equal
+        // and have the same key ID
+        let key = PublicKey::from_pem(RSA_2048_SPKI_PEM, SignatureScheme::RsaSsaPssSha256).unwrap();
+        assert_eq!(key.as_pem().unwrap(), RSA_2048_SPKI_PEM);
+        let expected =
+            PublicKey::from_spki(RSA_2048_SPKI, SignatureScheme::RsaSsaPssSha256).unwrap();
+        assert_eq!(key.key_id(), expected.key_id());
+        let key =
+            PublicKey::from_pem(ECDSA_P256_SPKI_PEM, SignatureScheme::EcdsaP256Sha256).unwrap();
+        assert_eq!(key.as_pem().unwrap(), ECDSA_P256_SPKI_PEM);
+        let expected =
+            PublicKey::from_spki(ECDSA_P256_SPKI, SignatureScheme::EcdsaP256Sha256).unwrap();
+        assert_eq!(key.key_id(), expected.key_id());
+    }
+
+    #[test]
+    fn key_id_is_calculated_over_input_spki() {
+        let key = PublicKey::from_pem(ED25519_SPKI_PEM, SignatureScheme::Ed25519).unwrap();
+        let der = pem::decode(SPKI_PEM_LABEL, ED25519_SPKI_PEM).unwrap();
+        assert_eq!(key.key_id(), &calculate_key_id(&der).unwrap());
+        assert_ne!(der, key.as_spki().unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "ring")]
+    fn private_key_pem() {
+        let fixtures = &[
+            (
+                RSA_2048_PK8_PEM,
+                RSA_2048_PK8,
+                SignatureScheme::RsaSsaPssSha256,
+            ),
+            (ED25519_PK8_PEM, ED25519_PK8, SignatureScheme::Ed25519),
+            (
+                ECDSA_P256_PK8_PEM,
+                ECDSA_P256_PK8,
+                SignatureScheme::EcdsaP256Sha256,
+            ),
+        ];
+
+        for (pem, pk8, scheme) in fixtures {
+            let key = PrivateKey::from_pem(pem, scheme.clone()).unwrap();
+            let expected = PrivateKey::from_pkcs8(pk8, scheme.clone()).unwrap();
+            assert_eq!(key.public(), expected.public());
+            assert_eq!(key.key_id(), expected.key_id());
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "ring")]
+    fn encrypted_private_key_pem() {
+        let fixtures = &[
+            (
+                RSA_2048_PK8_ENC_PEM,
+                RSA_2048_PK8,
+                SignatureScheme::RsaSsaPssSha256,
+            ),
+            (
+                RSA_2048_PK8_ENC_AES128_SHA1_PEM,
+                RSA_2048_PK8,
+                SignatureScheme::RsaSsaPssSha256,
+            ),
+            (ED25519_PK8_ENC_PEM, ED25519_PK8, SignatureScheme::Ed25519),
+            (
+                ECDSA_P256_PK8_ENC_PEM,
+                ECDSA_P256_PK8,
+                SignatureScheme::EcdsaP256Sha256,
+            ),
+        ];
+
+        for (pem, pk8, scheme) in fixtures {
+            let key = PrivateKey::from_encrypted_pem(pem, PEM_PASSPHRASE, scheme.clone()).unwrap();
+            let expected = PrivateKey::from_pkcs8(pk8, scheme.clone()).unwrap();
+            assert_eq!(key.public(), expected.public());
+            assert_eq!(key.key_id(), expected.key_id());
+
+            assert!(PrivateKey::from_encrypted_pem(pem, b"wrong", scheme.clone()).is_err());
+            assert!(PrivateKey::from_pem(pem, scheme.clone()).is_err());
+        }
+    }
+
+    #[test]
+    fn encrypt_private_key_round_trip() {
+        let pem = PrivateKey::encrypt_pkcs8_to_pem(ED25519_PK8, PEM_PASSPHRASE).unwrap();
+        let key =
+            PrivateKey::from_encrypted_pem(&pem, PEM_PASSPHRASE, SignatureScheme::Ed25519).unwrap();
+        let expected = PrivateKey::from_pkcs8(ED25519_PK8, SignatureScheme::Ed25519).unwrap();
+        assert_eq!(key.public(), expected.public());
+
+        let der = PrivateKey::encrypt_pkcs8(RSA_2048_PK8, PEM_PASSPHRASE).unwrap();
+        let key = PrivateKey::from_encrypted_pkcs8(
+            &der,
+            PEM_PASSPHRASE,
+            SignatureScheme::RsaSsaPssSha256,
+        )
+        .unwrap();
+        let expected =
+            PrivateKey::from_pkcs8(RSA_2048_PK8, SignatureScheme::RsaSsaPssSha256).unwrap();
+        assert_eq!(key.public(), expected.public());
+
+        let pem = PrivateKey::pkcs8_to_pem(ECDSA_P256_PK8);
+        assert_eq!(pem, ECDSA_P256_PK8_PEM);
+    }
+}
@@ -0,0 +1,93 @@
+//! Reading and writing the PEM (RFC 7468) textual encoding of DER structures.
+
+use data_encoding::BASE64;
+
+use crate::error::Error;
+use crate::Result;
+
+/// Length of the base64 lines in emitted PEM.
+const LINE_LENGTH: usize = 64;
+
+/// Encode `der` as PEM with the given label (e.g. `PUBLIC KEY`).
+pub fn encode(label: &str, der: &[u8]) -> String {
+    let encoded = BASE64.encode(der);
+
+    let mut pem = String::with_capacity(encoded.len() + encoded.len() / LINE_LENGTH + 64);
+    pem.push_str(&format!("-----BEGIN {}-----\n", label));
+    for line in encoded.as_bytes().chunks(LINE_LENGTH) {
+        // base64 is always ASCII
+        pem.push_str(&String::from_utf8_lossy(line));
+        pem.push('\n');
+    }
+    pem.push_str(&format!("-----END {}-----\n", label));
+    pem
+}
+
+/// Decode the first PEM block with the given label in `pem`. Text outside the block is ignored.
+pub fn decode(label: &str, pem: &str) -> Result<Vec<u8>> {
+    let begin = format!("-----BEGIN {}-----", label);
+    let end = format!("-----END {}-----", label);
+
+    let mut lines = pem.lines().map(str::trim);
+    if !lines.any(|line| line == begin) {
+        return Err(Error::Encoding(format!("PEM: no {} block found", label)));
+    }
+
+    let mut encoded = String::new();
+    for line in lines {
+        if line == end {
+            return Ok(BASE64.decode(encoded.as_bytes())?);
+        }
+
+        if line.contains(':') {
+            return Err(Error::Encoding(
+                "PEM: encapsulated headers are not supported".into(),
+            ));
+        }
+
+        encoded.push_str(line);
+    }
+
+    Err(Error::Encoding(format!(
+        "PEM: unterminated {} block",
+        label
+    )))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for len in &[0, 1, 47, 48, 49, 1000] {
+            let der = (0..*len).map(|i| i as u8).collect::<Vec<u8>>();
+            let pem = encode("TEST", &der);
+            assert!(pem.lines().all(|line| line.len() <= LINE_LENGTH));
+            assert_eq!(decode("TEST", &pem).unwrap(), der);
+        }
+    }
+
+    #[test]
+    fn decode_ignores_surrounding_text() {
+        let pem = "some comment\r\n-----BEGIN TEST-----\r\nAAEC\r\nAw==\r\n-----END TEST-----\r\n";
+        assert_eq!(decode("TEST", pem).unwrap(), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn decode_wrong_label() {
+        let pem = encode("PRIVATE KEY", &[0, 1, 2, 3]);
+        assert!(decode("PUBLIC KEY", &pem).is_err());
+    }
+
+    #[test]
+    fn decode_unterminated() {
+        assert!(decode("TEST", "-----BEGIN TEST-----\nAAECAw==\n").is_err());
+    }
+
+    #[test]
+    fn decode_headers() {
+        let pem = "-----BEGIN TEST-----\nProc-Type: 4,ENCRYPTED\n\nAAECAw==\n-----END TEST-----\n";
+        assert!(decode("TEST", pem).is_err());
+    }
+}
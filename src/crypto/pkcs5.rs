@@ -0,0 +1,330 @@
+//! Password based encryption of PKCS#8 private keys using PBES2 (RFC 8018).
+//!
+//! Only PBKDF2 is supported as the key derivation function and only AES-CBC is supported as the
+//! encryption scheme, which covers what `openssl pkcs8 -topk8 -v2 ...` produces.
+
+use aes::{Aes128, Aes256};
+use block_modes::block_padding::Pkcs7;
+use block_modes::{BlockMode, Cbc};
+use derp::{self, Der, Tag};
+use untrusted::{Input, Reader};
+
+use crate::crypto::provider::{default_provider, Pbkdf2Prf};
+use crate::error::Error;
+use crate::Result;
+
+/// 1.2.840.113549.1.5.13
+const PBES2_OID: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x05, 0x0d];
+
+/// 1.2.840.113549.1.5.12
+const PBKDF2_OID: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x05, 0x0c];
+
+/// 1.2.840.113549.2.7
+const HMAC_WITH_SHA1_OID: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x02, 0x07];
+
+/// 1.2.840.113549.2.9
+const HMAC_WITH_SHA256_OID: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x02, 0x09];
+
+/// 1.2.840.113549.2.11
+const HMAC_WITH_SHA512_OID: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x02, 0x0b];
+
+/// 2.16.840.1.101.3.4.1.2
+const AES_128_CBC_OID: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x01, 0x02];
+
+/// 2.16.840.1.101.3.4.1.42
+const AES_256_CBC_OID: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x01, 0x2a];
+
+/// PBKDF2 iteration count used when encrypting keys.
+const PBKDF2_ITERATIONS: u32 = 100_000;
+
+/// Length of the PBKDF2 salt used when encrypting keys.
+const SALT_LEN: usize = 16;
+
+/// AES block size, and therefore the length of the CBC IV.
+const AES_BLOCK_LEN: usize = 16;
+
+#[derive(Debug)]
+enum Cipher {
+    Aes128Cbc,
+    Aes256Cbc,
+}
+
+impl Cipher {
+    fn from_oid(oid: &[u8]) -> Option<Self> {
+        match oid {
+            x if x == AES_128_CBC_OID => Some(Cipher::Aes128Cbc),
+            x if x == AES_256_CBC_OID => Some(Cipher::Aes256Cbc),
+            _ => None,
+        }
+    }
+
+    fn key_len(&self) -> usize {
+        match self {
+            Cipher::Aes128Cbc => 16,
+            Cipher::Aes256Cbc => 32,
+        }
+    }
+
+    fn decrypt(&self, key: &[u8], iv: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+        let plaintext = match self {
+            Cipher::Aes128Cbc => Cbc::<Aes128, Pkcs7>::new_var(key, iv)
+                .map_err(|e| Error::Programming(format!("AES-128-CBC: {:?}", e)))?
+                .decrypt_vec(data),
+            Cipher::Aes256Cbc => Cbc::<Aes256, Pkcs7>::new_var(key, iv)
+                .map_err(|e| Error::Programming(format!("AES-256-CBC: {:?}", e)))?
+                .decrypt_vec(data),
+        };
+
+        plaintext.map_err(|_| {
+            Error::Encoding("Could not decrypt PKCS#8 key. The passphrase may be incorrect.".into())
+        })
+    }
+}
+
+struct Pbes2Params<'a> {
+    salt: Input<'a>,
+    iterations: u32,
+    key_len: Option<usize>,
+    prf: Pbkdf2Prf,
+    cipher: Cipher,
+    iv: Input<'a>,
+}
+
+/// Decrypt a DER encoded `EncryptedPrivateKeyInfo`, returning the DER encoded `PrivateKeyInfo`.
+pub fn decrypt(der_key: &[u8], passphrase: &[u8]) -> Result<Vec<u8>> {
+    let input = Input::from(der_key);
+    let (params, data) = input.read_all(derp::Error::Read, |input| {
+        derp::nested(input, Tag::Sequence, |input| {
+            let params = derp::nested(input, Tag::Sequence, read_pbes2_params)?;
+            let data = derp::expect_tag_and_get_value(input, Tag::OctetString)?;
+            Ok((params, data))
+        })
+    })?;
+
+    let params = params?;
+    let key_len = params.key_len.unwrap_or_else(|| params.cipher.key_len());
+    if key_len != params.cipher.key_len() {
+        return Err(Error::Encoding(format!(
+            "PBKDF2 key length {} does not match cipher {:?}",
+            key_len, params.cipher
+        )));
+    }
+
+    let mut key = vec![0; key_len];
+    default_provider().pbkdf2(
+        params.prf,
+        params.iterations,
+        params.salt.as_slice_less_safe(),
+        passphrase,
+        &mut key,
+    )?;
+
+    params.cipher.decrypt(
+        &key,
+        params.iv.as_slice_less_safe(),
+        data.as_slice_less_safe(),
+    )
+}
+
+/// Encrypt a DER encoded `PrivateKeyInfo` with PBES2 using PBKDF2-HMAC-SHA256 and AES-256-CBC,
+/// returning the DER encoded `EncryptedPrivateKeyInfo`.
+pub fn encrypt(der_key: &[u8], passphrase: &[u8]) -> Result<Vec<u8>> {
+    let provider = default_provider();
+
+    let mut salt = [0; SALT_LEN];
+    provider.fill_random(&mut salt)?;
+
+    let mut iv = [0; AES_BLOCK_LEN];
+    provider.fill_random(&mut iv)?;
+
+    let cipher = Cipher::Aes256Cbc;
+    let mut key = vec![0; cipher.key_len()];
+    provider.pbkdf2(
+        Pbkdf2Prf::HmacSha256,
+        PBKDF2_ITERATIONS,
+        &salt,
+        passphrase,
+        &mut key,
+    )?;
+
+    let data = Cbc::<Aes256, Pkcs7>::new_var(&key, &iv)
+        .map_err(|e| Error::Programming(format!("AES-256-CBC: {:?}", e)))?
+        .encrypt_vec(der_key);
+
+    let mut output = Vec::new();
+    {
+        let mut der = Der::new(&mut output);
+        der.sequence(|der| {
+            der.sequence(|der| {
+                der.element(Tag::Oid, PBES2_OID)?;
+                der.sequence(|der| {
+                    der.sequence(|der| {
+                        der.element(Tag::Oid, PBKDF2_OID)?;
+                        der.sequence(|der| {
+                            der.octet_string(&salt)?;
+                            der.positive_integer(&u32_to_be_bytes(PBKDF2_ITERATIONS))?;
+                            der.sequence(|der| {
+                                der.element(Tag::Oid, HMAC_WITH_SHA256_OID)?;
+                                der.null()
+                            })
+                        })
+                    })?;
+                    der.sequence(|der| {
+                        der.element(Tag::Oid, AES_256_CBC_OID)?;
+                        der.octet_string(&iv)
+                    })
+                })
+            })?;
+            der.octet_string(&data)
+        })?;
+    }
+
+    Ok(output)
+}
+
+/// Read the `AlgorithmIdentifier` of an `EncryptedPrivateKeyInfo`. Structurally valid but
+/// unsupported parameters are reported through the inner `Result` so the caller gets a more
+/// useful error than a DER parse failure.
+fn read_pbes2_params<'a>(
+    input: &mut Reader<'a>,
+) -> ::std::result::Result<Result<Pbes2Params<'a>>, derp::Error> {
+    let alg = derp::expect_tag_and_get_value(input, Tag::Oid)?;
+    if alg.as_slice_less_safe() != PBES2_OID {
+        let _ = input.skip_to_end();
+        return Ok(Err(Error::Encoding(
+            "Only PBES2 encrypted PKCS#8 keys are supported".into(),
+        )));
+    }
+
+    derp::nested(input, Tag::Sequence, |input| {
+        let kdf = derp::nested(input, Tag::Sequence, |input| {
+            let kdf = derp::expect_tag_and_get_value(input, Tag::Oid)?;
+            if kdf.as_slice_less_safe() != PBKDF2_OID {
+                let _ = input.skip_to_end();
+                return Ok(Err(Error::Encoding(
+                    "Only PBKDF2 is supported for PBES2 key derivation".into(),
+                )));
+            }
+
+            derp::nested(input, Tag::Sequence, |input| {
+                let salt = derp::expect_tag_and_get_value(input, Tag::OctetString)?;
+                let iterations = read_u32(input)?;
+
+                let key_len = if input.peek(Tag::Integer as u8) {
+                    Some(read_u32(input)? as usize)
+                } else {
+                    None
+                };
+
+                // the PRF defaults to HMAC-SHA1 when it is absent
+                let prf = if input.at_end() {
+                    Ok(Pbkdf2Prf::HmacSha1)
+                } else {
+                    derp::nested(input, Tag::Sequence, |input| {
+                        let prf = derp::expect_tag_and_get_value(input, Tag::Oid)?;
+                        if !input.at_end() {
+                            derp::read_null(input)?;
+                        }
+                        Ok(match prf.as_slice_less_safe() {
+                            x if x == HMAC_WITH_SHA1_OID => Ok(Pbkdf2Prf::HmacSha1),
+                            x if x == HMAC_WITH_SHA256_OID => Ok(Pbkdf2Prf::HmacSha256),
+                            x if x == HMAC_WITH_SHA512_OID => Ok(Pbkdf2Prf::HmacSha512),
+                            _ => Err(Error::Encoding("Unsupported PBKDF2 PRF".into())),
+                        })
+                    })?
+                };
+
+                Ok(prf.map(|prf| (salt, iterations, key_len, prf)))
+            })
+        })?;
+
+        let cipher = derp::nested(input, Tag::Sequence, |input| {
+            let cipher = derp::expect_tag_and_get_value(input, Tag::Oid)?;
+            match Cipher::from_oid(cipher.as_slice_less_safe()) {
+                Some(cipher) => {
+                    let iv = derp::expect_tag_and_get_value(input, Tag::OctetString)?;
+                    Ok(Ok((cipher, iv)))
+                }
+                None => {
+                    let _ = input.skip_to_end();
+                    Ok(Err(Error::Encoding(
+                        "Unsupported PBES2 encryption scheme".into(),
+                    )))
+                }
+            }
+        })?;
+
+        Ok(kdf.and_then(|(salt, iterations, key_len, prf)| {
+            let (cipher, iv) = cipher?;
+
+            if iterations == 0 {
+                return Err(Error::Encoding("PBKDF2 iteration count was 0".into()));
+            }
+
+            if iv.len() != AES_BLOCK_LEN {
+                return Err(Error::Encoding(format!(
+                    "AES-CBC IV had length {}",
+                    iv.len()
+                )));
+            }
+
+            Ok(Pbes2Params {
+                salt,
+                iterations,
+                key_len,
+                prf,
+                cipher,
+                iv,
+            })
+        }))
+    })
+}
+
+fn read_u32(input: &mut Reader) -> ::std::result::Result<u32, derp::Error> {
+    let value = derp::nonnegative_integer(input, 0)?;
+    let bytes = value.as_slice_less_safe();
+    if bytes.len() > 4 {
+        return Err(derp::Error::WrongValue);
+    }
+    Ok(bytes.iter().fold(0, |acc, b| (acc << 8) | u32::from(*b)))
+}
+
+/// Minimal big-endian encoding of `value`, as required for DER integers.
+fn u32_to_be_bytes(value: u32) -> Vec<u8> {
+    let bytes = [
+        (value >> 24) as u8,
+        (value >> 16) as u8,
+        (value >> 8) as u8,
+        value as u8,
+    ];
+    let start = bytes.iter().position(|b| *b != 0).unwrap_or(3);
+    bytes[start..].to_vec()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let data = b"not actually a private key";
+        let encrypted = encrypt(data, b"hunter2").unwrap();
+        assert_eq!(decrypt(&encrypted, b"hunter2").unwrap(), &data[..]);
+        assert!(decrypt(&encrypted, b"hunter3").is_err());
+    }
+
+    #[test]
+    fn encrypt_is_salted() {
+        let data = b"not actually a private key";
+        let first = encrypt(data, b"hunter2").unwrap();
+        let second = encrypt(data, b"hunter2").unwrap();
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn u32_der_bytes() {
+        assert_eq!(u32_to_be_bytes(0), vec![0]);
+        assert_eq!(u32_to_be_bytes(2048), vec![0x08, 0x00]);
+        assert_eq!(u32_to_be_bytes(100_000), vec![0x01, 0x86, 0xa0]);
+    }
+}
@@ -0,0 +1,262 @@
+//! Pluggable implementations of the cryptographic primitives TUF relies on.
+//!
+//! Everything in `tuf::crypto` that hashes, signs, verifies, or parses private keys goes through a
+//! `CryptoProvider`. Which provider is used is selected at compile time with cargo features:
+//!
+//! * `ring` (default): backed by [`ring`](https://crates.io/crates/ring). Supports every
+//!   `SignatureScheme`.
+//! * `rust-crypto`: backed by pure Rust crates from the RustCrypto and dalek projects. This
+//!   provider does not support ECDSA or RSASSA-PSS, but it builds for any target `rustc` does.
+//!   Metadata that lists keys using those schemes is rejected when it is loaded.
+//!
+//! If both features are enabled, `ring` is used.
+
+use blake2::Digest;
+
+use crate::crypto::{HashAlgorithm, KeyType, SignatureScheme};
+use crate::Result;
+
+#[cfg(feature = "ring")]
+pub use crate::crypto::ring_provider::RingProvider;
+#[cfg(feature = "rust-crypto")]
+pub use crate::crypto::rust_provider::RustCryptoProvider;
+
+#[cfg(not(any(feature = "ring", feature = "rust-crypto")))]
+compile_error!("One of the features `ring` or `rust-crypto` must be enabled");
+
+#[cfg(feature = "ring")]
+static DEFAULT_PROVIDER: RingProvider = RingProvider;
+
+#[cfg(all(feature = "rust-crypto", not(feature = "ring")))]
+static DEFAULT_PROVIDER: RustCryptoProvider = RustCryptoProvider;
+
+/// The `CryptoProvider` selected by cargo features.
+pub fn default_provider() -> &'static dyn CryptoProvider {
+    &DEFAULT_PROVIDER
+}
+
+/// An in-progress digest calculation.
+pub trait DigestContext: Send {
+    /// Add `data` to the digest.
+    fn update(&mut self, data: &[u8]);
+
+    /// Finish the calculation and return the digest.
+    fn finish(self: Box<Self>) -> Vec<u8>;
+}
+
+/// Adapts a RustCrypto `Digest` to a `DigestContext`.
+pub(crate) struct RustCryptoDigest<D>(pub(crate) D);
+
+impl<D: Digest + Send> DigestContext for RustCryptoDigest<D> {
+    fn update(&mut self, data: &[u8]) {
+        self.0.input(data)
+    }
+
+    fn finish(self: Box<Self>) -> Vec<u8> {
+        self.0.result().to_vec()
+    }
+}
+
+/// A private key parsed by a `CryptoProvider`.
+pub trait KeyPair: Send + Sync {
+    /// The public half of the key, encoded as it is in a `PublicKey`. That is the raw point for
+    /// Ed25519 and ECDSA, and a PKCS#1 `RSAPublicKey` for RSA.
+    fn public_key(&self) -> &[u8];
+
+    /// Sign `msg` with the given scheme.
+    fn sign(&self, scheme: &SignatureScheme, msg: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// The pseudorandom function used by PBKDF2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pbkdf2Prf {
+    /// HMAC-SHA1
+    HmacSha1,
+    /// HMAC-SHA256
+    HmacSha256,
+    /// HMAC-SHA512
+    HmacSha512,
+}
+
+/// An implementation of the cryptographic primitives used by TUF.
+pub trait CryptoProvider: Send + Sync {
+    /// Whether this provider can sign and verify with `scheme`.
+    fn supports(&self, scheme: &SignatureScheme) -> bool;
+
+    /// Start a digest calculation with the given algorithm.
+    fn digest(&self, alg: &HashAlgorithm) -> Result<Box<dyn DigestContext>>;
+
+    /// Verify that `sig` is a signature over `msg` by `public_key` using `scheme`. The public key
+    /// is encoded as it is in `KeyPair::public_key`.
+    fn verify(
+        &self,
+        scheme: &SignatureScheme,
+        public_key: &[u8],
+        msg: &[u8],
+        sig: &[u8],
+    ) -> Result<()>;
+
+    /// Parse a PKCS#8 DER encoded private key of the given type.
+    fn key_pair_from_pkcs8(&self, key_type: &KeyType, der_key: &[u8]) -> Result<Box<dyn KeyPair>>;
+
+    /// Generate a new Ed25519 or ECDSA key and return it as PKCS#8 DER bytes. RSA keys are
+    /// generated by `PrivateKey::new_rsa` independent of the provider.
+    fn generate_pkcs8(&self, key_type: &KeyType) -> Result<Vec<u8>>;
+
+    /// Fill `buf` with cryptographically secure random bytes.
+    fn fill_random(&self, buf: &mut [u8]) -> Result<()>;
+
+    /// Derive a key from `secret` into `out` with PBKDF2.
+    fn pbkdf2(
+        &self,
+        prf: Pbkdf2Prf,
+        iterations: u32,
+        salt: &[u8],
+        secret: &[u8],
+        out: &mut [u8],
+    ) -> Result<()>;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::Error;
+
+    const ED25519_PK8: &[u8] = include_bytes!("../../tests/ed25519/ed25519-1.pk8.der");
+    const RSA_2048_PK8: &[u8] = include_bytes!("../../tests/rsa/rsa-2048.pk8.der");
+    const ECDSA_P256_PK8: &[u8] = include_bytes!("../../tests/ecdsa/ecdsa-p256.pk8.der");
+    const ECDSA_P384_PK8: &[u8] = include_bytes!("../../tests/ecdsa/ecdsa-p384.pk8.der");
+
+    fn providers() -> Vec<&'static dyn CryptoProvider> {
+        let mut providers: Vec<&'static dyn CryptoProvider> = Vec::new();
+        #[cfg(feature = "ring")]
+        providers.push(&RingProvider);
+        #[cfg(feature = "rust-crypto")]
+        providers.push(&RustCryptoProvider);
+        providers
+    }
+
+    fn is_unsupported<T>(res: Result<T>) -> bool {
+        match res {
+            Err(Error::UnsupportedKeyFormat(_)) => true,
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn sign_verify_matrix() {
+        let keys = &[
+            (KeyType::Ed25519, SignatureScheme::Ed25519, ED25519_PK8),
+            (KeyType::Rsa, SignatureScheme::RsaSsaPkcs1v15Sha256, RSA_2048_PK8),
+            (KeyType::Rsa, SignatureScheme::RsaSsaPkcs1v15Sha512, RSA_2048_PK8),
+            (KeyType::Rsa, SignatureScheme::RsaSsaPssSha256, RSA_2048_PK8),
+            (KeyType::Rsa, SignatureScheme::RsaSsaPssSha512, RSA_2048_PK8),
+            (KeyType::EcdsaP256, SignatureScheme::EcdsaP256Sha256, ECDSA_P256_PK8),
+            (KeyType::EcdsaP384, SignatureScheme::EcdsaP384Sha384, ECDSA_P384_PK8),
+        ];
+
+        for provider in providers() {
+            for (typ, scheme, der) in keys {
+                if !provider.supports(scheme) {
+                    assert!(is_unsupported(provider.verify(scheme, b"", b"message", b"")));
+                    match provider.key_pair_from_pkcs8(typ, der) {
+                        Ok(key) => assert!(is_unsupported(key.sign(scheme, b"message"))),
+                        Err(err) => assert!(is_unsupported::<()>(Err(err))),
+                    }
+                    continue;
+                }
+
+                let key = provider.key_pair_from_pkcs8(typ, der).unwrap();
+                let sig = key.sign(scheme, b"message").unwrap();
+
+                // every provider that supports the scheme must accept the signature
+                for verifier in providers() {
+                    if !verifier.supports(scheme) {
+                        continue;
+                    }
+                    verifier
+                        .verify(scheme, key.public_key(), b"message", &sig)
+                        .unwrap();
+                    assert!(verifier
+                        .verify(scheme, key.public_key(), b"massage", &sig)
+                        .is_err());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn digest_known_answer() {
+        let provider = default_provider();
+
+        let mut context = provider.digest(&HashAlgorithm::Sha256).unwrap();
+        context.update(b"abc");
+        assert_eq!(
+            context.finish(),
+            vec![
+                0xba, 0x78, 0x16, 0xbf, 0x8f, 0x01, 0xcf, 0xea, 0x41, 0x41, 0x40, 0xde, 0x5d, 0xae,
+                0x22, 0x23, 0xb0, 0x03, 0x61, 0xa3, 0x96, 0x17, 0x7a, 0x9c, 0xb4, 0x10, 0xff, 0x61,
+                0xf2, 0x00, 0x15, 0xad,
+            ]
+        );
+
+        assert!(provider
+            .digest(&HashAlgorithm::Unknown("md5".into()))
+            .is_err());
+    }
+
+    #[test]
+    #[cfg(all(feature = "ring", feature = "rust-crypto"))]
+    fn providers_agree() {
+        use crate::crypto::{PrivateKey, SignatureScheme};
+
+        let ring = RingProvider;
+        let rust = RustCryptoProvider;
+
+        for alg in crate::crypto::HASH_ALG_PREFS {
+            let mut a = ring.digest(alg).unwrap();
+            let mut b = rust.digest(alg).unwrap();
+            a.update(b"some data");
+            b.update(b"some data");
+            assert_eq!(a.finish(), b.finish());
+        }
+
+        let mut a = [0; 32];
+        let mut b = [0; 32];
+        ring.pbkdf2(Pbkdf2Prf::HmacSha256, 10, b"salt", b"secret", &mut a)
+            .unwrap();
+        rust.pbkdf2(Pbkdf2Prf::HmacSha256, 10, b"salt", b"secret", &mut b)
+            .unwrap();
+        assert_eq!(a, b);
+
+        let keys = &[
+            (KeyType::Ed25519, SignatureScheme::Ed25519),
+            (KeyType::Rsa, SignatureScheme::RsaSsaPkcs1v15Sha256),
+            (KeyType::Rsa, SignatureScheme::RsaSsaPkcs1v15Sha512),
+        ];
+        let ed25519 = rust.generate_pkcs8(&KeyType::Ed25519).unwrap();
+        let rsa = PrivateKey::new_rsa(2048).unwrap();
+
+        for (typ, scheme) in keys {
+            let der = if typ == &KeyType::Ed25519 {
+                &ed25519
+            } else {
+                &rsa
+            };
+
+            for (signer, verifier) in &[
+                (&ring as &dyn CryptoProvider, &rust as &dyn CryptoProvider),
+                (&rust as &dyn CryptoProvider, &ring as &dyn CryptoProvider),
+            ] {
+                let key = signer.key_pair_from_pkcs8(typ, der).unwrap();
+                let sig = key.sign(scheme, b"message").unwrap();
+                verifier
+                    .verify(scheme, key.public_key(), b"message", &sig)
+                    .unwrap();
+                assert!(verifier
+                    .verify(scheme, key.public_key(), b"massage", &sig)
+                    .is_err());
+            }
+        }
+    }
+}
@@ -0,0 +1,285 @@
+//! A `CryptoProvider` backed by `ring`.
+
+use blake2::Blake2b;
+use ring::digest::{self, SHA1, SHA256, SHA384, SHA512, SHA512_256};
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::signature::{
+    self, ECDSAKeyPair, Ed25519KeyPair, RSAEncoding, RSAKeyPair, RSASigningState,
+    ECDSA_P256_SHA256_ASN1, ECDSA_P256_SHA256_ASN1_SIGNING, ECDSA_P384_SHA384_ASN1,
+    ECDSA_P384_SHA384_ASN1_SIGNING, ED25519, RSA_PKCS1_2048_8192_SHA256,
+    RSA_PKCS1_2048_8192_SHA512, RSA_PKCS1_SHA256, RSA_PKCS1_SHA512, RSA_PSS_2048_8192_SHA256,
+    RSA_PSS_2048_8192_SHA512, RSA_PSS_SHA256, RSA_PSS_SHA512,
+};
+use std::fmt::{self, Debug};
+use std::sync::Arc;
+use untrusted::Input;
+
+use crate::crypto::provider::{
+    CryptoProvider, DigestContext, KeyPair, Pbkdf2Prf, RustCryptoDigest,
+};
+use crate::crypto::{
+    extract_ecdsa_pub_from_pkcs8, extract_rsa_pub_from_pkcs8, pkcs8_version, HashAlgorithm,
+    KeyType, SignatureScheme,
+};
+use crate::error::Error;
+use crate::Result;
+
+/// A `CryptoProvider` backed by `ring`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RingProvider;
+
+impl CryptoProvider for RingProvider {
+    fn supports(&self, scheme: &SignatureScheme) -> bool {
+        match *scheme {
+            SignatureScheme::Unknown(_) => false,
+            _ => true,
+        }
+    }
+
+    fn digest(&self, alg: &HashAlgorithm) -> Result<Box<dyn DigestContext>> {
+        let alg = match *alg {
+            HashAlgorithm::Sha256 => &SHA256,
+            HashAlgorithm::Sha384 => &SHA384,
+            HashAlgorithm::Sha512 => &SHA512,
+            HashAlgorithm::Sha512Trunc256 => &SHA512_256,
+            // ring does not implement BLAKE2
+            HashAlgorithm::Blake2b => return Ok(Box::new(RustCryptoDigest(Blake2b::default()))),
+            HashAlgorithm::Unknown(ref s) => {
+                return Err(Error::IllegalArgument(format!(
+                    "Unknown hash algorithm: {}",
+                    s
+                )));
+            }
+        };
+        Ok(Box::new(RingDigestContext(digest::Context::new(alg))))
+    }
+
+    fn verify(
+        &self,
+        scheme: &SignatureScheme,
+        public_key: &[u8],
+        msg: &[u8],
+        sig: &[u8],
+    ) -> Result<()> {
+        let alg: &dyn signature::VerificationAlgorithm = match *scheme {
+            SignatureScheme::Ed25519 => &ED25519,
+            SignatureScheme::RsaSsaPssSha256 => &RSA_PSS_2048_8192_SHA256,
+            SignatureScheme::RsaSsaPssSha512 => &RSA_PSS_2048_8192_SHA512,
+            SignatureScheme::RsaSsaPkcs1v15Sha256 => &RSA_PKCS1_2048_8192_SHA256,
+            SignatureScheme::RsaSsaPkcs1v15Sha512 => &RSA_PKCS1_2048_8192_SHA512,
+            SignatureScheme::EcdsaP256Sha256 => &ECDSA_P256_SHA256_ASN1,
+            SignatureScheme::EcdsaP384Sha384 => &ECDSA_P384_SHA384_ASN1,
+            SignatureScheme::Unknown(ref s) => {
+                return Err(Error::IllegalArgument(format!(
+                    "Unknown signature scheme: {}",
+                    s
+                )));
+            }
+        };
+
+        signature::verify(
+            alg,
+            Input::from(public_key),
+            Input::from(msg),
+            Input::from(sig),
+        )
+        .map_err(|_| Error::BadSignature)
+    }
+
+    fn key_pair_from_pkcs8(&self, key_type: &KeyType, der_key: &[u8]) -> Result<Box<dyn KeyPair>> {
+        let key_pair = match *key_type {
+            KeyType::Ed25519 => {
+                // ring only checks v2 documents, which carry the public key. Tools like
+                // `openssl pkcs8` emit v1 documents without it, and for those there is nothing
+                // to check the derived public key against.
+                let input = Input::from(der_key);
+                let key = match pkcs8_version(der_key) {
+                    Ok(0) => Ed25519KeyPair::from_pkcs8_maybe_unchecked(input),
+                    _ => Ed25519KeyPair::from_pkcs8(input),
+                }
+                .map_err(|_| Error::Encoding("Could not parse key as PKCS#8".into()))?;
+                let public = key.public_key_bytes().to_vec();
+                RingKeyPair {
+                    key: RingKey::Ed25519(key),
+                    public,
+                }
+            }
+            KeyType::Rsa => {
+                let key = RSAKeyPair::from_pkcs8(Input::from(der_key))
+                    .map_err(|_| Error::Encoding("Could not parse key as PKCS#8v2".into()))?;
+
+                if key.public_modulus_len() < 256 {
+                    return Err(Error::IllegalArgument(format!(
+                        "RSA public modulus must be 2048 or greater. Found {}",
+                        key.public_modulus_len() * 8
+                    )));
+                }
+
+                RingKeyPair {
+                    key: RingKey::Rsa(Arc::new(key)),
+                    public: extract_rsa_pub_from_pkcs8(der_key)?,
+                }
+            }
+            KeyType::EcdsaP256 | KeyType::EcdsaP384 => {
+                let alg = if *key_type == KeyType::EcdsaP256 {
+                    &ECDSA_P256_SHA256_ASN1_SIGNING
+                } else {
+                    &ECDSA_P384_SHA384_ASN1_SIGNING
+                };
+                let key = ECDSAKeyPair::from_pkcs8(alg, Input::from(der_key))
+                    .map_err(|_| Error::Encoding("Could not parse key as PKCS#8v1".into()))?;
+
+                RingKeyPair {
+                    key: RingKey::Ecdsa(key),
+                    public: extract_ecdsa_pub_from_pkcs8(der_key, key_type)?,
+                }
+            }
+            KeyType::Unknown(ref s) => {
+                return Err(Error::IllegalArgument(format!("Unknown key type: {}", s)));
+            }
+        };
+
+        Ok(Box::new(key_pair))
+    }
+
+    fn generate_pkcs8(&self, key_type: &KeyType) -> Result<Vec<u8>> {
+        match *key_type {
+            KeyType::Ed25519 => Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+                .map(|bytes| bytes.to_vec())
+                .map_err(|_| Error::Opaque("Failed to generate Ed25519 key".into())),
+            KeyType::EcdsaP256 => {
+                ECDSAKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &SystemRandom::new())
+                    .map(|doc| doc.as_ref().to_vec())
+                    .map_err(|_| Error::Opaque("Failed to generate ECDSA P-256 key".into()))
+            }
+            KeyType::EcdsaP384 => {
+                ECDSAKeyPair::generate_pkcs8(&ECDSA_P384_SHA384_ASN1_SIGNING, &SystemRandom::new())
+                    .map(|doc| doc.as_ref().to_vec())
+                    .map_err(|_| Error::Opaque("Failed to generate ECDSA P-384 key".into()))
+            }
+            ref typ => Err(Error::IllegalArgument(format!(
+                "Cannot generate keys of type {:?}",
+                typ
+            ))),
+        }
+    }
+
+    fn fill_random(&self, buf: &mut [u8]) -> Result<()> {
+        SystemRandom::new()
+            .fill(buf)
+            .map_err(|_| Error::Opaque("Could not generate random bytes".into()))
+    }
+
+    fn pbkdf2(
+        &self,
+        prf: Pbkdf2Prf,
+        iterations: u32,
+        salt: &[u8],
+        secret: &[u8],
+        out: &mut [u8],
+    ) -> Result<()> {
+        if iterations == 0 {
+            return Err(Error::IllegalArgument(
+                "PBKDF2 iteration count must be positive".into(),
+            ));
+        }
+
+        let alg = match prf {
+            Pbkdf2Prf::HmacSha1 => &SHA1,
+            Pbkdf2Prf::HmacSha256 => &SHA256,
+            Pbkdf2Prf::HmacSha512 => &SHA512,
+        };
+        pbkdf2::derive(alg, iterations, salt, secret, out);
+        Ok(())
+    }
+}
+
+struct RingDigestContext(digest::Context);
+
+impl DigestContext for RingDigestContext {
+    fn update(&mut self, data: &[u8]) {
+        self.0.update(data)
+    }
+
+    fn finish(self: Box<Self>) -> Vec<u8> {
+        self.0.finish().as_ref().to_vec()
+    }
+}
+
+enum RingKey {
+    Ed25519(Ed25519KeyPair),
+    Rsa(Arc<RSAKeyPair>),
+    Ecdsa(ECDSAKeyPair),
+}
+
+impl Debug for RingKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match *self {
+            RingKey::Ed25519(_) => "ed25519",
+            RingKey::Rsa(_) => "rsa",
+            RingKey::Ecdsa(_) => "ecdsa",
+        };
+        write!(f, "RingKey {{ \"{}\" }}", s)
+    }
+}
+
+struct RingKeyPair {
+    key: RingKey,
+    public: Vec<u8>,
+}
+
+impl RingKeyPair {
+    fn rsa_sign(
+        rsa: &Arc<RSAKeyPair>,
+        encoding: &'static dyn RSAEncoding,
+        msg: &[u8],
+    ) -> Result<Vec<u8>> {
+        let mut signing_state = RSASigningState::new(rsa.clone())
+            .map_err(|_| Error::Opaque("Could not initialize RSA signing state.".into()))?;
+        let rng = SystemRandom::new();
+        let mut buf = vec![0; signing_state.key_pair().public_modulus_len()];
+        signing_state
+            .sign(encoding, &rng, msg, &mut buf)
+            .map_err(|_| Error::Opaque("Failed to sign message.".into()))?;
+        Ok(buf)
+    }
+}
+
+impl KeyPair for RingKeyPair {
+    fn public_key(&self) -> &[u8] {
+        &self.public
+    }
+
+    fn sign(&self, scheme: &SignatureScheme, msg: &[u8]) -> Result<Vec<u8>> {
+        match (&self.key, scheme) {
+            (&RingKey::Rsa(ref rsa), &SignatureScheme::RsaSsaPssSha256) => {
+                Self::rsa_sign(rsa, &RSA_PSS_SHA256, msg)
+            }
+            (&RingKey::Rsa(ref rsa), &SignatureScheme::RsaSsaPssSha512) => {
+                Self::rsa_sign(rsa, &RSA_PSS_SHA512, msg)
+            }
+            (&RingKey::Rsa(ref rsa), &SignatureScheme::RsaSsaPkcs1v15Sha256) => {
+                Self::rsa_sign(rsa, &RSA_PKCS1_SHA256, msg)
+            }
+            (&RingKey::Rsa(ref rsa), &SignatureScheme::RsaSsaPkcs1v15Sha512) => {
+                Self::rsa_sign(rsa, &RSA_PKCS1_SHA512, msg)
+            }
+            (&RingKey::Ed25519(ref ed), &SignatureScheme::Ed25519) => {
+                Ok(ed.sign(msg).as_ref().into())
+            }
+            (&RingKey::Ecdsa(ref ec), &SignatureScheme::EcdsaP256Sha256)
+            | (&RingKey::Ecdsa(ref ec), &SignatureScheme::EcdsaP384Sha384) => {
+                let rng = SystemRandom::new();
+                let sig = ec
+                    .sign(Input::from(msg), &rng)
+                    .map_err(|_| Error::Opaque("Failed to sign message.".into()))?;
+                Ok(sig.as_ref().into())
+            }
+            (k, s) => Err(Error::IllegalArgument(format!(
+                "Key {:?} can't be used with scheme {:?}",
+                k, s
+            ))),
+        }
+    }
+}
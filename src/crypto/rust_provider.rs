@@ -0,0 +1,467 @@
+//! A `CryptoProvider` built only on pure Rust crates.
+//!
+//! This provider supports Ed25519 and RSASSA-PKCS1-v1_5. ECDSA and RSASSA-PSS keys and signatures
+//! are rejected with `Error::UnsupportedKeyFormat`, as is any metadata that lists keys using those
+//! schemes.
+
+use blake2::Blake2b;
+use derp::{self, Der, Tag};
+use ed25519_dalek::{
+    Keypair as Ed25519KeyPair, PublicKey as Ed25519PublicKey, SecretKey as Ed25519SecretKey,
+    Signature as Ed25519Signature, PUBLIC_KEY_LENGTH, SECRET_KEY_LENGTH,
+};
+use hmac::Hmac;
+use num_bigint_dig::BigUint;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use rsa::hash::Hashes;
+use rsa::padding::PaddingScheme;
+use rsa::{PublicKey as RsaPublicKey, RSAPrivateKey, RSAPublicKey};
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha384, Sha512, Sha512Trunc256};
+use untrusted::{Input, Reader};
+
+use crate::crypto::provider::{
+    CryptoProvider, DigestContext, KeyPair, Pbkdf2Prf, RustCryptoDigest,
+};
+use crate::crypto::{
+    write_pkcs1, HashAlgorithm, KeyType, SignatureScheme, ED25519_SPKI_OID, RSA_SPKI_OID,
+};
+use crate::error::Error;
+use crate::Result;
+
+/// The smallest RSA modulus, in bits, that will be used for signing or verification.
+const MIN_RSA_MODULUS_BITS: usize = 2048;
+
+/// The largest RSA modulus, in bits, that will be used for verification.
+const MAX_RSA_MODULUS_BITS: usize = 8192;
+
+/// A `CryptoProvider` built only on pure Rust crates.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RustCryptoProvider;
+
+impl CryptoProvider for RustCryptoProvider {
+    fn supports(&self, scheme: &SignatureScheme) -> bool {
+        match *scheme {
+            SignatureScheme::Ed25519
+            | SignatureScheme::RsaSsaPkcs1v15Sha256
+            | SignatureScheme::RsaSsaPkcs1v15Sha512 => true,
+            _ => false,
+        }
+    }
+
+    fn digest(&self, alg: &HashAlgorithm) -> Result<Box<dyn DigestContext>> {
+        match *alg {
+            HashAlgorithm::Sha256 => Ok(Box::new(RustCryptoDigest(Sha256::new()))),
+            HashAlgorithm::Sha384 => Ok(Box::new(RustCryptoDigest(Sha384::new()))),
+            HashAlgorithm::Sha512 => Ok(Box::new(RustCryptoDigest(Sha512::new()))),
+            HashAlgorithm::Sha512Trunc256 => Ok(Box::new(RustCryptoDigest(Sha512Trunc256::new()))),
+            HashAlgorithm::Blake2b => Ok(Box::new(RustCryptoDigest(Blake2b::new()))),
+            HashAlgorithm::Unknown(ref s) => Err(Error::IllegalArgument(format!(
+                "Unknown hash algorithm: {}",
+                s
+            ))),
+        }
+    }
+
+    fn verify(
+        &self,
+        scheme: &SignatureScheme,
+        public_key: &[u8],
+        msg: &[u8],
+        sig: &[u8],
+    ) -> Result<()> {
+        match *scheme {
+            SignatureScheme::Ed25519 => {
+                let public_key =
+                    Ed25519PublicKey::from_bytes(public_key).map_err(|_| Error::BadSignature)?;
+                let sig = Ed25519Signature::from_bytes(sig).map_err(|_| Error::BadSignature)?;
+                public_key
+                    .verify(msg, &sig)
+                    .map_err(|_| Error::BadSignature)
+            }
+            SignatureScheme::RsaSsaPkcs1v15Sha256 | SignatureScheme::RsaSsaPkcs1v15Sha512 => {
+                let public_key = read_pkcs1(public_key).map_err(|_| Error::BadSignature)?;
+                let bits = public_key.n().bits();
+                if bits < MIN_RSA_MODULUS_BITS || bits > MAX_RSA_MODULUS_BITS {
+                    return Err(Error::BadSignature);
+                }
+
+                let (hash, hashed) = rsa_digest(scheme, msg)?;
+                public_key
+                    .verify(PaddingScheme::PKCS1v15, Some(&hash), &hashed, sig)
+                    .map_err(|_| Error::BadSignature)
+            }
+            ref s => Err(unsupported_scheme(s)),
+        }
+    }
+
+    fn key_pair_from_pkcs8(&self, key_type: &KeyType, der_key: &[u8]) -> Result<Box<dyn KeyPair>> {
+        match *key_type {
+            KeyType::Ed25519 => {
+                let (secret, public) = read_ed25519_pkcs8(der_key)
+                    .map_err(|_| Error::Encoding("Could not parse key as PKCS#8".into()))?;
+                let secret = Ed25519SecretKey::from_bytes(&secret)
+                    .map_err(|e| Error::Encoding(format!("Bad Ed25519 key: {:?}", e)))?;
+                let key = Ed25519KeyPair {
+                    public: (&secret).into(),
+                    secret,
+                };
+
+                if let Some(public) = public {
+                    if public != key.public.as_bytes() {
+                        return Err(Error::Encoding(
+                            "Ed25519 public key does not match the private key".into(),
+                        ));
+                    }
+                }
+
+                Ok(Box::new(RustEd25519KeyPair(key)))
+            }
+            KeyType::Rsa => {
+                let (key, public) = read_rsa_pkcs8(der_key)
+                    .map_err(|_| Error::Encoding("Could not parse key as PKCS#8".into()))?;
+                key.validate()
+                    .map_err(|e| Error::Encoding(format!("Bad RSA key: {:?}", e)))?;
+
+                if key.n().bits() < MIN_RSA_MODULUS_BITS {
+                    return Err(Error::IllegalArgument(format!(
+                        "RSA public modulus must be 2048 or greater. Found {}",
+                        key.n().bits()
+                    )));
+                }
+
+                Ok(Box::new(RustRsaKeyPair { key, public }))
+            }
+            ref typ => Err(Error::UnsupportedKeyFormat(format!(
+                "Key type {:?} is not supported by the pure Rust crypto provider",
+                typ
+            ))),
+        }
+    }
+
+    fn generate_pkcs8(&self, key_type: &KeyType) -> Result<Vec<u8>> {
+        match *key_type {
+            KeyType::Ed25519 => {
+                let secret = Ed25519SecretKey::generate(&mut os_rng()?);
+                let public: Ed25519PublicKey = (&secret).into();
+                Ok(write_ed25519_pkcs8(secret.as_bytes(), public.as_bytes())?)
+            }
+            ref typ => Err(Error::UnsupportedKeyFormat(format!(
+                "Cannot generate keys of type {:?} with the pure Rust crypto provider",
+                typ
+            ))),
+        }
+    }
+
+    fn fill_random(&self, buf: &mut [u8]) -> Result<()> {
+        os_rng()?
+            .try_fill_bytes(buf)
+            .map_err(|e| Error::Opaque(format!("Could not generate random bytes: {:?}", e)))
+    }
+
+    fn pbkdf2(
+        &self,
+        prf: Pbkdf2Prf,
+        iterations: u32,
+        salt: &[u8],
+        secret: &[u8],
+        out: &mut [u8],
+    ) -> Result<()> {
+        if iterations == 0 {
+            return Err(Error::IllegalArgument(
+                "PBKDF2 iteration count must be positive".into(),
+            ));
+        }
+
+        let iterations = iterations as usize;
+        match prf {
+            Pbkdf2Prf::HmacSha1 => pbkdf2::pbkdf2::<Hmac<Sha1>>(secret, salt, iterations, out),
+            Pbkdf2Prf::HmacSha256 => pbkdf2::pbkdf2::<Hmac<Sha256>>(secret, salt, iterations, out),
+            Pbkdf2Prf::HmacSha512 => pbkdf2::pbkdf2::<Hmac<Sha512>>(secret, salt, iterations, out),
+        }
+        Ok(())
+    }
+}
+
+struct RustEd25519KeyPair(Ed25519KeyPair);
+
+impl KeyPair for RustEd25519KeyPair {
+    fn public_key(&self) -> &[u8] {
+        self.0.public.as_bytes()
+    }
+
+    fn sign(&self, scheme: &SignatureScheme, msg: &[u8]) -> Result<Vec<u8>> {
+        match *scheme {
+            SignatureScheme::Ed25519 => Ok(self.0.sign(msg).to_bytes().to_vec()),
+            ref s => Err(Error::IllegalArgument(format!(
+                "Ed25519 keys can't be used with scheme {:?}",
+                s
+            ))),
+        }
+    }
+}
+
+struct RustRsaKeyPair {
+    key: RSAPrivateKey,
+    public: Vec<u8>,
+}
+
+impl KeyPair for RustRsaKeyPair {
+    fn public_key(&self) -> &[u8] {
+        &self.public
+    }
+
+    fn sign(&self, scheme: &SignatureScheme, msg: &[u8]) -> Result<Vec<u8>> {
+        match *scheme {
+            SignatureScheme::RsaSsaPkcs1v15Sha256 | SignatureScheme::RsaSsaPkcs1v15Sha512 => {
+                let (hash, hashed) = rsa_digest(scheme, msg)?;
+                self.key
+                    .sign_blinded(
+                        &mut os_rng()?,
+                        PaddingScheme::PKCS1v15,
+                        Some(&hash),
+                        &hashed,
+                    )
+                    .map_err(|_| Error::Opaque("Failed to sign message.".into()))
+            }
+            ref s => Err(unsupported_scheme(s)),
+        }
+    }
+}
+
+fn unsupported_scheme(scheme: &SignatureScheme) -> Error {
+    Error::UnsupportedKeyFormat(format!(
+        "Signature scheme {:?} is not supported by the pure Rust crypto provider",
+        scheme
+    ))
+}
+
+fn os_rng() -> Result<OsRng> {
+    OsRng::new().map_err(|e| Error::Opaque(format!("Could not open OS RNG: {:?}", e)))
+}
+
+/// Hash `msg` as required by an RSASSA-PKCS1-v1_5 scheme.
+fn rsa_digest(scheme: &SignatureScheme, msg: &[u8]) -> Result<(Hashes, Vec<u8>)> {
+    match *scheme {
+        SignatureScheme::RsaSsaPkcs1v15Sha256 => {
+            Ok((Hashes::SHA256, Sha256::digest(msg).to_vec()))
+        }
+        SignatureScheme::RsaSsaPkcs1v15Sha512 => {
+            Ok((Hashes::SHA512, Sha512::digest(msg).to_vec()))
+        }
+        ref s => Err(unsupported_scheme(s)),
+    }
+}
+
+/// Read a PKCS#1 `RSAPublicKey`.
+fn read_pkcs1(der: &[u8]) -> ::std::result::Result<RSAPublicKey, derp::Error> {
+    let (n, e) = Input::from(der).read_all(derp::Error::Read, |input| {
+        derp::nested(input, Tag::Sequence, |input| {
+            let n = derp::positive_integer(input)?;
+            let e = derp::positive_integer(input)?;
+            Ok((to_biguint(n), to_biguint(e)))
+        })
+    })?;
+    RSAPublicKey::new(n, e).map_err(|_| derp::Error::WrongValue)
+}
+
+/// Read the `PrivateKeyInfo` header and check the algorithm OID, leaving `input` at the
+/// `privateKey` field.
+fn read_pkcs8_header(input: &mut Reader, oid: &[u8]) -> ::std::result::Result<(), derp::Error> {
+    if derp::small_nonnegative_integer(input)? > 1 {
+        return Err(derp::Error::WrongValue);
+    }
+
+    derp::nested(input, Tag::Sequence, |input| {
+        let actual_alg_id = derp::expect_tag_and_get_value(input, Tag::Oid)?;
+        if actual_alg_id.as_slice_less_safe() != oid {
+            return Err(derp::Error::WrongValue);
+        }
+        // NULL for RSA, absent for Ed25519
+        if !input.at_end() {
+            derp::read_null(input)?;
+        }
+        Ok(())
+    })
+}
+
+/// Read an Ed25519 PKCS#8 v1 or v2 key, returning the secret key and the public key if present.
+fn read_ed25519_pkcs8(
+    der_key: &[u8],
+) -> ::std::result::Result<(Vec<u8>, Option<Vec<u8>>), derp::Error> {
+    Input::from(der_key).read_all(derp::Error::Read, |input| {
+        derp::nested(input, Tag::Sequence, |input| {
+            read_pkcs8_header(input, ED25519_SPKI_OID)?;
+
+            let secret = derp::nested(input, Tag::OctetString, |input| {
+                let secret = derp::expect_tag_and_get_value(input, Tag::OctetString)?;
+                if secret.len() != SECRET_KEY_LENGTH {
+                    return Err(derp::Error::WrongValue);
+                }
+                Ok(secret.as_slice_less_safe().to_vec())
+            })?;
+
+            if input.peek(Tag::ContextSpecificConstructed0 as u8) {
+                let _ = derp::expect_tag_and_get_value(input, Tag::ContextSpecificConstructed0)?;
+            }
+
+            let public = if input.peek(Tag::ContextSpecificConstructed1 as u8) {
+                let public = derp::nested(input, Tag::ContextSpecificConstructed1, |input| {
+                    derp::bit_string_with_no_unused_bits(input)
+                })?;
+                if public.len() != PUBLIC_KEY_LENGTH {
+                    return Err(derp::Error::WrongValue);
+                }
+                Some(public.as_slice_less_safe().to_vec())
+            } else {
+                None
+            };
+
+            Ok((secret, public))
+        })
+    })
+}
+
+/// Write an Ed25519 key as PKCS#8 v2, the same format `ring` generates.
+fn write_ed25519_pkcs8(
+    secret: &[u8],
+    public: &[u8],
+) -> ::std::result::Result<Vec<u8>, derp::Error> {
+    let mut private_key = Vec::new();
+    {
+        let mut der = Der::new(&mut private_key);
+        der.octet_string(secret)?;
+    }
+
+    let mut output = Vec::new();
+    {
+        let mut der = Der::new(&mut output);
+        der.sequence(|der| {
+            der.integer(&[1])?;
+            der.sequence(|der| der.element(Tag::Oid, ED25519_SPKI_OID))?;
+            der.octet_string(&private_key)?;
+            der.nested(Tag::ContextSpecificConstructed1, |der| {
+                der.bit_string(0, public)
+            })
+        })?;
+    }
+
+    Ok(output)
+}
+
+/// Read an RSA PKCS#8 key, returning the key and its PKCS#1 encoded public key.
+fn read_rsa_pkcs8(der_key: &[u8]) -> ::std::result::Result<(RSAPrivateKey, Vec<u8>), derp::Error> {
+    Input::from(der_key).read_all(derp::Error::Read, |input| {
+        derp::nested(input, Tag::Sequence, |input| {
+            read_pkcs8_header(input, RSA_SPKI_OID)?;
+
+            let key = derp::nested(input, Tag::OctetString, |input| {
+                derp::nested(input, Tag::Sequence, |input| {
+                    // only two-prime keys are supported
+                    if derp::small_nonnegative_integer(input)? != 0 {
+                        return Err(derp::Error::WrongValue);
+                    }
+
+                    let n = derp::positive_integer(input)?;
+                    let e = derp::positive_integer(input)?;
+                    let d = derp::positive_integer(input)?;
+                    let p = derp::positive_integer(input)?;
+                    let q = derp::positive_integer(input)?;
+                    // the CRT values are recomputed by `RSAPrivateKey`
+                    let _ = input.skip_to_end();
+
+                    let public = write_pkcs1(n.as_slice_less_safe(), e.as_slice_less_safe())?;
+                    let key = RSAPrivateKey::from_components(
+                        to_biguint(n),
+                        to_biguint(e),
+                        to_biguint(d),
+                        vec![to_biguint(p), to_biguint(q)],
+                    );
+                    Ok((key, public))
+                })
+            })?;
+
+            // ignore any attributes
+            let _ = input.skip_to_end();
+            Ok(key)
+        })
+    })
+}
+
+fn to_biguint(input: Input) -> BigUint {
+    BigUint::from_bytes_be(input.as_slice_less_safe())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ED25519_PK8: &[u8] = include_bytes!("../../tests/ed25519/ed25519-1.pk8.der");
+    const RSA_2048_PK8: &[u8] = include_bytes!("../../tests/rsa/rsa-2048.pk8.der");
+
+    #[test]
+    fn ed25519_pkcs8_round_trip() {
+        let provider = RustCryptoProvider;
+        let der = provider.generate_pkcs8(&KeyType::Ed25519).unwrap();
+        let key = provider
+            .key_pair_from_pkcs8(&KeyType::Ed25519, &der)
+            .unwrap();
+
+        let sig = key.sign(&SignatureScheme::Ed25519, b"message").unwrap();
+        provider
+            .verify(
+                &SignatureScheme::Ed25519,
+                key.public_key(),
+                b"message",
+                &sig,
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn read_fixtures() {
+        let provider = RustCryptoProvider;
+
+        let key = provider
+            .key_pair_from_pkcs8(&KeyType::Ed25519, ED25519_PK8)
+            .unwrap();
+        let sig = key.sign(&SignatureScheme::Ed25519, b"message").unwrap();
+        provider
+            .verify(
+                &SignatureScheme::Ed25519,
+                key.public_key(),
+                b"message",
+                &sig,
+            )
+            .unwrap();
+
+        let key = provider
+            .key_pair_from_pkcs8(&KeyType::Rsa, RSA_2048_PK8)
+            .unwrap();
+        let scheme = SignatureScheme::RsaSsaPkcs1v15Sha256;
+        let sig = key.sign(&scheme, b"message").unwrap();
+        provider
+            .verify(&scheme, key.public_key(), b"message", &sig)
+            .unwrap();
+        assert!(provider
+            .verify(&scheme, key.public_key(), b"massage", &sig)
+            .is_err());
+
+        assert!(key
+            .sign(&SignatureScheme::RsaSsaPssSha256, b"message")
+            .is_err());
+    }
+
+    #[test]
+    fn unsupported_key_types() {
+        let provider = RustCryptoProvider;
+        assert!(provider.supports(&SignatureScheme::RsaSsaPkcs1v15Sha256));
+        assert!(!provider.supports(&SignatureScheme::RsaSsaPssSha256));
+        assert!(!provider.supports(&SignatureScheme::EcdsaP256Sha256));
+        assert!(provider.generate_pkcs8(&KeyType::EcdsaP256).is_err());
+        assert!(provider
+            .key_pair_from_pkcs8(&KeyType::EcdsaP256, ED25519_PK8)
+            .is_err());
+    }
+}
@@ -3,6 +3,7 @@
 use data_encoding::DecodeError;
 use derp;
 use http;
+#[cfg(feature = "hyper")]
 use hyper;
 use serde_json;
 use std::fmt;
@@ -37,10 +38,24 @@ pub enum Error {
     /// The target is unavailable. This may mean it is either not in the metadata or the metadata
     /// chain to the target cannot be fully verified.
     TargetUnavailable,
+    /// A request to a remote repository did not complete in time.
+    Timeout,
+    /// A request to a remote repository could not be sent, or its response could not be received.
+    Transport {
+        /// A description of what went wrong.
+        message: String,
+        /// Whether sending the same request again may succeed.
+        retryable: bool,
+    },
     /// There is no known or available hash algorithm.
     UnkonwnHashAlgorithm(String),
     /// There is no known or available key type.
     UnknownKeyType(String),
+    /// The key type or signature scheme is known, but the crypto provider in use cannot handle it.
+    UnsupportedKeyFormat(String),
+    /// The metadata was written for a version of the TUF specification this library does not
+    /// support.
+    UnsupportedSpecVersion(String),
     /// The metadata or target failed to verify.
     VerificationFailure(String),
 }
@@ -58,8 +73,12 @@ impl ::std::error::Error for Error {
             Error::Opaque(_) => "opaque",
             Error::Programming(_) => "programming",
             Error::TargetUnavailable => "target unavailable",
+            Error::Timeout => "timeout",
+            Error::Transport { .. } => "transport",
             Error::UnkonwnHashAlgorithm(_) => "unknown hash algorithm",
             Error::UnknownKeyType(_) => "unknown key type",
+            Error::UnsupportedKeyFormat(_) => "unsupported key format",
+            Error::UnsupportedSpecVersion(_) => "unsupported spec version",
             Error::VerificationFailure(_) => "verification failure",
         }
     }
@@ -77,6 +96,13 @@ impl From<serde_json::error::Error> for Error {
     }
 }
 
+#[cfg(feature = "cbor")]
+impl From<serde_cbor::error::Error> for Error {
+    fn from(err: serde_cbor::error::Error) -> Error {
+        Error::Encoding(format!("CBOR: {:?}", err))
+    }
+}
+
 impl Error {
     /// Helper to include the path that causd the error for FS I/O errors.
     pub fn from_io(err: &io::Error, path: &Path) -> Error {
@@ -96,6 +122,7 @@ impl From<http::Error> for Error {
     }
 }
 
+#[cfg(feature = "hyper")]
 impl From<hyper::Error> for Error {
     fn from(err: hyper::Error) -> Error {
         Error::Opaque(format!("Hyper: {:?}", err))
@@ -0,0 +1,642 @@
+//! Deterministic CBOR encoding as described in
+//! [RFC 8949 section 4.2.1](https://tools.ietf.org/html/rfc8949#section-4.2.1).
+//!
+//! * Integers, lengths, and tags use the shortest possible head.
+//! * Only definite length arrays, maps, and strings are produced.
+//! * Map keys are sorted by the bytewise lexicographic order of their deterministic encodings.
+//!
+//! Floating point numbers are rejected, as they are in canonical JSON.
+
+use serde::ser::{self, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+use std::io::Write;
+
+const MAJOR_UNSIGNED: u8 = 0;
+const MAJOR_NEGATIVE: u8 = 1;
+const MAJOR_BYTES: u8 = 2;
+const MAJOR_TEXT: u8 = 3;
+const MAJOR_ARRAY: u8 = 4;
+const MAJOR_MAP: u8 = 5;
+const MAJOR_SIMPLE: u8 = 7;
+
+const FALSE: u8 = 0xf4;
+const TRUE: u8 = 0xf5;
+const NULL: u8 = 0xf6;
+
+/// Serialize `value` as deterministic CBOR into `writer`.
+pub fn to_writer<W, T>(mut writer: W, value: &T) -> Result<(), String>
+where
+    W: Write,
+    T: Serialize + ?Sized,
+{
+    let bytes = to_vec(value)?;
+    writer
+        .write_all(&bytes)
+        .map_err(|e| format!("Write error: {}", e))
+}
+
+/// Serialize `value` as deterministic CBOR.
+pub fn to_vec<T>(value: &T) -> Result<Vec<u8>, String>
+where
+    T: Serialize + ?Sized,
+{
+    to_buf(value).map_err(|e| e.0)
+}
+
+/// Return the exact bytes of the value stored under the text key `field` in the CBOR map
+/// `bytes`.
+pub fn extract_field<'a>(bytes: &'a [u8], field: &str) -> Result<&'a [u8], String> {
+    let (major, entries, mut offset) = read_head(bytes, 0)?;
+    if major != MAJOR_MAP {
+        return Err("Expected a CBOR map".into());
+    }
+
+    let field = text_key(field);
+    for _ in 0..entries {
+        let key_start = offset;
+        offset = skip_item(bytes, offset)?;
+        let key = &bytes[key_start..offset];
+
+        let value_start = offset;
+        offset = skip_item(bytes, offset)?;
+
+        if key == &*field {
+            return Ok(&bytes[value_start..offset]);
+        }
+    }
+
+    Err("CBOR map did not contain the requested key".into())
+}
+
+fn text_key(s: &str) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(s.len() + 9);
+    write_head(&mut buf, MAJOR_TEXT, s.len() as u64);
+    buf.extend(s.as_bytes());
+    buf
+}
+
+/// Read the head of the item at `offset` and return its major type, argument, and the offset
+/// just past the head.
+fn read_head(bytes: &[u8], offset: usize) -> Result<(u8, u64, usize), String> {
+    let initial = *bytes
+        .get(offset)
+        .ok_or_else(|| String::from("Unexpected end of CBOR data"))?;
+    let major = initial >> 5;
+    let info = initial & 0x1f;
+
+    let size = match info {
+        0..=23 => return Ok((major, u64::from(info), offset + 1)),
+        24 => 1,
+        25 => 2,
+        26 => 4,
+        27 => 8,
+        _ => return Err("Indefinite lengths and reserved values are not supported".into()),
+    };
+
+    let arg = bytes
+        .get(offset + 1..offset + 1 + size)
+        .ok_or_else(|| String::from("Unexpected end of CBOR data"))?
+        .iter()
+        .fold(0u64, |acc, b| (acc << 8) | u64::from(*b));
+    Ok((major, arg, offset + 1 + size))
+}
+
+/// Return the offset just past the item starting at `offset`.
+fn skip_item(bytes: &[u8], offset: usize) -> Result<usize, String> {
+    let (major, arg, mut offset) = read_head(bytes, offset)?;
+    match major {
+        MAJOR_UNSIGNED | MAJOR_NEGATIVE | MAJOR_SIMPLE => Ok(offset),
+        MAJOR_BYTES | MAJOR_TEXT => {
+            let end = (offset as u64)
+                .checked_add(arg)
+                .filter(|end| *end <= bytes.len() as u64)
+                .ok_or_else(|| String::from("Unexpected end of CBOR data"))?;
+            Ok(end as usize)
+        }
+        MAJOR_ARRAY | MAJOR_MAP => {
+            let items = if major == MAJOR_MAP {
+                arg.saturating_mul(2)
+            } else {
+                arg
+            };
+            for _ in 0..items {
+                offset = skip_item(bytes, offset)?;
+            }
+            Ok(offset)
+        }
+        // tags
+        _ => skip_item(bytes, offset),
+    }
+}
+
+fn write_head(buf: &mut Vec<u8>, major: u8, arg: u64) {
+    let major = major << 5;
+    if arg < 24 {
+        buf.push(major | arg as u8);
+    } else if arg <= u64::from(::std::u8::MAX) {
+        buf.push(major | 24);
+        buf.push(arg as u8);
+    } else if arg <= u64::from(::std::u16::MAX) {
+        buf.push(major | 25);
+        buf.extend(&(arg as u16).to_be_bytes());
+    } else if arg <= u64::from(::std::u32::MAX) {
+        buf.push(major | 26);
+        buf.extend(&(arg as u32).to_be_bytes());
+    } else {
+        buf.push(major | 27);
+        buf.extend(&arg.to_be_bytes());
+    }
+}
+
+#[derive(Debug)]
+struct SerError(String);
+
+impl Display for SerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl ::std::error::Error for SerError {}
+
+impl ser::Error for SerError {
+    fn custom<T: Display>(msg: T) -> Self {
+        SerError(msg.to_string())
+    }
+}
+
+fn to_buf<T>(value: &T) -> Result<Vec<u8>, SerError>
+where
+    T: Serialize + ?Sized,
+{
+    let mut ser = CanonicalSerializer { buf: Vec::new() };
+    value.serialize(&mut ser)?;
+    Ok(ser.buf)
+}
+
+struct CanonicalSerializer {
+    buf: Vec<u8>,
+}
+
+impl CanonicalSerializer {
+    fn signed(&mut self, v: i128) -> Result<(), SerError> {
+        if v >= 0 && v <= i128::from(::std::u64::MAX) {
+            write_head(&mut self.buf, MAJOR_UNSIGNED, v as u64);
+            Ok(())
+        } else if v < 0 && v >= -1 - i128::from(::std::u64::MAX) {
+            write_head(&mut self.buf, MAJOR_NEGATIVE, (-1 - v) as u64);
+            Ok(())
+        } else {
+            Err(SerError(format!("Integer out of range for CBOR: {}", v)))
+        }
+    }
+
+    /// Non-unit enum variants are encoded as a map with a single entry, like JSON.
+    fn begin_variant(&mut self, variant: &str) {
+        write_head(&mut self.buf, MAJOR_MAP, 1);
+        self.buf.extend(text_key(variant));
+    }
+}
+
+impl<'a> ser::Serializer for &'a mut CanonicalSerializer {
+    type Ok = ();
+    type Error = SerError;
+
+    type SerializeSeq = SeqSerializer<'a>;
+    type SerializeTuple = SeqSerializer<'a>;
+    type SerializeTupleStruct = SeqSerializer<'a>;
+    type SerializeTupleVariant = SeqSerializer<'a>;
+    type SerializeMap = MapSerializer<'a>;
+    type SerializeStruct = MapSerializer<'a>;
+    type SerializeStructVariant = MapSerializer<'a>;
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn serialize_bool(self, v: bool) -> Result<(), SerError> {
+        self.buf.push(if v { TRUE } else { FALSE });
+        Ok(())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), SerError> {
+        self.signed(i128::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), SerError> {
+        self.signed(i128::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), SerError> {
+        self.signed(i128::from(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), SerError> {
+        self.signed(i128::from(v))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<(), SerError> {
+        self.signed(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), SerError> {
+        self.serialize_u64(u64::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), SerError> {
+        self.serialize_u64(u64::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), SerError> {
+        self.serialize_u64(u64::from(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), SerError> {
+        write_head(&mut self.buf, MAJOR_UNSIGNED, v);
+        Ok(())
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<(), SerError> {
+        if v > u128::from(::std::u64::MAX) {
+            return Err(SerError(format!("Integer out of range for CBOR: {}", v)));
+        }
+        self.serialize_u64(v as u64)
+    }
+
+    fn serialize_f32(self, _: f32) -> Result<(), SerError> {
+        Err(SerError("Floating point numbers are not supported".into()))
+    }
+
+    fn serialize_f64(self, _: f64) -> Result<(), SerError> {
+        Err(SerError("Floating point numbers are not supported".into()))
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), SerError> {
+        let mut buf = [0; 4];
+        self.serialize_str(v.encode_utf8(&mut buf))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), SerError> {
+        self.buf.extend(text_key(v));
+        Ok(())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), SerError> {
+        write_head(&mut self.buf, MAJOR_BYTES, v.len() as u64);
+        self.buf.extend(v);
+        Ok(())
+    }
+
+    fn serialize_none(self) -> Result<(), SerError> {
+        self.serialize_unit()
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), SerError> {
+        self.buf.push(NULL);
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<(), SerError> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+    ) -> Result<(), SerError> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T>(self, _: &'static str, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.begin_variant(variant);
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq, SerError> {
+        Ok(SeqSerializer {
+            ser: self,
+            items: Vec::new(),
+            len: 0,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, SerError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, SerError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, SerError> {
+        self.begin_variant(variant);
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, SerError> {
+        Ok(MapSerializer {
+            ser: self,
+            entries: BTreeMap::new(),
+            next_key: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, SerError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, SerError> {
+        self.begin_variant(variant);
+        self.serialize_map(Some(len))
+    }
+}
+
+/// Arrays need their length up front, so elements are buffered until the end.
+struct SeqSerializer<'a> {
+    ser: &'a mut CanonicalSerializer,
+    items: Vec<u8>,
+    len: u64,
+}
+
+impl<'a> SeqSerializer<'a> {
+    fn element<T>(&mut self, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.items.extend(to_buf(value)?);
+        self.len += 1;
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), SerError> {
+        write_head(&mut self.ser.buf, MAJOR_ARRAY, self.len);
+        self.ser.buf.extend(self.items);
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeSeq for SeqSerializer<'a> {
+    type Ok = ();
+    type Error = SerError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), SerError> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeTuple for SeqSerializer<'a> {
+    type Ok = ();
+    type Error = SerError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), SerError> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeTupleStruct for SeqSerializer<'a> {
+    type Ok = ();
+    type Error = SerError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), SerError> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeTupleVariant for SeqSerializer<'a> {
+    type Ok = ();
+    type Error = SerError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), SerError> {
+        self.finish()
+    }
+}
+
+/// Map entries are keyed by the encoding of their key, which is exactly the order deterministic
+/// CBOR requires.
+struct MapSerializer<'a> {
+    ser: &'a mut CanonicalSerializer,
+    entries: BTreeMap<Vec<u8>, Vec<u8>>,
+    next_key: Option<Vec<u8>>,
+}
+
+impl<'a> MapSerializer<'a> {
+    fn entry<T>(&mut self, key: Vec<u8>, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized,
+    {
+        let value = to_buf(value)?;
+        if self.entries.insert(key, value).is_some() {
+            return Err(SerError("Duplicate map key".into()));
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), SerError> {
+        write_head(&mut self.ser.buf, MAJOR_MAP, self.entries.len() as u64);
+        for (k, v) in self.entries {
+            self.ser.buf.extend(k);
+            self.ser.buf.extend(v);
+        }
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeMap for MapSerializer<'a> {
+    type Ok = ();
+    type Error = SerError;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.next_key = Some(to_buf(key)?);
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized,
+    {
+        let key = self
+            .next_key
+            .take()
+            .ok_or_else(|| SerError("serialize_value called before serialize_key".into()))?;
+        self.entry(key, value)
+    }
+
+    fn end(self) -> Result<(), SerError> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeStruct for MapSerializer<'a> {
+    type Ok = ();
+    type Error = SerError;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.entry(text_key(key), value)
+    }
+
+    fn end(self) -> Result<(), SerError> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeStructVariant for MapSerializer<'a> {
+    type Ok = ();
+    type Error = SerError;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.entry(text_key(key), value)
+    }
+
+    fn end(self) -> Result<(), SerError> {
+        self.finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn rfc_8949_examples() {
+        // from appendix A
+        assert_eq!(to_vec(&0).unwrap(), vec![0x00]);
+        assert_eq!(to_vec(&23).unwrap(), vec![0x17]);
+        assert_eq!(to_vec(&24).unwrap(), vec![0x18, 0x18]);
+        assert_eq!(to_vec(&1000).unwrap(), vec![0x19, 0x03, 0xe8]);
+        assert_eq!(
+            to_vec(&1_000_000_000_000u64).unwrap(),
+            vec![0x1b, 0x00, 0x00, 0x00, 0xe8, 0xd4, 0xa5, 0x10, 0x00]
+        );
+        assert_eq!(
+            to_vec(&::std::u64::MAX).unwrap(),
+            vec![0x1b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]
+        );
+        assert_eq!(to_vec(&-1).unwrap(), vec![0x20]);
+        assert_eq!(to_vec(&-1000).unwrap(), vec![0x39, 0x03, 0xe7]);
+        assert_eq!(to_vec(&false).unwrap(), vec![0xf4]);
+        assert_eq!(to_vec(&()).unwrap(), vec![0xf6]);
+        assert_eq!(to_vec("IETF").unwrap(), b"\x64IETF".to_vec());
+        assert_eq!(to_vec("\u{fc}").unwrap(), vec![0x62, 0xc3, 0xbc]);
+        assert_eq!(
+            to_vec(&vec![1, 2, 3]).unwrap(),
+            vec![0x83, 0x01, 0x02, 0x03]
+        );
+        assert!(to_vec(&1.5).is_err());
+    }
+
+    #[test]
+    fn map_keys_sorted_by_encoding() {
+        let mut map = HashMap::new();
+        let _ = map.insert("aa", 1);
+        let _ = map.insert("b", 2);
+        let _ = map.insert("a", 3);
+
+        // shorter keys sort first because their head is smaller
+        assert_eq!(
+            to_vec(&map).unwrap(),
+            vec![0xa3, 0x61, b'a', 0x03, 0x61, b'b', 0x02, 0x62, b'a', b'a', 0x01]
+        );
+    }
+
+    #[test]
+    fn extract_signed_field() {
+        let mut map = BTreeMap::new();
+        let _ = map.insert("signatures", vec![vec![1, 2], vec![]]);
+        let _ = map.insert("signed", vec![vec![3], vec![300, 4]]);
+        let bytes = to_vec(&map).unwrap();
+
+        assert_eq!(
+            extract_field(&bytes, "signed").unwrap(),
+            &*to_vec(&vec![vec![3], vec![300, 4]]).unwrap()
+        );
+        assert!(extract_field(&bytes, "missing").is_err());
+
+        // `signed` is shorter than `signatures`, so it's the first entry
+        let signed_end = 1 + text_key("signed").len() + to_vec(&map["signed"]).unwrap().len();
+        assert!(extract_field(&bytes[..signed_end - 1], "signed").is_err());
+    }
+}
@@ -1,6 +1,12 @@
 use itoa;
+use serde::de::{self, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{self, Serialize};
 use serde_json;
 use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+use std::io::Write;
+use std::ops::Range;
+use std::str;
 
 pub fn canonicalize(jsn: &serde_json::Value) -> Result<Vec<u8>, String> {
     let converted = convert(jsn)?;
@@ -116,9 +122,874 @@ fn convert(jsn: &serde_json::Value) -> Result<Value, String> {
     }
 }
 
+/// Serialize `value` as canonical JSON directly into `writer`, without building an intermediate
+/// `serde_json::Value`. The output is identical to `canonicalize(&serde_json::to_value(value)?)`.
+///
+/// Object keys must be written in sorted order, but serde visits them in declaration order. So
+/// `value` is serialized once into a single `Canonical` buffer that records where each object's
+/// entries are, and that buffer is then written out in one pass with every object's entries in
+/// key order. Each byte of output is buffered exactly once, however deeply objects are nested.
+pub fn to_writer<W, T>(mut writer: W, value: &T) -> Result<(), String>
+where
+    W: Write,
+    T: Serialize + ?Sized,
+{
+    let mut canonical = Canonical::default();
+    value.serialize(&mut canonical).map_err(|e| e.0)?;
+    canonical.write_to(&mut writer).map_err(|e| e.0)
+}
+
+/// Like `to_writer`, but returns the bytes.
+pub fn to_vec<T>(value: &T) -> Result<Vec<u8>, String>
+where
+    T: Serialize + ?Sized,
+{
+    let mut buf = Vec::new();
+    to_writer(&mut buf, value)?;
+    Ok(buf)
+}
+
+#[derive(Debug)]
+struct SerError(String);
+
+impl Display for SerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl ::std::error::Error for SerError {}
+
+impl ser::Error for SerError {
+    fn custom<T: Display>(msg: T) -> Self {
+        SerError(msg.to_string())
+    }
+}
+
+fn write_err(err: ::std::io::Error) -> SerError {
+    SerError(format!("Write error: {}", err))
+}
+
+fn write_escaped<W: Write>(writer: &mut W, s: &str) -> Result<(), SerError> {
+    // serde_json's escaping is what `canonicalize` uses, so reuse it here too
+    serde_json::to_writer(writer, s).map_err(|e| SerError(format!("{:?}", e)))
+}
+
+/// Canonical JSON that has been serialized, but whose objects are not yet in key order.
+///
+/// Everything is appended to `buf` in the order serde produces it. `pieces` says how to read it
+/// back: runs of bytes that are copied as they are, and objects, whose entries are written in key
+/// order. The pieces of an object's entries follow the object's own piece.
+#[derive(Default)]
+struct Canonical {
+    buf: Vec<u8>,
+    pieces: Vec<Piece>,
+    /// Where the bytes in `buf` that aren't part of a piece yet start.
+    pending: usize,
+}
+
+enum Piece {
+    /// `buf[range]`, copied as it is.
+    Bytes(Range<usize>),
+    /// An object. Its entries' pieces are the ones after this, up to `end`.
+    Object { entries: Vec<Entry>, end: usize },
+}
+
+struct Entry {
+    /// The unescaped key, in `buf`.
+    key: Range<usize>,
+    /// The value, in `pieces`.
+    value: Range<usize>,
+}
+
+impl Canonical {
+    fn write(&mut self, bytes: &[u8]) -> Result<(), SerError> {
+        self.buf.extend_from_slice(bytes);
+        Ok(())
+    }
+
+    fn write_integer<I: itoa::Integer>(&mut self, n: I) -> Result<(), SerError> {
+        itoa::write(&mut self.buf, n).map(|_| ()).map_err(write_err)
+    }
+
+    /// Open the `{"variant":` wrapper serde_json uses for non-unit enum variants.
+    fn begin_variant(&mut self, variant: &str) -> Result<(), SerError> {
+        self.write(b"{")?;
+        write_escaped(&mut self.buf, variant)?;
+        self.write(b":")
+    }
+
+    /// End the current run of bytes, so that the pieces after it can be reordered.
+    fn flush(&mut self) {
+        if self.pending < self.buf.len() {
+            self.pieces.push(Piece::Bytes(self.pending..self.buf.len()));
+            self.pending = self.buf.len();
+        }
+    }
+
+    /// Start an object, returning the index of its piece.
+    fn begin_object(&mut self) -> usize {
+        self.flush();
+        self.pieces.push(Piece::Object {
+            entries: Vec::new(),
+            end: 0,
+        });
+        self.pieces.len() - 1
+    }
+
+    /// Store an object key. Keys are not part of any piece, as they're written from their entry.
+    fn key(&mut self, key: &str) -> Range<usize> {
+        self.flush();
+        let start = self.buf.len();
+        self.buf.extend_from_slice(key.as_bytes());
+        self.pending = self.buf.len();
+        start..self.buf.len()
+    }
+
+    /// Finish the object started at `index`, once the pieces of all its `entries` are stored.
+    fn end_object(&mut self, index: usize, mut entries: Vec<Entry>) -> Result<(), SerError> {
+        self.flush();
+        {
+            let buf = &self.buf;
+            entries.sort_by(|a, b| buf[a.key.clone()].cmp(&buf[b.key.clone()]));
+            if let Some(dup) = entries
+                .windows(2)
+                .find(|w| buf[w[0].key.clone()] == buf[w[1].key.clone()])
+            {
+                return Err(SerError(format!(
+                    "Duplicate object key: {}",
+                    String::from_utf8_lossy(&buf[dup[0].key.clone()])
+                )));
+            }
+        }
+        self.pieces[index] = Piece::Object {
+            entries,
+            end: self.pieces.len(),
+        };
+        Ok(())
+    }
+
+    fn write_to<W: Write>(&mut self, writer: &mut W) -> Result<(), SerError> {
+        self.flush();
+        self.write_pieces(writer, 0..self.pieces.len())
+    }
+
+    fn write_pieces<W: Write>(&self, writer: &mut W, pieces: Range<usize>) -> Result<(), SerError> {
+        let mut i = pieces.start;
+        while i < pieces.end {
+            match self.pieces[i] {
+                Piece::Bytes(ref range) => {
+                    writer
+                        .write_all(&self.buf[range.clone()])
+                        .map_err(write_err)?;
+                    i += 1;
+                }
+                Piece::Object { ref entries, end } => {
+                    writer.write_all(b"{").map_err(write_err)?;
+                    for (n, entry) in entries.iter().enumerate() {
+                        if n > 0 {
+                            writer.write_all(b",").map_err(write_err)?;
+                        }
+                        let key = str::from_utf8(&self.buf[entry.key.clone()])
+                            .map_err(|e| SerError(format!("Bad object key: {}", e)))?;
+                        write_escaped(writer, key)?;
+                        writer.write_all(b":").map_err(write_err)?;
+                        self.write_pieces(writer, entry.value.clone())?;
+                    }
+                    writer.write_all(b"}").map_err(write_err)?;
+                    i = end;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> ser::Serializer for &'a mut Canonical {
+    type Ok = ();
+    type Error = SerError;
+
+    type SerializeSeq = SeqSerializer<'a>;
+    type SerializeTuple = SeqSerializer<'a>;
+    type SerializeTupleStruct = SeqSerializer<'a>;
+    type SerializeTupleVariant = SeqSerializer<'a>;
+    type SerializeMap = ObjectSerializer<'a>;
+    type SerializeStruct = ObjectSerializer<'a>;
+    type SerializeStructVariant = ObjectSerializer<'a>;
+
+    fn serialize_bool(self, v: bool) -> Result<(), SerError> {
+        self.write(if v { b"true" } else { b"false" })
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), SerError> {
+        self.write_integer(v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), SerError> {
+        self.write_integer(v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), SerError> {
+        self.write_integer(v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), SerError> {
+        self.write_integer(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), SerError> {
+        self.write_integer(v)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), SerError> {
+        self.write_integer(v)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), SerError> {
+        self.write_integer(v)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), SerError> {
+        self.write_integer(v)
+    }
+
+    fn serialize_f32(self, _: f32) -> Result<(), SerError> {
+        Err(SerError("only i64 and u64 are supported".into()))
+    }
+
+    fn serialize_f64(self, _: f64) -> Result<(), SerError> {
+        Err(SerError("only i64 and u64 are supported".into()))
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), SerError> {
+        let mut buf = [0; 4];
+        self.serialize_str(v.encode_utf8(&mut buf))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), SerError> {
+        write_escaped(&mut self.buf, v)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), SerError> {
+        let mut seq = ser::Serializer::serialize_seq(self, Some(v.len()))?;
+        for b in v {
+            ser::SerializeSeq::serialize_element(&mut seq, b)?;
+        }
+        ser::SerializeSeq::end(seq)
+    }
+
+    fn serialize_none(self) -> Result<(), SerError> {
+        self.serialize_unit()
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), SerError> {
+        self.write(b"null")
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<(), SerError> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+    ) -> Result<(), SerError> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T>(self, _: &'static str, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.begin_variant(variant)?;
+        value.serialize(&mut *self)?;
+        self.write(b"}")
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq, SerError> {
+        self.write(b"[")?;
+        Ok(SeqSerializer {
+            ser: self,
+            first: true,
+            in_variant: false,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, SerError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, SerError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, SerError> {
+        self.begin_variant(variant)?;
+        let mut seq = self.serialize_seq(Some(len))?;
+        seq.in_variant = true;
+        Ok(seq)
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, SerError> {
+        Ok(ObjectSerializer {
+            index: self.begin_object(),
+            ser: self,
+            entries: Vec::new(),
+            next_key: None,
+            in_variant: false,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, SerError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, SerError> {
+        self.begin_variant(variant)?;
+        let mut obj = self.serialize_map(Some(len))?;
+        obj.in_variant = true;
+        Ok(obj)
+    }
+}
+
+struct SeqSerializer<'a> {
+    ser: &'a mut Canonical,
+    first: bool,
+    in_variant: bool,
+}
+
+impl<'a> SeqSerializer<'a> {
+    fn element<T>(&mut self, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized,
+    {
+        if !self.first {
+            self.ser.write(b",")?;
+        }
+        self.first = false;
+        value.serialize(&mut *self.ser)
+    }
+
+    fn finish(self) -> Result<(), SerError> {
+        self.ser.write(b"]")?;
+        if self.in_variant {
+            self.ser.write(b"}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeSeq for SeqSerializer<'a> {
+    type Ok = ();
+    type Error = SerError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), SerError> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeTuple for SeqSerializer<'a> {
+    type Ok = ();
+    type Error = SerError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), SerError> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeTupleStruct for SeqSerializer<'a> {
+    type Ok = ();
+    type Error = SerError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), SerError> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeTupleVariant for SeqSerializer<'a> {
+    type Ok = ();
+    type Error = SerError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), SerError> {
+        self.finish()
+    }
+}
+
+/// Stores each entry's value as it comes, and puts the entries in key order when the object ends.
+struct ObjectSerializer<'a> {
+    ser: &'a mut Canonical,
+    index: usize,
+    entries: Vec<Entry>,
+    next_key: Option<Range<usize>>,
+    in_variant: bool,
+}
+
+impl<'a> ObjectSerializer<'a> {
+    fn entry<T>(&mut self, key: Range<usize>, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized,
+    {
+        let start = self.ser.pieces.len();
+        value.serialize(&mut *self.ser)?;
+        self.ser.flush();
+        self.entries.push(Entry {
+            key,
+            value: start..self.ser.pieces.len(),
+        });
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), SerError> {
+        self.ser.end_object(self.index, self.entries)?;
+        if self.in_variant {
+            self.ser.write(b"}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeMap for ObjectSerializer<'a> {
+    type Ok = ();
+    type Error = SerError;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized,
+    {
+        let key = key.serialize(MapKeySerializer)?;
+        self.next_key = Some(self.ser.key(&key));
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized,
+    {
+        let key = self
+            .next_key
+            .take()
+            .ok_or_else(|| SerError("serialize_value called before serialize_key".into()))?;
+        self.entry(key, value)
+    }
+
+    fn end(self) -> Result<(), SerError> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeStruct for ObjectSerializer<'a> {
+    type Ok = ();
+    type Error = SerError;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized,
+    {
+        let key = self.ser.key(key);
+        self.entry(key, value)
+    }
+
+    fn end(self) -> Result<(), SerError> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeStructVariant for ObjectSerializer<'a> {
+    type Ok = ();
+    type Error = SerError;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized,
+    {
+        let key = self.ser.key(key);
+        self.entry(key, value)
+    }
+
+    fn end(self) -> Result<(), SerError> {
+        self.finish()
+    }
+}
+
+/// Turns map keys into strings the same way `serde_json::to_value` does.
+struct MapKeySerializer;
+
+fn key_must_be_a_string() -> SerError {
+    SerError("key must be a string".into())
+}
+
+impl ser::Serializer for MapKeySerializer {
+    type Ok = String;
+    type Error = SerError;
+
+    type SerializeSeq = ser::Impossible<String, SerError>;
+    type SerializeTuple = ser::Impossible<String, SerError>;
+    type SerializeTupleStruct = ser::Impossible<String, SerError>;
+    type SerializeTupleVariant = ser::Impossible<String, SerError>;
+    type SerializeMap = ser::Impossible<String, SerError>;
+    type SerializeStruct = ser::Impossible<String, SerError>;
+    type SerializeStructVariant = ser::Impossible<String, SerError>;
+
+    fn serialize_bool(self, _: bool) -> Result<String, SerError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<String, SerError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<String, SerError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<String, SerError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<String, SerError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<String, SerError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<String, SerError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<String, SerError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<String, SerError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_f32(self, _: f32) -> Result<String, SerError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_f64(self, _: f64) -> Result<String, SerError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_char(self, v: char) -> Result<String, SerError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<String, SerError> {
+        Ok(v.into())
+    }
+
+    fn serialize_bytes(self, _: &[u8]) -> Result<String, SerError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_none(self) -> Result<String, SerError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_some<T>(self, _: &T) -> Result<String, SerError>
+    where
+        T: Serialize + ?Sized,
+    {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit(self) -> Result<String, SerError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<String, SerError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+    ) -> Result<String, SerError> {
+        Ok(variant.into())
+    }
+
+    fn serialize_newtype_struct<T>(self, _: &'static str, value: &T) -> Result<String, SerError>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> Result<String, SerError>
+    where
+        T: Serialize + ?Sized,
+    {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq, SerError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple, SerError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleStruct, SerError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleVariant, SerError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, SerError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStruct, SerError> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant, SerError> {
+        Err(key_must_be_a_string())
+    }
+}
+
+/// Re-encode the JSON in `bytes` in canonical form without building a `serde_json::Value`.
+pub fn recanonicalize(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let mut canonical = Canonical::default();
+    let mut de = serde_json::Deserializer::from_slice(bytes);
+    CanonicalSeed {
+        ser: &mut canonical,
+        comma: false,
+    }
+    .deserialize(&mut de)
+    .and_then(|()| de.end())
+    .map_err(|e| format!("{:?}", e))?;
+
+    let mut buf = Vec::new();
+    canonical.write_to(&mut buf).map_err(|e| e.0)?;
+    Ok(buf)
+}
+
+/// Deserializes any JSON value straight into a `Canonical`, preceded by a comma if `comma` is set.
+struct CanonicalSeed<'a> {
+    ser: &'a mut Canonical,
+    comma: bool,
+}
+
+impl<'a> CanonicalSeed<'a> {
+    fn encode<T, E>(self, value: &T) -> Result<(), E>
+    where
+        T: Serialize + ?Sized,
+        E: de::Error,
+    {
+        value.serialize(self.ser).map_err(E::custom)
+    }
+}
+
+impl<'a, 'de> DeserializeSeed<'de> for CanonicalSeed<'a> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, de: D) -> Result<(), D::Error> {
+        if self.comma {
+            self.ser.write(b",").map_err(de::Error::custom)?;
+        }
+        de.deserialize_any(self)
+    }
+}
+
+impl<'a, 'de> Visitor<'de> for CanonicalSeed<'a> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON value")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<(), E> {
+        self.encode(&v)
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<(), E> {
+        self.encode(&v)
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<(), E> {
+        self.encode(&v)
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<(), E> {
+        self.encode(&v)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<(), E> {
+        self.encode(v)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<(), E> {
+        self.encode(&())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        self.ser.write(b"[").map_err(de::Error::custom)?;
+        let mut comma = false;
+        while let Some(()) = seq.next_element_seed(CanonicalSeed {
+            ser: &mut *self.ser,
+            comma,
+        })? {
+            comma = true;
+        }
+        self.ser.write(b"]").map_err(de::Error::custom)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let index = self.ser.begin_object();
+        let mut entries = Vec::new();
+        while let Some(key) = map.next_key_seed(KeySeed(&mut *self.ser))? {
+            let start = self.ser.pieces.len();
+            map.next_value_seed(CanonicalSeed {
+                ser: &mut *self.ser,
+                comma: false,
+            })?;
+            self.ser.flush();
+            entries.push(Entry {
+                key,
+                value: start..self.ser.pieces.len(),
+            });
+        }
+        self.ser
+            .end_object(index, entries)
+            .map_err(de::Error::custom)
+    }
+}
+
+/// Deserializes an object key into a `Canonical`.
+struct KeySeed<'a>(&'a mut Canonical);
+
+impl<'a, 'de> DeserializeSeed<'de> for KeySeed<'a> {
+    type Value = Range<usize>;
+
+    fn deserialize<D: Deserializer<'de>>(self, de: D) -> Result<Range<usize>, D::Error> {
+        de.deserialize_str(self)
+    }
+}
+
+impl<'a, 'de> Visitor<'de> for KeySeed<'a> {
+    type Value = Range<usize>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an object key")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Range<usize>, E> {
+        Ok(self.0.key(v))
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use serde_derive::Serialize;
+    use serde_json::json;
+    use std::collections::HashMap;
 
     #[test]
     fn write_str() {
@@ -153,4 +1024,116 @@ mod test {
         jsn.write(&mut out).unwrap();
         assert_eq!(&out, &b"{\"lol\":[\"haha\",\"new\\nline\"]}");
     }
+
+    fn assert_same<T: Serialize>(value: &T) {
+        let tree = canonicalize(&serde_json::to_value(value).unwrap()).unwrap();
+        assert_eq!(to_vec(value).unwrap(), tree);
+    }
+
+    #[test]
+    fn stream_matches_tree() {
+        assert_same(&json!({
+            "z": [1, -2, 18446744073709551615u64, null, true, false],
+            "a": {"nested": {"c": "new\nline", "b": "\u{1F980} \"quoted\" \\"}},
+            "": [],
+            "\u{e9}": {},
+            "A": "upper sorts first",
+        }));
+
+        #[derive(Serialize)]
+        enum Variant {
+            Unit,
+            Newtype(u32),
+            Tuple(u8, String),
+            Struct { y: bool, x: Option<u8> },
+        }
+
+        #[derive(Serialize)]
+        struct Thing {
+            zeta: Vec<Variant>,
+            alpha: HashMap<u32, &'static str>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            skipped: Option<u8>,
+            bytes: &'static [u8],
+            ch: char,
+        }
+
+        let mut alpha = HashMap::new();
+        let _ = alpha.insert(10, "ten");
+        let _ = alpha.insert(9, "nine");
+
+        assert_same(&Thing {
+            zeta: vec![
+                Variant::Unit,
+                Variant::Newtype(7),
+                Variant::Tuple(1, "one".into()),
+                Variant::Struct { y: true, x: None },
+            ],
+            alpha,
+            skipped: None,
+            bytes: b"\x00\xff",
+            ch: '\u{7f}',
+        });
+    }
+
+    #[test]
+    fn stream_rejects_floats() {
+        assert!(to_vec(&1.5f64).is_err());
+        assert!(to_vec(&vec![1.0f32]).is_err());
+        assert!(to_vec(&json!({ "a": 0.1 })).is_err());
+    }
+
+    #[test]
+    fn stream_rejects_duplicate_keys() {
+        struct Dup;
+
+        impl Serialize for Dup {
+            fn serialize<S: ser::Serializer>(&self, ser: S) -> Result<S::Ok, S::Error> {
+                use serde::ser::SerializeMap;
+                let mut map = ser.serialize_map(Some(2))?;
+                map.serialize_entry("a", &1)?;
+                map.serialize_entry("a", &2)?;
+                map.end()
+            }
+        }
+
+        assert!(to_vec(&Dup).is_err());
+    }
+
+    #[test]
+    fn stream_buffers_each_byte_once() {
+        // a long string inside many levels of objects, with keys that arrive out of order
+        let mut value = serde_json::Value::String("x".repeat(1 << 16));
+        for i in 0..64 {
+            let mut map = serde_json::Map::new();
+            let _ = map.insert(format!("level{}", i), value);
+            let _ = map.insert("".into(), json!([i]));
+            value = serde_json::Value::Object(map);
+        }
+
+        let mut canonical = Canonical::default();
+        value.serialize(&mut canonical).unwrap();
+        let mut out = Vec::new();
+        canonical.write_to(&mut out).unwrap();
+
+        assert_eq!(out, canonicalize(&value).unwrap());
+        assert!(canonical.buf.len() <= out.len());
+        assert!(canonical.pieces.len() <= 4 * 64 + 1);
+    }
+
+    #[test]
+    fn recanonicalize_matches_tree() {
+        let jsn: &[u8] = br#"{
+            "z": [1, -2, 18446744073709551615, null, true, false],
+            "a": {"nested": {"c": "new\nline", "b": "\ud83e\udd80 \"quoted\" \\ \u00e9"}},
+            "": [],
+            "A": "upper sorts first"
+        }"#;
+        let tree = canonicalize(&serde_json::from_slice(jsn).unwrap()).unwrap();
+        assert_eq!(recanonicalize(jsn).unwrap(), tree);
+        assert_eq!(recanonicalize(&tree).unwrap(), tree);
+
+        assert!(recanonicalize(br#"{"a": 1.5}"#).is_err());
+        assert!(recanonicalize(br#"{"a": 1, "a": 2}"#).is_err());
+    }
 }
@@ -1,12 +1,16 @@
 //! Structures and functions to aid in various TUF data interchange formats.
 
+#[cfg(feature = "cbor")]
+mod cbor;
 mod cjson;
 
 use serde::de::DeserializeOwned;
 use serde::ser::Serialize;
-use serde_json;
+use serde_derive::Deserialize;
+use serde_json::{self, value::RawValue};
 use std::fmt::Debug;
 use std::io::{Read, Write};
+use std::marker::PhantomData;
 
 use crate::error::Error;
 use crate::Result;
@@ -22,11 +26,50 @@ pub trait DataInterchange: Debug + PartialEq + Clone {
     /// A function that canonicalizes data to allow for deterministic signatures.
     fn canonicalize(raw_data: &Self::RawData) -> Result<Vec<u8>>;
 
+    /// Serialize `data` straight into its canonical form. This is equivalent to
+    /// `canonicalize(&serialize(data)?)`, but implementations may avoid building `RawData`.
+    fn to_canonical_bytes<T>(data: &T) -> Result<Vec<u8>>
+    where
+        T: Serialize,
+    {
+        Self::canonicalize(&Self::serialize(data)?)
+    }
+
     /// Deserialize from `RawData`.
     fn deserialize<T>(raw_data: &Self::RawData) -> Result<T>
     where
         T: DeserializeOwned;
 
+    /// Split serialized `SignedMetadata` into its `signatures`, deserialized as `S`, and the exact
+    /// bytes of its `signed` portion, in a single pass over the document.
+    ///
+    /// By default the `signed` portion is parsed and canonicalized again, so the bytes are only
+    /// exactly what was received if it was already canonical. Implementations should override
+    /// this if they can slice the `signed` portion out of the document as it is.
+    fn extract_signed<S>(signed_metadata: &[u8]) -> Result<(S, Vec<u8>)>
+    where
+        S: DeserializeOwned,
+    {
+        #[derive(Deserialize)]
+        #[serde(bound(deserialize = "S: DeserializeOwned, R: DeserializeOwned"))]
+        struct Envelope<S, R> {
+            signatures: S,
+            signed: R,
+        }
+
+        let envelope: Envelope<S, Self::RawData> = Self::from_slice(signed_metadata)?;
+        Ok((envelope.signatures, Self::canonicalize(&envelope.signed)?))
+    }
+
+    /// Check that `bytes` are already in canonical form.
+    fn ensure_canonical(bytes: &[u8]) -> Result<()> {
+        let raw: Self::RawData = Self::from_slice(bytes)?;
+        if Self::canonicalize(&raw)? != bytes {
+            return Err(Error::Encoding("Data was not canonical".into()));
+        }
+        Ok(())
+    }
+
     /// Serialize into `RawData`.
     fn serialize<T>(data: &T) -> Result<Self::RawData>
     where
@@ -51,9 +94,59 @@ pub trait DataInterchange: Debug + PartialEq + Clone {
     fn from_slice<T>(slice: &[u8]) -> Result<T>
     where
         T: DeserializeOwned;
+
+    /// Whether the `expires` timestamp of metadata must be in the form this library writes it,
+    /// `YYYY-MM-DDThh:mm:ssZ` with fractional seconds only when they are non-zero. If `false`,
+    /// `SignedMetadata::from_slice` accepts any RFC 3339 timestamp with an offset equivalent to
+    /// UTC. Deserializing metadata directly is always strict.
+    fn strict_datetimes() -> bool {
+        true
+    }
+}
+
+/// Whether a data interchange accepts timestamps that are not in their canonical form.
+pub trait DatetimePolicy: Debug + PartialEq + Clone {
+    /// See `DataInterchange::strict_datetimes`.
+    fn strict() -> bool;
 }
 
-/// JSON data interchange.
+/// Only accept timestamps in their canonical form. This is the default.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StrictDatetimes;
+
+impl DatetimePolicy for StrictDatetimes {
+    fn strict() -> bool {
+        true
+    }
+}
+
+/// Accept any RFC 3339 timestamp with an offset equivalent to UTC, for metadata written by other
+/// tools.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LenientDatetimes;
+
+impl DatetimePolicy for LenientDatetimes {
+    fn strict() -> bool {
+        false
+    }
+}
+
+/// JSON data interchange that only accepts timestamps in their canonical form.
+///
+/// ```
+/// # use tuf::interchange::{DataInterchange, Json, LenientJson};
+/// assert!(Json::strict_datetimes());
+/// assert!(!LenientJson::strict_datetimes());
+/// assert_eq!(LenientJson::extension(), "json");
+/// ```
+pub type Json = JsonInterchange<StrictDatetimes>;
+
+/// JSON data interchange that accepts timestamps written with fractional seconds or a `+00:00`
+/// offset.
+pub type LenientJson = JsonInterchange<LenientDatetimes>;
+
+/// JSON data interchange, parameterized by how strictly it parses timestamps. Use it through the
+/// `Json` or `LenientJson` aliases.
 ///
 /// # Schema
 ///
@@ -64,7 +157,13 @@ pub trait DataInterchange: Debug + PartialEq + Clone {
 ///
 /// `NATURAL_NUMBER` is an integer in the range `[1, 2**32)`.
 ///
-/// `EXPIRES` is an ISO-8601 date time in format `YYYY-MM-DD'T'hh:mm:ss'Z'`.
+/// `EXPIRES` is an ISO-8601 date time in format `YYYY-MM-DD'T'hh:mm:ss'Z'`, with fractional seconds
+/// if they are non-zero. `LenientJson` accepts any RFC 3339 timestamp with an offset equivalent to
+/// UTC.
+///
+/// `SPEC_VERSION` is the version of the TUF specification the metadata conforms to, in the format
+/// `MAJOR.MINOR.PATCH`. It may be absent from metadata written by earlier releases of this
+/// library.
 ///
 /// `KEY_ID` is the base64url encoded value of `sha256(spki(pub_key))`.
 ///
@@ -133,16 +232,20 @@ pub trait DataInterchange: Debug + PartialEq + Clone {
 /// ```bash
 /// {
 ///   "type": "root",
+///   "spec_version": SPEC_VERSION,
 ///   "version": NATURAL_NUMBER,
 ///   "expires": EXPIRES,
 ///   "keys": [PUB_KEY, ...]
 ///   "root": ROLE_DESCRIPTION,
 ///   "snapshot": ROLE_DESCRIPTION,
 ///   "targets": ROLE_DESCRIPTION,
-///   "timestamp": ROLE_DESCRIPTION
+///   "timestamp": ROLE_DESCRIPTION,
+///   "mirrors": ROLE_DESCRIPTION
 /// }
 /// ```
 ///
+/// `mirrors` is optional.
+///
 /// `ROLE_DESCRIPTION` is the following:
 ///
 /// ```bash
@@ -157,6 +260,7 @@ pub trait DataInterchange: Debug + PartialEq + Clone {
 /// ```bash
 /// {
 ///   "type": "snapshot",
+///   "spec_version": SPEC_VERSION,
 ///   "version": NATURAL_NUMBER,
 ///   "expires": EXPIRES,
 ///   "meta": {
@@ -173,6 +277,7 @@ pub trait DataInterchange: Debug + PartialEq + Clone {
 /// ```bash
 /// {
 ///   "type": "timestamp",
+///   "spec_version": SPEC_VERSION,
 ///   "version": NATURAL_NUMBER,
 ///   "expires": EXPIRES,
 ///   "targets": {
@@ -216,14 +321,46 @@ pub trait DataInterchange: Debug + PartialEq + Clone {
 /// ```bash
 /// {
 ///   "type": "timestamp",
+///   "spec_version": SPEC_VERSION,
 ///   "version": NATURAL_NUMBER,
 ///   "expires": EXPIRES,
 ///   "snapshot": METADATA_DESCRIPTION
 /// }
 /// ```
+///
+/// ## `MirrorsMetadata`
+///
+/// ```bash
+/// {
+///   "type": "mirrors",
+///   "spec_version": SPEC_VERSION,
+///   "version": NATURAL_NUMBER,
+///   "expires": EXPIRES,
+///   "mirrors": [MIRROR, ...]
+/// }
+/// ```
+///
+/// `MIRROR` is:
+///
+/// ```bash
+/// {
+///   "urlbase": URL,
+///   "metapath": PATH,
+///   "targetspath": PATH,
+///   "metacontent": [PATTERN, ...],
+///   "targetscontent": [PATTERN, ...]
+/// }
+/// ```
+///
+/// `PATTERN` is a string where `*` matches any sequence of characters and `?` matches any single
+/// character.
 #[derive(Debug, Clone, PartialEq)]
-pub struct Json {}
-impl DataInterchange for Json {
+pub struct JsonInterchange<P>(PhantomData<P>);
+
+impl<P> DataInterchange for JsonInterchange<P>
+where
+    P: DatetimePolicy,
+{
     type RawData = serde_json::Value;
 
     /// ```
@@ -246,6 +383,25 @@ impl DataInterchange for Json {
         cjson::canonicalize(raw_data).map_err(Error::Opaque)
     }
 
+    /// ```
+    /// # use tuf::interchange::{DataInterchange, Json};
+    /// # use std::collections::BTreeMap;
+    /// let mut map = BTreeMap::new();
+    /// map.insert("foo", vec![1, 2]);
+    /// map.insert("bar", vec![]);
+    /// let out = Json::to_canonical_bytes(&map).unwrap();
+    /// assert_eq!(out, br#"{"bar":[],"foo":[1,2]}"#);
+    ///
+    /// // floats have no canonical form
+    /// assert!(Json::to_canonical_bytes(&1.5).is_err());
+    /// ```
+    fn to_canonical_bytes<T>(data: &T) -> Result<Vec<u8>>
+    where
+        T: Serialize,
+    {
+        cjson::to_vec(data).map_err(Error::Opaque)
+    }
+
     /// ```
     /// # use serde_derive::Deserialize;
     /// # use serde_json::json;
@@ -272,6 +428,45 @@ impl DataInterchange for Json {
         Ok(serde_json::from_value(raw_data.clone())?)
     }
 
+    /// ```
+    /// # use tuf::interchange::{DataInterchange, Json};
+    /// let jsn = br#"{"signatures": [], "signed": {"b": 1, "a": [2, 3]}}"#;
+    /// let (signatures, signed) = Json::extract_signed::<Vec<u8>>(jsn).unwrap();
+    /// assert!(signatures.is_empty());
+    /// assert_eq!(signed, br#"{"b": 1, "a": [2, 3]}"#);
+    /// ```
+    fn extract_signed<S>(signed_metadata: &[u8]) -> Result<(S, Vec<u8>)>
+    where
+        S: DeserializeOwned,
+    {
+        #[derive(Deserialize)]
+        #[serde(bound(deserialize = "S: DeserializeOwned"))]
+        struct Envelope<'a, S> {
+            signatures: S,
+            #[serde(borrow)]
+            signed: &'a RawValue,
+        }
+
+        let envelope: Envelope<S> = serde_json::from_slice(signed_metadata)?;
+        Ok((
+            envelope.signatures,
+            envelope.signed.get().as_bytes().to_vec(),
+        ))
+    }
+
+    /// ```
+    /// # use tuf::interchange::{DataInterchange, Json};
+    /// assert!(Json::ensure_canonical(br#"{"a":[1,"b"],"c":null}"#).is_ok());
+    /// assert!(Json::ensure_canonical(br#"{"c":null,"a":[1,"b"]}"#).is_err());
+    /// assert!(Json::ensure_canonical(br#"{"a": [1, "b"], "c": null}"#).is_err());
+    /// ```
+    fn ensure_canonical(bytes: &[u8]) -> Result<()> {
+        if cjson::recanonicalize(bytes).map_err(Error::Encoding)? != bytes {
+            return Err(Error::Encoding("Data was not canonical JSON".into()));
+        }
+        Ok(())
+    }
+
     /// ```
     /// # use serde_derive::Serialize;
     /// # use serde_json::json;
@@ -305,14 +500,12 @@ impl DataInterchange for Json {
     /// Json::to_writer(&mut buf, &arr).unwrap();
     /// assert!(&buf == b"[1, 2, 3]" || &buf == b"[1,2,3]");
     /// ```
-    fn to_writer<W, T: Sized>(mut writer: W, value: &T) -> Result<()>
+    fn to_writer<W, T: Sized>(writer: W, value: &T) -> Result<()>
     where
         W: Write,
         T: Serialize,
     {
-        let bytes = Self::canonicalize(&Self::serialize(value)?)?;
-        writer.write_all(&bytes)?;
-        Ok(())
+        cjson::to_writer(writer, value).map_err(Error::Opaque)
     }
 
     /// ```
@@ -341,4 +534,115 @@ impl DataInterchange for Json {
     {
         Ok(serde_json::from_slice(slice)?)
     }
+
+    fn strict_datetimes() -> bool {
+        P::strict()
+    }
+}
+
+/// CBOR data interchange using the deterministic encoding from
+/// [RFC 8949](https://tools.ietf.org/html/rfc8949#section-4.2.1).
+///
+/// The metadata has the same structure as it does with `Json`. Map keys are text strings, and
+/// values that are base64url strings in JSON remain base64url text strings in CBOR.
+///
+/// This requires the `cbor` feature. Use it through the `Cbor` or `LenientCbor` aliases.
+#[cfg(feature = "cbor")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CborInterchange<P>(PhantomData<P>);
+
+/// CBOR data interchange that only accepts timestamps in their canonical form.
+#[cfg(feature = "cbor")]
+pub type Cbor = CborInterchange<StrictDatetimes>;
+
+/// CBOR data interchange that accepts any RFC 3339 timestamp with an offset equivalent to UTC.
+#[cfg(feature = "cbor")]
+pub type LenientCbor = CborInterchange<LenientDatetimes>;
+
+#[cfg(feature = "cbor")]
+impl<P> DataInterchange for CborInterchange<P>
+where
+    P: DatetimePolicy,
+{
+    type RawData = serde_cbor::Value;
+
+    /// ```
+    /// # use tuf::interchange::{DataInterchange, Cbor};
+    /// assert_eq!(Cbor::extension(), "cbor");
+    /// ```
+    fn extension() -> &'static str {
+        "cbor"
+    }
+
+    /// ```
+    /// # use tuf::interchange::{DataInterchange, Cbor};
+    /// # use std::collections::BTreeMap;
+    /// let mut map = BTreeMap::new();
+    /// map.insert("foo", 1);
+    /// map.insert("a", 2);
+    /// let raw = Cbor::serialize(&map).unwrap();
+    /// let out = Cbor::canonicalize(&raw).unwrap();
+    /// assert_eq!(out, vec![0xa2, 0x61, b'a', 0x02, 0x63, b'f', b'o', b'o', 0x01]);
+    /// ```
+    fn canonicalize(raw_data: &Self::RawData) -> Result<Vec<u8>> {
+        cbor::to_vec(raw_data).map_err(Error::Encoding)
+    }
+
+    fn to_canonical_bytes<T>(data: &T) -> Result<Vec<u8>>
+    where
+        T: Serialize,
+    {
+        cbor::to_vec(data).map_err(Error::Encoding)
+    }
+
+    fn deserialize<T>(raw_data: &Self::RawData) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        Ok(serde_cbor::value::from_value(raw_data.clone())?)
+    }
+
+    fn extract_signed<S>(signed_metadata: &[u8]) -> Result<(S, Vec<u8>)>
+    where
+        S: DeserializeOwned,
+    {
+        let signatures =
+            cbor::extract_field(signed_metadata, "signatures").map_err(Error::Encoding)?;
+        let signed = cbor::extract_field(signed_metadata, "signed").map_err(Error::Encoding)?;
+        Ok((serde_cbor::from_slice(signatures)?, signed.to_vec()))
+    }
+
+    fn serialize<T>(data: &T) -> Result<Self::RawData>
+    where
+        T: Serialize,
+    {
+        Ok(serde_cbor::value::to_value(data)?)
+    }
+
+    fn to_writer<W, T: Sized>(writer: W, value: &T) -> Result<()>
+    where
+        W: Write,
+        T: Serialize,
+    {
+        cbor::to_writer(writer, value).map_err(Error::Encoding)
+    }
+
+    fn from_reader<R, T>(rdr: R) -> Result<T>
+    where
+        R: Read,
+        T: DeserializeOwned,
+    {
+        Ok(serde_cbor::from_reader(rdr)?)
+    }
+
+    fn from_slice<T>(slice: &[u8]) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        Ok(serde_cbor::from_slice(slice)?)
+    }
+
+    fn strict_datetimes() -> bool {
+        P::strict()
+    }
 }
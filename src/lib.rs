@@ -119,6 +119,8 @@ pub mod metadata;
 pub mod repository;
 pub mod tuf;
 
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+mod compression;
 mod shims;
 mod util;
 
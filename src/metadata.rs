@@ -1,17 +1,22 @@
 //! TUF metadata.
 
 use chrono::offset::Utc;
-use chrono::{DateTime, Duration};
+use chrono::{DateTime, Duration, Timelike};
 use log::{debug, warn};
-use serde::de::{Deserialize, DeserializeOwned, Deserializer, Error as DeserializeError};
-use serde::ser::{Error as SerializeError, Serialize, Serializer};
+use serde::de::{
+    Deserialize, DeserializeOwned, Deserializer, Error as DeserializeError, MapAccess, SeqAccess,
+    Visitor,
+};
+use serde::ser::{Error as SerializeError, Serialize, SerializeStruct, Serializer};
 use serde_derive::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::{self, Debug, Display};
 use std::io::Read;
 use std::marker::PhantomData;
+use url::Url;
 
-use crate::crypto::{self, HashAlgorithm, HashValue, KeyId, PrivateKey, PublicKey, Signature};
+use crate::crypto::{self, HashAlgorithm, HashValue, KeyId, PublicKey, Signature, Signer};
 use crate::error::Error;
 use crate::interchange::DataInterchange;
 use crate::shims;
@@ -145,6 +150,9 @@ fn safe_path(path: &str) -> Result<()> {
 }
 
 /// The TUF role.
+///
+/// `Mirrors` was added in 0.3.0, which is a breaking change for code that matches on every
+/// variant of `Role`.
 #[derive(Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Role {
     /// The root role.
@@ -159,6 +167,9 @@ pub enum Role {
     /// The timestamp role.
     #[serde(rename = "timestamp")]
     Timestamp,
+    /// The optional mirrors role.
+    #[serde(rename = "mirrors")]
+    Mirrors,
 }
 
 impl Role {
@@ -171,6 +182,7 @@ impl Role {
     /// assert!(Role::Snapshot.fuzzy_matches_path(&MetadataPath::from_role(&Role::Snapshot)));
     /// assert!(Role::Targets.fuzzy_matches_path(&MetadataPath::from_role(&Role::Targets)));
     /// assert!(Role::Timestamp.fuzzy_matches_path(&MetadataPath::from_role(&Role::Timestamp)));
+    /// assert!(Role::Mirrors.fuzzy_matches_path(&MetadataPath::from_role(&Role::Mirrors)));
     ///
     /// assert!(!Role::Root.fuzzy_matches_path(&MetadataPath::from_role(&Role::Snapshot)));
     /// assert!(!Role::Root.fuzzy_matches_path(&MetadataPath::new("wat".into()).unwrap()));
@@ -180,8 +192,13 @@ impl Role {
             Role::Root if &path.0 == "root" => true,
             Role::Snapshot if &path.0 == "snapshot" => true,
             Role::Timestamp if &path.0 == "timestamp" => true,
+            Role::Mirrors if &path.0 == "mirrors" => true,
             Role::Targets if &path.0 == "targets" => true,
-            Role::Targets if !&["root", "snapshot", "targets"].contains(&path.0.as_str()) => true,
+            Role::Targets
+                if !&["root", "snapshot", "targets", "mirrors"].contains(&path.0.as_str()) =>
+            {
+                true
+            }
             _ => false,
         }
     }
@@ -193,6 +210,7 @@ impl Role {
             Role::Snapshot => "snapshot",
             Role::Targets => "targets",
             Role::Timestamp => "timestamp",
+            Role::Mirrors => "mirrors",
         }
     }
 }
@@ -225,11 +243,56 @@ impl MetadataVersion {
     }
 }
 
+/// The version of the TUF specification implemented by this library. It is written into the
+/// `spec_version` field of all metadata created by this library.
+pub const SPEC_VERSION: &str = "1.0.0";
+
+/// The version of the TUF specification assumed for metadata without a `spec_version` field.
+/// Earlier releases of this library did not write the field.
+pub const LEGACY_SPEC_VERSION: &str = "1.0.0";
+
+/// Parse the major version out of a `spec_version` such as `"1.0.0"`.
+///
+/// ```
+/// # use tuf::metadata::spec_major_version;
+/// assert_eq!(spec_major_version("1.0.0").unwrap(), 1);
+/// assert_eq!(spec_major_version("2.1").unwrap(), 2);
+/// assert!(spec_major_version("1").is_err());
+/// assert!(spec_major_version("one.0.0").is_err());
+/// ```
+pub fn spec_major_version(spec_version: &str) -> Result<u32> {
+    let parts = spec_version
+        .split('.')
+        .map(|p| p.parse::<u32>())
+        .collect::<::std::result::Result<Vec<u32>, _>>()
+        .map_err(|_| Error::IllegalArgument(format!("Illegal spec version: {:?}", spec_version)))?;
+
+    if parts.len() < 2 || parts.len() > 3 {
+        return Err(Error::IllegalArgument(format!(
+            "Spec version must have a major and minor component. Found: {:?}",
+            spec_version
+        )));
+    }
+
+    Ok(parts[0])
+}
+
+/// The default expiration of new metadata, `days` from now. Sub-second precision is dropped so
+/// the timestamp is written without fractional seconds.
+fn expires_in(days: i64) -> DateTime<Utc> {
+    let expires = Utc::now() + Duration::days(days);
+    expires.with_nanosecond(0).unwrap_or(expires)
+}
+
 /// Top level trait used for role metadata.
 pub trait Metadata: Debug + PartialEq + Serialize + DeserializeOwned {
     /// The role associated with the metadata.
     const ROLE: Role;
 
+    /// The version of the TUF specification the metadata conforms to, or `None` if the metadata
+    /// predates the `spec_version` field. Such metadata is treated as `LEGACY_SPEC_VERSION`.
+    fn spec_version(&self) -> Option<&str>;
+
     /// The version number.
     fn version(&self) -> u32;
 
@@ -238,11 +301,15 @@ pub trait Metadata: Debug + PartialEq + Serialize + DeserializeOwned {
 }
 
 /// A piece of raw metadata with attached signatures.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct SignedMetadata<D, M> {
     signatures: Vec<Signature>,
     #[serde(rename = "signed")]
     metadata: M,
+    /// The exact bytes of the `signed` portion as they were received, if this was parsed with
+    /// `from_slice`.
+    #[serde(skip_deserializing)]
+    raw_signed: Option<Vec<u8>>,
     #[serde(skip_serializing, skip_deserializing)]
     _interchage: PhantomData<D>,
 }
@@ -252,11 +319,52 @@ where
     D: DataInterchange,
     M: Metadata,
 {
-    /// Create a new `SignedMetadata`. The supplied private key is used to sign the canonicalized
-    /// bytes of the provided metadata with the provided scheme.
+    /// Parse serialized signed metadata. Unlike `D::from_slice`, this keeps the exact bytes of the
+    /// `signed` portion so that signatures are verified against what was received, including any
+    /// fields `M` does not know about. Those bytes must be canonical.
+    ///
+    /// Unless `D::strict_datetimes()` is unset, the `expires` timestamp must be in the form this
+    /// library writes it.
+    pub fn from_slice(bytes: &[u8]) -> Result<Self> {
+        let (signatures, raw_signed) = D::extract_signed(bytes)?;
+        D::ensure_canonical(&raw_signed)?;
+
+        let metadata = if D::strict_datetimes() {
+            D::from_slice(&raw_signed)?
+        } else {
+            // Rewrite the timestamp into the form `M` accepts. Signatures are still checked
+            // against the bytes as they were received.
+            let mut fields: HashMap<String, D::RawData> = D::from_slice(&raw_signed)?;
+            if let Some(expires) = fields.get_mut("expires") {
+                let ts: String = D::deserialize(expires)?;
+                *expires = D::serialize(&shims::canonicalize_datetime(&ts)?)?;
+            }
+            D::deserialize(&D::serialize(&fields)?)?
+        };
+
+        Ok(SignedMetadata {
+            signatures,
+            metadata,
+            raw_signed: Some(raw_signed),
+            _interchage: PhantomData,
+        })
+    }
+
+    /// The bytes that signatures are calculated over. For received metadata these are the bytes
+    /// that were received, which were checked to be canonical when they were parsed.
+    fn signed_bytes(&self) -> Result<Cow<[u8]>> {
+        match self.raw_signed {
+            Some(ref raw) => Ok(Cow::Borrowed(raw)),
+            None => Ok(Cow::Owned(D::to_canonical_bytes(&self.metadata)?)),
+        }
+    }
+
+    /// Create a new `SignedMetadata`. The supplied signer is used to sign the canonicalized
+    /// bytes of the provided metadata with its signature scheme.
     ///
     /// ```
     /// # use chrono::prelude::*;
+    /// # use futures::executor::block_on;
     /// # use tuf::crypto::{PrivateKey, SignatureScheme, HashAlgorithm};
     /// # use tuf::interchange::Json;
     /// # use tuf::metadata::{SignedMetadata, SnapshotMetadataBuilder};
@@ -266,16 +374,22 @@ where
     /// let key = PrivateKey::from_pkcs8(&key, SignatureScheme::Ed25519).unwrap();
     ///
     /// let snapshot = SnapshotMetadataBuilder::new().build().unwrap();
-    /// SignedMetadata::<Json, _>::new(snapshot, &key).unwrap();
+    /// block_on(SignedMetadata::<Json, _>::new(snapshot, &key)).unwrap();
     /// # }
     /// ```
-    pub fn new(metadata: M, private_key: &PrivateKey) -> Result<SignedMetadata<D, M>> {
-        let raw = D::serialize(&metadata)?;
-        let bytes = D::canonicalize(&raw)?;
-        let sig = private_key.sign(&bytes)?;
+    pub async fn new<'a>(
+        metadata: M,
+        signer: &'a (dyn Signer + 'a),
+    ) -> Result<SignedMetadata<D, M>>
+    where
+        M: 'a,
+    {
+        let bytes = D::to_canonical_bytes(&metadata)?;
+        let sig = await!(signer.sign(&bytes))?;
         Ok(SignedMetadata {
             signatures: vec![sig],
             metadata,
+            raw_signed: None,
             _interchage: PhantomData,
         })
     }
@@ -290,6 +404,7 @@ where
     ///
     /// ```
     /// # use chrono::prelude::*;
+    /// # use futures::executor::block_on;
     /// # use tuf::crypto::{PrivateKey, SignatureScheme, HashAlgorithm};
     /// # use tuf::interchange::Json;
     /// # use tuf::metadata::{SignedMetadata, SnapshotMetadataBuilder};
@@ -304,21 +419,21 @@ where
     /// let key_2 = PrivateKey::from_pkcs8(&key_2, SignatureScheme::Ed25519).unwrap();
     ///
     /// let snapshot = SnapshotMetadataBuilder::new().build().unwrap();
-    /// let mut snapshot = SignedMetadata::<Json, _>::new(snapshot, &key_1).unwrap();
+    /// let mut snapshot = block_on(SignedMetadata::<Json, _>::new(snapshot, &key_1)).unwrap();
     ///
-    /// snapshot.add_signature(&key_2).unwrap();
+    /// block_on(snapshot.add_signature(&key_2)).unwrap();
     /// assert_eq!(snapshot.signatures().len(), 2);
     ///
-    /// snapshot.add_signature(&key_2).unwrap();
+    /// block_on(snapshot.add_signature(&key_2)).unwrap();
     /// assert_eq!(snapshot.signatures().len(), 2);
     /// # }
     /// ```
-    pub fn add_signature(&mut self, private_key: &PrivateKey) -> Result<()> {
-        let raw = D::serialize(&self.metadata)?;
-        let bytes = D::canonicalize(&raw)?;
-        let sig = private_key.sign(&bytes)?;
-        self.signatures
-            .retain(|s| s.key_id() != private_key.key_id());
+    pub async fn add_signature<'a>(&'a mut self, signer: &'a (dyn Signer + 'a)) -> Result<()> {
+        let sig = {
+            let bytes = self.signed_bytes()?;
+            await!(signer.sign(&bytes))?
+        };
+        self.signatures.retain(|s| s.key_id() != signer.key_id());
         self.signatures.push(sig);
         Ok(())
     }
@@ -364,6 +479,7 @@ where
     ///
     /// ```
     /// # use chrono::prelude::*;
+    /// # use futures::executor::block_on;
     /// # use tuf::crypto::{PrivateKey, SignatureScheme, HashAlgorithm};
     /// # use tuf::interchange::Json;
     /// # use tuf::metadata::{SnapshotMetadataBuilder, SignedMetadata};
@@ -376,7 +492,7 @@ where
     /// let key_2 = PrivateKey::from_pkcs8(&key_2, SignatureScheme::Ed25519).unwrap();
     ///
     /// let snapshot = SnapshotMetadataBuilder::new().build().unwrap();
-    /// let snapshot = SignedMetadata::<Json, _>::new(snapshot, &key_1).unwrap();
+    /// let snapshot = block_on(SignedMetadata::<Json, _>::new(snapshot, &key_1)).unwrap();
     ///
     /// assert!(snapshot.verify(
     ///     1,
@@ -422,7 +538,7 @@ where
             .map(|k| (k.key_id(), k))
             .collect::<HashMap<&KeyId, &PublicKey>>();
 
-        let canonical_bytes = D::canonicalize(&D::serialize(&self.metadata)?)?;
+        let canonical_bytes = self.signed_bytes()?;
 
         let mut signatures_needed = threshold;
         for sig in &self.signatures {
@@ -460,6 +576,40 @@ where
     }
 }
 
+impl<D, M> PartialEq for SignedMetadata<D, M>
+where
+    M: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        // `raw_signed` is only a record of how the metadata was received
+        self.signatures == other.signatures && self.metadata == other.metadata
+    }
+}
+
+impl<D, M> Serialize for SignedMetadata<D, M>
+where
+    D: DataInterchange,
+    M: Metadata,
+{
+    fn serialize<S>(&self, ser: S) -> ::std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut st = ser.serialize_struct("SignedMetadata", 2)?;
+        st.serialize_field("signatures", &self.signatures)?;
+        match self.raw_signed {
+            // write back what was received so that unknown fields survive a round trip
+            Some(ref raw) => {
+                let raw: D::RawData =
+                    D::from_slice(raw).map_err(|e| SerializeError::custom(format!("{:?}", e)))?;
+                st.serialize_field("signed", &raw)?;
+            }
+            None => st.serialize_field("signed", &self.metadata)?,
+        }
+        st.end()
+    }
+}
+
 impl<D, M> AsRef<M> for SignedMetadata<D, M> {
     fn as_ref(&self) -> &M {
         &self.metadata
@@ -468,11 +618,15 @@ impl<D, M> AsRef<M> for SignedMetadata<D, M> {
 
 impl<D, M> Metadata for SignedMetadata<D, M>
 where
-    D: Debug + PartialEq,
+    D: DataInterchange,
     M: Metadata,
 {
     const ROLE: Role = M::ROLE;
 
+    fn spec_version(&self) -> Option<&str> {
+        self.metadata.spec_version()
+    }
+
     fn version(&self) -> u32 {
         self.metadata.version()
     }
@@ -482,8 +636,150 @@ where
     }
 }
 
+/// The value of a field that this library does not recognize.
+///
+/// Metadata may carry fields added by later revisions of the specification or by extensions
+/// such as TAPs. They are kept as `ExtensionValue`s so that they survive a round trip through
+/// parsing, editing, and re-serialization. Floating point numbers are not representable in
+/// canonical metadata and are rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExtensionValue {
+    /// An explicit null.
+    Null,
+    /// A boolean.
+    Bool(bool),
+    /// A non-negative integer.
+    Unsigned(u64),
+    /// A negative integer.
+    Signed(i64),
+    /// A string.
+    String(String),
+    /// An ordered list of values.
+    Array(Vec<ExtensionValue>),
+    /// A map of string keys to values.
+    Object(BTreeMap<String, ExtensionValue>),
+}
+
+impl Serialize for ExtensionValue {
+    fn serialize<S>(&self, ser: S) -> ::std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match *self {
+            ExtensionValue::Null => ser.serialize_unit(),
+            ExtensionValue::Bool(b) => ser.serialize_bool(b),
+            ExtensionValue::Unsigned(n) => ser.serialize_u64(n),
+            ExtensionValue::Signed(n) => ser.serialize_i64(n),
+            ExtensionValue::String(ref s) => ser.serialize_str(s),
+            ExtensionValue::Array(ref values) => values.serialize(ser),
+            ExtensionValue::Object(ref map) => map.serialize(ser),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ExtensionValue {
+    fn deserialize<D: Deserializer<'de>>(de: D) -> ::std::result::Result<Self, D::Error> {
+        de.deserialize_any(ExtensionValueVisitor)
+    }
+}
+
+struct ExtensionValueVisitor;
+
+impl<'de> Visitor<'de> for ExtensionValueVisitor {
+    type Value = ExtensionValue;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a null, boolean, integer, string, array, or map")
+    }
+
+    fn visit_unit<E>(self) -> ::std::result::Result<Self::Value, E>
+    where
+        E: DeserializeError,
+    {
+        Ok(ExtensionValue::Null)
+    }
+
+    fn visit_none<E>(self) -> ::std::result::Result<Self::Value, E>
+    where
+        E: DeserializeError,
+    {
+        Ok(ExtensionValue::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(
+        self,
+        de: D,
+    ) -> ::std::result::Result<Self::Value, D::Error> {
+        Deserialize::deserialize(de)
+    }
+
+    fn visit_bool<E>(self, b: bool) -> ::std::result::Result<Self::Value, E>
+    where
+        E: DeserializeError,
+    {
+        Ok(ExtensionValue::Bool(b))
+    }
+
+    fn visit_u64<E>(self, n: u64) -> ::std::result::Result<Self::Value, E>
+    where
+        E: DeserializeError,
+    {
+        Ok(ExtensionValue::Unsigned(n))
+    }
+
+    fn visit_i64<E>(self, n: i64) -> ::std::result::Result<Self::Value, E>
+    where
+        E: DeserializeError,
+    {
+        if n >= 0 {
+            Ok(ExtensionValue::Unsigned(n as u64))
+        } else {
+            Ok(ExtensionValue::Signed(n))
+        }
+    }
+
+    fn visit_str<E>(self, s: &str) -> ::std::result::Result<Self::Value, E>
+    where
+        E: DeserializeError,
+    {
+        Ok(ExtensionValue::String(s.into()))
+    }
+
+    fn visit_string<E>(self, s: String) -> ::std::result::Result<Self::Value, E>
+    where
+        E: DeserializeError,
+    {
+        Ok(ExtensionValue::String(s))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(
+        self,
+        mut seq: A,
+    ) -> ::std::result::Result<Self::Value, A::Error> {
+        let mut values = Vec::new();
+        while let Some(value) = seq.next_element()? {
+            values.push(value);
+        }
+        Ok(ExtensionValue::Array(values))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(
+        self,
+        mut map: A,
+    ) -> ::std::result::Result<Self::Value, A::Error> {
+        let mut values = BTreeMap::new();
+        while let Some((key, value)) = map.next_entry::<String, ExtensionValue>()? {
+            if values.insert(key.clone(), value).is_some() {
+                return Err(DeserializeError::custom(format!("Duplicate key: {}", key)));
+            }
+        }
+        Ok(ExtensionValue::Object(values))
+    }
+}
+
 /// Helper to construct `RootMetadata`.
 pub struct RootMetadataBuilder {
+    spec_version: Option<String>,
     version: u32,
     expires: DateTime<Utc>,
     consistent_snapshot: bool,
@@ -496,6 +792,10 @@ pub struct RootMetadataBuilder {
     targets_key_ids: HashSet<KeyId>,
     timestamp_threshold: u32,
     timestamp_key_ids: HashSet<KeyId>,
+    mirrors_threshold: u32,
+    mirrors_key_ids: HashSet<KeyId>,
+    role_extensions: HashMap<Role, HashMap<String, ExtensionValue>>,
+    extensions: HashMap<String, ExtensionValue>,
 }
 
 impl RootMetadataBuilder {
@@ -507,8 +807,9 @@ impl RootMetadataBuilder {
     /// * role thresholds: 1
     pub fn new() -> Self {
         RootMetadataBuilder {
+            spec_version: Some(SPEC_VERSION.into()),
             version: 1,
-            expires: Utc::now() + Duration::days(365),
+            expires: expires_in(365),
             consistent_snapshot: false,
             keys: HashMap::new(),
             root_threshold: 1,
@@ -519,9 +820,20 @@ impl RootMetadataBuilder {
             targets_key_ids: HashSet::new(),
             timestamp_threshold: 1,
             timestamp_key_ids: HashSet::new(),
+            mirrors_threshold: 1,
+            mirrors_key_ids: HashSet::new(),
+            role_extensions: HashMap::new(),
+            extensions: HashMap::new(),
         }
     }
 
+    /// Set the `spec_version` written into the root metadata. It defaults to `SPEC_VERSION`, and
+    /// `build` fails if it is not of the form `MAJOR.MINOR` or `MAJOR.MINOR.PATCH`.
+    pub fn spec_version(mut self, spec_version: String) -> Self {
+        self.spec_version = Some(spec_version);
+        self
+    }
+
     /// Set the version number for this metadata.
     pub fn version(mut self, version: u32) -> Self {
         self.version = version;
@@ -596,26 +908,79 @@ impl RootMetadataBuilder {
         self
     }
 
+    /// Set the mirrors threshold.
+    pub fn mirrors_threshold(mut self, threshold: u32) -> Self {
+        self.mirrors_threshold = threshold;
+        self
+    }
+
+    /// Add a mirrors public key. The mirrors role is only defined if it has at least one key.
+    pub fn mirrors_key(mut self, public_key: PublicKey) -> Self {
+        let key_id = public_key.key_id().clone();
+        let _ = self.keys.insert(key_id.clone(), public_key);
+        let _ = self.mirrors_key_ids.insert(key_id);
+        self
+    }
+
+    /// Add a root metadata field this library has no model for, such as one introduced by a TAP.
+    pub fn extension(mut self, key: String, value: ExtensionValue) -> Self {
+        let _ = self.extensions.insert(key, value);
+        self
+    }
+
     /// Construct a new `RootMetadata`.
     pub fn build(self) -> Result<RootMetadata> {
+        let extensions = self.extensions;
+        // carry over the unmodeled fields of roles taken from an existing `RootMetadata`
+        let mut role_extensions = self.role_extensions;
+        let mut role_definition = |role: Role, threshold: u32, key_ids: HashSet<KeyId>| {
+            RoleDefinition::new(threshold, key_ids).map(|definition| {
+                definition.with_extensions(role_extensions.remove(&role).unwrap_or_default())
+            })
+        };
+
+        let mirrors = if self.mirrors_key_ids.is_empty() {
+            None
+        } else {
+            Some(role_definition(
+                Role::Mirrors,
+                self.mirrors_threshold,
+                self.mirrors_key_ids,
+            )?)
+        };
+
         RootMetadata::new(
             self.version,
             self.expires,
             self.consistent_snapshot,
             self.keys,
-            RoleDefinition::new(self.root_threshold, self.root_key_ids)?,
-            RoleDefinition::new(self.snapshot_threshold, self.snapshot_key_ids)?,
-            RoleDefinition::new(self.targets_threshold, self.targets_key_ids)?,
-            RoleDefinition::new(self.timestamp_threshold, self.timestamp_key_ids)?,
-        )
+            role_definition(Role::Root, self.root_threshold, self.root_key_ids)?,
+            role_definition(
+                Role::Snapshot,
+                self.snapshot_threshold,
+                self.snapshot_key_ids,
+            )?,
+            role_definition(Role::Targets, self.targets_threshold, self.targets_key_ids)?,
+            role_definition(
+                Role::Timestamp,
+                self.timestamp_threshold,
+                self.timestamp_key_ids,
+            )?,
+        )?
+        .with_mirrors(mirrors)
+        .with_spec_version(self.spec_version)
+        .map(|root| root.with_extensions(extensions))
     }
 
     /// Construct a new `SignedMetadata<D, RootMetadata>`.
-    pub fn signed<D>(self, private_key: &PrivateKey) -> Result<SignedMetadata<D, RootMetadata>>
+    pub async fn signed<'a, D>(
+        self,
+        signer: &'a (dyn Signer + 'a),
+    ) -> Result<SignedMetadata<D, RootMetadata>>
     where
         D: DataInterchange,
     {
-        Ok(SignedMetadata::new(self.build()?, private_key)?)
+        Ok(await!(SignedMetadata::new(self.build()?, signer))?)
     }
 }
 
@@ -627,7 +992,17 @@ impl Default for RootMetadataBuilder {
 
 impl From<RootMetadata> for RootMetadataBuilder {
     fn from(metadata: RootMetadata) -> Self {
+        let mut role_extensions = HashMap::new();
+        let _ = role_extensions.insert(Role::Root, metadata.root.extensions);
+        let _ = role_extensions.insert(Role::Snapshot, metadata.snapshot.extensions);
+        let _ = role_extensions.insert(Role::Targets, metadata.targets.extensions);
+        let _ = role_extensions.insert(Role::Timestamp, metadata.timestamp.extensions);
+        if let Some(ref mirrors) = metadata.mirrors {
+            let _ = role_extensions.insert(Role::Mirrors, mirrors.extensions.clone());
+        }
+
         RootMetadataBuilder {
+            spec_version: metadata.spec_version,
             version: metadata.version,
             expires: metadata.expires,
             consistent_snapshot: metadata.consistent_snapshot,
@@ -640,6 +1015,10 @@ impl From<RootMetadata> for RootMetadataBuilder {
             targets_key_ids: metadata.targets.key_ids,
             timestamp_threshold: metadata.timestamp.threshold,
             timestamp_key_ids: metadata.timestamp.key_ids,
+            mirrors_threshold: metadata.mirrors.as_ref().map(|m| m.threshold).unwrap_or(1),
+            mirrors_key_ids: metadata.mirrors.map(|m| m.key_ids).unwrap_or_default(),
+            role_extensions,
+            extensions: metadata.extensions,
         }
     }
 }
@@ -647,6 +1026,7 @@ impl From<RootMetadata> for RootMetadataBuilder {
 /// Metadata for the root role.
 #[derive(Debug, Clone, PartialEq)]
 pub struct RootMetadata {
+    spec_version: Option<String>,
     version: u32,
     expires: DateTime<Utc>,
     consistent_snapshot: bool,
@@ -655,6 +1035,8 @@ pub struct RootMetadata {
     snapshot: RoleDefinition,
     targets: RoleDefinition,
     timestamp: RoleDefinition,
+    mirrors: Option<RoleDefinition>,
+    extensions: HashMap<String, ExtensionValue>,
 }
 
 impl RootMetadata {
@@ -676,7 +1058,12 @@ impl RootMetadata {
             )));
         }
 
+        for key in keys.values() {
+            key.check_supported()?;
+        }
+
         Ok(RootMetadata {
+            spec_version: Some(SPEC_VERSION.into()),
             version,
             expires,
             consistent_snapshot,
@@ -685,6 +1072,8 @@ impl RootMetadata {
             snapshot,
             targets,
             timestamp,
+            mirrors: None,
+            extensions: HashMap::new(),
         })
     }
 
@@ -718,11 +1107,51 @@ impl RootMetadata {
     pub fn timestamp(&self) -> &RoleDefinition {
         &self.timestamp
     }
+
+    /// An immutable reference to the optional mirrors role's definition.
+    pub fn mirrors(&self) -> Option<&RoleDefinition> {
+        self.mirrors.as_ref()
+    }
+
+    /// Set the optional mirrors role's definition.
+    pub fn with_mirrors(mut self, mirrors: Option<RoleDefinition>) -> Self {
+        self.mirrors = mirrors;
+        self
+    }
+
+    /// Set the TUF specification version the root metadata claims to conform to. `None` leaves
+    /// the field out, as in metadata written before it existed.
+    pub fn with_spec_version(mut self, spec_version: Option<String>) -> Result<Self> {
+        if let Some(ref spec_version) = spec_version {
+            let _ = spec_major_version(spec_version)?;
+        }
+        self.spec_version = spec_version;
+        Ok(self)
+    }
+
+    /// Fields of the root metadata that this library has no model for.
+    ///
+    /// These are written back unchanged when the metadata is serialized, so a root that is edited
+    /// and re-signed by this library does not lose fields that other clients rely on.
+    pub fn extensions(&self) -> &HashMap<String, ExtensionValue> {
+        &self.extensions
+    }
+
+    /// Replace the root metadata's unmodeled fields. Serialization fails if one of them shares a
+    /// name with a field defined by the specification.
+    pub fn with_extensions(mut self, extensions: HashMap<String, ExtensionValue>) -> Self {
+        self.extensions = extensions;
+        self
+    }
 }
 
 impl Metadata for RootMetadata {
     const ROLE: Role = Role::Root;
 
+    fn spec_version(&self) -> Option<&str> {
+        self.spec_version.as_ref().map(|v| v.as_str())
+    }
+
     fn version(&self) -> u32 {
         self.version
     }
@@ -757,6 +1186,7 @@ impl<'de> Deserialize<'de> for RootMetadata {
 pub struct RoleDefinition {
     threshold: u32,
     key_ids: HashSet<KeyId>,
+    extensions: HashMap<String, ExtensionValue>,
 }
 
 impl RoleDefinition {
@@ -780,7 +1210,11 @@ impl RoleDefinition {
             )));
         }
 
-        Ok(RoleDefinition { threshold, key_ids })
+        Ok(RoleDefinition {
+            threshold,
+            key_ids,
+            extensions: HashMap::new(),
+        })
     }
 
     /// The threshold number of signatures required for the role to be trusted.
@@ -792,6 +1226,17 @@ impl RoleDefinition {
     pub fn key_ids(&self) -> &HashSet<KeyId> {
         &self.key_ids
     }
+
+    /// Fields of the role definition beyond `threshold` and `key_ids`.
+    pub fn extensions(&self) -> &HashMap<String, ExtensionValue> {
+        &self.extensions
+    }
+
+    /// Replace the role definition's unmodeled fields.
+    pub fn with_extensions(mut self, extensions: HashMap<String, ExtensionValue>) -> Self {
+        self.extensions = extensions;
+        self
+    }
 }
 
 impl Serialize for RoleDefinition {
@@ -910,9 +1355,11 @@ impl<'de> Deserialize<'de> for MetadataPath {
 
 /// Helper to construct `TimestampMetadata`.
 pub struct TimestampMetadataBuilder {
+    spec_version: Option<String>,
     version: u32,
     expires: DateTime<Utc>,
     snapshot: MetadataDescription,
+    extensions: HashMap<String, ExtensionValue>,
 }
 
 impl TimestampMetadataBuilder {
@@ -928,7 +1375,7 @@ impl TimestampMetadataBuilder {
         D: DataInterchange,
         M: Metadata,
     {
-        let bytes = D::canonicalize(&D::serialize(&snapshot)?)?;
+        let bytes = D::to_canonical_bytes(&snapshot)?;
         let description = MetadataDescription::from_reader(&*bytes, snapshot.version(), hash_algs)?;
 
         Ok(Self::from_metadata_description(description))
@@ -941,12 +1388,20 @@ impl TimestampMetadataBuilder {
     /// * expires: 1 day from the current time.
     pub fn from_metadata_description(description: MetadataDescription) -> Self {
         TimestampMetadataBuilder {
+            spec_version: Some(SPEC_VERSION.into()),
             version: 1,
-            expires: Utc::now() + Duration::days(1),
+            expires: expires_in(1),
             snapshot: description,
+            extensions: HashMap::new(),
         }
     }
 
+    /// Override the `spec_version` of the timestamp metadata. `build` rejects malformed versions.
+    pub fn spec_version(mut self, spec_version: String) -> Self {
+        self.spec_version = Some(spec_version);
+        self
+    }
+
     /// Set the version number for this metadata.
     pub fn version(mut self, version: u32) -> Self {
         self.version = version;
@@ -959,26 +1414,40 @@ impl TimestampMetadataBuilder {
         self
     }
 
+    /// Add a timestamp metadata field this library has no model for.
+    pub fn extension(mut self, key: String, value: ExtensionValue) -> Self {
+        let _ = self.extensions.insert(key, value);
+        self
+    }
+
     /// Construct a new `TimestampMetadata`.
     pub fn build(self) -> Result<TimestampMetadata> {
-        TimestampMetadata::new(self.version, self.expires, self.snapshot)
+        let extensions = self.extensions;
+        TimestampMetadata::new(self.version, self.expires, self.snapshot)?
+            .with_spec_version(self.spec_version)
+            .map(|timestamp| timestamp.with_extensions(extensions))
     }
 
     /// Construct a new `SignedMetadata<D, TimestampMetadata>`.
-    pub fn signed<D>(self, private_key: &PrivateKey) -> Result<SignedMetadata<D, TimestampMetadata>>
+    pub async fn signed<'a, D>(
+        self,
+        signer: &'a (dyn Signer + 'a),
+    ) -> Result<SignedMetadata<D, TimestampMetadata>>
     where
         D: DataInterchange,
     {
-        Ok(SignedMetadata::new(self.build()?, private_key)?)
+        Ok(await!(SignedMetadata::new(self.build()?, signer))?)
     }
 }
 
 /// Metadata for the timestamp role.
 #[derive(Debug, Clone, PartialEq)]
 pub struct TimestampMetadata {
+    spec_version: Option<String>,
     version: u32,
     expires: DateTime<Utc>,
     snapshot: MetadataDescription,
+    extensions: HashMap<String, ExtensionValue>,
 }
 
 impl TimestampMetadata {
@@ -996,9 +1465,11 @@ impl TimestampMetadata {
         }
 
         Ok(TimestampMetadata {
+            spec_version: Some(SPEC_VERSION.into()),
             version,
             expires,
             snapshot,
+            extensions: HashMap::new(),
         })
     }
 
@@ -1006,11 +1477,35 @@ impl TimestampMetadata {
     pub fn snapshot(&self) -> &MetadataDescription {
         &self.snapshot
     }
+
+    /// Set or clear the `spec_version` of the timestamp metadata.
+    pub fn with_spec_version(mut self, spec_version: Option<String>) -> Result<Self> {
+        if let Some(ref spec_version) = spec_version {
+            let _ = spec_major_version(spec_version)?;
+        }
+        self.spec_version = spec_version;
+        Ok(self)
+    }
+
+    /// Fields of the timestamp metadata that this library has no model for.
+    pub fn extensions(&self) -> &HashMap<String, ExtensionValue> {
+        &self.extensions
+    }
+
+    /// Replace the timestamp metadata's unmodeled fields.
+    pub fn with_extensions(mut self, extensions: HashMap<String, ExtensionValue>) -> Self {
+        self.extensions = extensions;
+        self
+    }
 }
 
 impl Metadata for TimestampMetadata {
     const ROLE: Role = Role::Timestamp;
 
+    fn spec_version(&self) -> Option<&str> {
+        self.spec_version.as_ref().map(|v| v.as_str())
+    }
+
     fn version(&self) -> u32 {
         self.version
     }
@@ -1129,9 +1624,11 @@ impl<'de> Deserialize<'de> for MetadataDescription {
 
 /// Helper to construct `SnapshotMetadata`.
 pub struct SnapshotMetadataBuilder {
+    spec_version: Option<String>,
     version: u32,
     expires: DateTime<Utc>,
     meta: HashMap<MetadataPath, MetadataDescription>,
+    extensions: HashMap<String, ExtensionValue>,
 }
 
 impl SnapshotMetadataBuilder {
@@ -1141,12 +1638,20 @@ impl SnapshotMetadataBuilder {
     /// * expires: 7 days from the current time.
     pub fn new() -> Self {
         SnapshotMetadataBuilder {
+            spec_version: Some(SPEC_VERSION.into()),
             version: 1,
-            expires: Utc::now() + Duration::days(7),
+            expires: expires_in(7),
             meta: HashMap::new(),
+            extensions: HashMap::new(),
         }
     }
 
+    /// Override the `spec_version` of the snapshot metadata. `build` rejects malformed versions.
+    pub fn spec_version(mut self, spec_version: String) -> Self {
+        self.spec_version = Some(spec_version);
+        self
+    }
+
     /// Set the version number for this metadata.
     pub fn version(mut self, version: u32) -> Self {
         self.version = version;
@@ -1184,7 +1689,7 @@ impl SnapshotMetadataBuilder {
         M: Metadata,
         D: DataInterchange,
     {
-        let bytes = D::canonicalize(&D::serialize(metadata)?)?;
+        let bytes = D::to_canonical_bytes(metadata)?;
         let description = MetadataDescription::from_reader(&*bytes, metadata.version(), hash_algs)?;
         let path = MetadataPath::new(path.into())?;
         Ok(self.insert_metadata_description(path, description))
@@ -1200,17 +1705,29 @@ impl SnapshotMetadataBuilder {
         self
     }
 
+    /// Add a snapshot metadata field this library has no model for.
+    pub fn extension(mut self, key: String, value: ExtensionValue) -> Self {
+        let _ = self.extensions.insert(key, value);
+        self
+    }
+
     /// Construct a new `SnapshotMetadata`.
     pub fn build(self) -> Result<SnapshotMetadata> {
-        SnapshotMetadata::new(self.version, self.expires, self.meta)
+        let extensions = self.extensions;
+        SnapshotMetadata::new(self.version, self.expires, self.meta)?
+            .with_spec_version(self.spec_version)
+            .map(|snapshot| snapshot.with_extensions(extensions))
     }
 
     /// Construct a new `SignedMetadata<D, SnapshotMetadata>`.
-    pub fn signed<D>(self, private_key: &PrivateKey) -> Result<SignedMetadata<D, SnapshotMetadata>>
+    pub async fn signed<'a, D>(
+        self,
+        signer: &'a (dyn Signer + 'a),
+    ) -> Result<SignedMetadata<D, SnapshotMetadata>>
     where
         D: DataInterchange,
     {
-        Ok(SignedMetadata::new(self.build()?, private_key)?)
+        Ok(await!(SignedMetadata::new(self.build()?, signer))?)
     }
 }
 
@@ -1223,9 +1740,11 @@ impl Default for SnapshotMetadataBuilder {
 impl From<SnapshotMetadata> for SnapshotMetadataBuilder {
     fn from(meta: SnapshotMetadata) -> Self {
         SnapshotMetadataBuilder {
+            spec_version: meta.spec_version,
             version: meta.version,
             expires: meta.expires,
             meta: meta.meta,
+            extensions: meta.extensions,
         }
     }
 }
@@ -1233,9 +1752,11 @@ impl From<SnapshotMetadata> for SnapshotMetadataBuilder {
 /// Metadata for the snapshot role.
 #[derive(Debug, Clone, PartialEq)]
 pub struct SnapshotMetadata {
+    spec_version: Option<String>,
     version: u32,
     expires: DateTime<Utc>,
     meta: HashMap<MetadataPath, MetadataDescription>,
+    extensions: HashMap<String, ExtensionValue>,
 }
 
 impl SnapshotMetadata {
@@ -1253,9 +1774,11 @@ impl SnapshotMetadata {
         }
 
         Ok(SnapshotMetadata {
+            spec_version: Some(SPEC_VERSION.into()),
             version,
             expires,
             meta,
+            extensions: HashMap::new(),
         })
     }
 
@@ -1263,11 +1786,35 @@ impl SnapshotMetadata {
     pub fn meta(&self) -> &HashMap<MetadataPath, MetadataDescription> {
         &self.meta
     }
+
+    /// Set or clear the `spec_version` of the snapshot metadata.
+    pub fn with_spec_version(mut self, spec_version: Option<String>) -> Result<Self> {
+        if let Some(ref spec_version) = spec_version {
+            let _ = spec_major_version(spec_version)?;
+        }
+        self.spec_version = spec_version;
+        Ok(self)
+    }
+
+    /// Fields of the snapshot metadata that this library has no model for.
+    pub fn extensions(&self) -> &HashMap<String, ExtensionValue> {
+        &self.extensions
+    }
+
+    /// Replace the snapshot metadata's unmodeled fields.
+    pub fn with_extensions(mut self, extensions: HashMap<String, ExtensionValue>) -> Self {
+        self.extensions = extensions;
+        self
+    }
 }
 
 impl Metadata for SnapshotMetadata {
     const ROLE: Role = Role::Snapshot;
 
+    fn spec_version(&self) -> Option<&str> {
+        self.spec_version.as_ref().map(|v| v.as_str())
+    }
+
     fn version(&self) -> u32 {
         self.version
     }
@@ -1437,10 +1984,11 @@ impl TargetPath {
 }
 
 /// Description of a target, used in verification.
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct TargetDescription {
     size: u64,
     hashes: HashMap<HashAlgorithm, HashValue>,
+    extensions: HashMap<String, ExtensionValue>,
 }
 
 impl TargetDescription {
@@ -1455,7 +2003,11 @@ impl TargetDescription {
             ));
         }
 
-        Ok(TargetDescription { size, hashes })
+        Ok(TargetDescription {
+            size,
+            hashes,
+            extensions: HashMap::new(),
+        })
     }
 
     /// Read the from the given reader and calculate the size and hash values.
@@ -1491,7 +2043,11 @@ impl TargetDescription {
         R: Read,
     {
         let (size, hashes) = crypto::calculate_hashes(read, hash_algs)?;
-        Ok(TargetDescription { size, hashes })
+        Ok(TargetDescription {
+            size,
+            hashes,
+            extensions: HashMap::new(),
+        })
     }
 
     /// The maximum size of the target.
@@ -1503,6 +2059,28 @@ impl TargetDescription {
     pub fn hashes(&self) -> &HashMap<HashAlgorithm, HashValue> {
         &self.hashes
     }
+
+    /// Fields of the target description beyond `size` and `hashes`, such as `custom`.
+    pub fn extensions(&self) -> &HashMap<String, ExtensionValue> {
+        &self.extensions
+    }
+
+    /// Replace the target description's unmodeled fields.
+    pub fn with_extensions(mut self, extensions: HashMap<String, ExtensionValue>) -> Self {
+        self.extensions = extensions;
+        self
+    }
+}
+
+impl Serialize for TargetDescription {
+    fn serialize<S>(&self, ser: S) -> ::std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        shims::TargetDescription::from(self)
+            .map_err(|e| SerializeError::custom(format!("{:?}", e)))?
+            .serialize(ser)
+    }
 }
 
 impl<'de> Deserialize<'de> for TargetDescription {
@@ -1517,10 +2095,12 @@ impl<'de> Deserialize<'de> for TargetDescription {
 /// Metadata for the targets role.
 #[derive(Debug, Clone, PartialEq)]
 pub struct TargetsMetadata {
+    spec_version: Option<String>,
     version: u32,
     expires: DateTime<Utc>,
     targets: HashMap<VirtualTargetPath, TargetDescription>,
     delegations: Option<Delegations>,
+    extensions: HashMap<String, ExtensionValue>,
 }
 
 impl TargetsMetadata {
@@ -1539,10 +2119,12 @@ impl TargetsMetadata {
         }
 
         Ok(TargetsMetadata {
+            spec_version: Some(SPEC_VERSION.into()),
             version,
             expires,
             targets,
             delegations,
+            extensions: HashMap::new(),
         })
     }
 
@@ -1555,11 +2137,36 @@ impl TargetsMetadata {
     pub fn delegations(&self) -> Option<&Delegations> {
         self.delegations.as_ref()
     }
+
+    /// Set or clear the `spec_version` of the targets metadata.
+    pub fn with_spec_version(mut self, spec_version: Option<String>) -> Result<Self> {
+        if let Some(ref spec_version) = spec_version {
+            let _ = spec_major_version(spec_version)?;
+        }
+        self.spec_version = spec_version;
+        Ok(self)
+    }
+
+    /// Fields of the targets metadata, such as those added by TAPs, that this library has no
+    /// model for.
+    pub fn extensions(&self) -> &HashMap<String, ExtensionValue> {
+        &self.extensions
+    }
+
+    /// Replace the targets metadata's unmodeled fields.
+    pub fn with_extensions(mut self, extensions: HashMap<String, ExtensionValue>) -> Self {
+        self.extensions = extensions;
+        self
+    }
 }
 
 impl Metadata for TargetsMetadata {
     const ROLE: Role = Role::Targets;
 
+    fn spec_version(&self) -> Option<&str> {
+        self.spec_version.as_ref().map(|v| v.as_str())
+    }
+
     fn version(&self) -> u32 {
         self.version
     }
@@ -1591,10 +2198,12 @@ impl<'de> Deserialize<'de> for TargetsMetadata {
 
 /// Helper to construct `TargetsMetadata`.
 pub struct TargetsMetadataBuilder {
+    spec_version: Option<String>,
     version: u32,
     expires: DateTime<Utc>,
     targets: HashMap<VirtualTargetPath, TargetDescription>,
     delegations: Option<Delegations>,
+    extensions: HashMap<String, ExtensionValue>,
 }
 
 impl TargetsMetadataBuilder {
@@ -1604,13 +2213,21 @@ impl TargetsMetadataBuilder {
     /// * expires: 90 days from the current time.
     pub fn new() -> Self {
         TargetsMetadataBuilder {
+            spec_version: Some(SPEC_VERSION.into()),
             version: 1,
-            expires: Utc::now() + Duration::days(90),
+            expires: expires_in(90),
             targets: HashMap::new(),
             delegations: None,
+            extensions: HashMap::new(),
         }
     }
 
+    /// Override the `spec_version` of the targets metadata. `build` rejects malformed versions.
+    pub fn spec_version(mut self, spec_version: String) -> Self {
+        self.spec_version = Some(spec_version);
+        self
+    }
+
     /// Set the version number for this metadata.
     pub fn version(mut self, version: u32) -> Self {
         self.version = version;
@@ -1653,17 +2270,29 @@ impl TargetsMetadataBuilder {
         self
     }
 
+    /// Add a targets metadata field this library has no model for.
+    pub fn extension(mut self, key: String, value: ExtensionValue) -> Self {
+        let _ = self.extensions.insert(key, value);
+        self
+    }
+
     /// Construct a new `TargetsMetadata`.
     pub fn build(self) -> Result<TargetsMetadata> {
-        TargetsMetadata::new(self.version, self.expires, self.targets, self.delegations)
+        let extensions = self.extensions;
+        TargetsMetadata::new(self.version, self.expires, self.targets, self.delegations)?
+            .with_spec_version(self.spec_version)
+            .map(|targets| targets.with_extensions(extensions))
     }
 
     /// Construct a new `SignedMetadata<D, TargetsMetadata>`.
-    pub fn signed<D>(self, private_key: &PrivateKey) -> Result<SignedMetadata<D, TargetsMetadata>>
+    pub async fn signed<'a, D>(
+        self,
+        signer: &'a (dyn Signer + 'a),
+    ) -> Result<SignedMetadata<D, TargetsMetadata>>
     where
         D: DataInterchange,
     {
-        Ok(SignedMetadata::new(self.build()?, private_key)?)
+        Ok(await!(SignedMetadata::new(self.build()?, signer))?)
     }
 }
 
@@ -1673,6 +2302,19 @@ impl Default for TargetsMetadataBuilder {
     }
 }
 
+impl From<TargetsMetadata> for TargetsMetadataBuilder {
+    fn from(meta: TargetsMetadata) -> Self {
+        TargetsMetadataBuilder {
+            spec_version: meta.spec_version,
+            version: meta.version,
+            expires: meta.expires,
+            targets: meta.targets,
+            delegations: meta.delegations,
+            extensions: meta.extensions,
+        }
+    }
+}
+
 /// Wrapper to described a collections of delegations.
 #[derive(Debug, PartialEq, Clone)]
 pub struct Delegations {
@@ -1693,6 +2335,10 @@ impl Delegations {
             return Err(Error::IllegalArgument("Roles cannot be empty.".into()));
         }
 
+        for key in keys {
+            key.check_supported()?;
+        }
+
         if roles.len()
             != roles
                 .iter()
@@ -1752,6 +2398,7 @@ pub struct Delegation {
     threshold: u32,
     key_ids: HashSet<KeyId>,
     paths: HashSet<VirtualTargetPath>,
+    extensions: HashMap<String, ExtensionValue>,
 }
 
 impl Delegation {
@@ -1787,6 +2434,7 @@ impl Delegation {
             threshold,
             key_ids,
             paths,
+            extensions: HashMap::new(),
         })
     }
 
@@ -1814,6 +2462,18 @@ impl Delegation {
     pub fn paths(&self) -> &HashSet<VirtualTargetPath> {
         &self.paths
     }
+
+    /// Fields of the delegation that this library has no model for, such as the
+    /// `path_hash_prefixes` of a hashed bin delegation.
+    pub fn extensions(&self) -> &HashMap<String, ExtensionValue> {
+        &self.extensions
+    }
+
+    /// Replace the delegation's unmodeled fields.
+    pub fn with_extensions(mut self, extensions: HashMap<String, ExtensionValue>) -> Self {
+        self.extensions = extensions;
+        self
+    }
 }
 
 impl Serialize for Delegation {
@@ -1821,7 +2481,9 @@ impl Serialize for Delegation {
     where
         S: Serializer,
     {
-        shims::Delegation::from(self).serialize(ser)
+        shims::Delegation::from(self)
+            .map_err(|e| SerializeError::custom(format!("{:?}", e)))?
+            .serialize(ser)
     }
 }
 
@@ -1834,14 +2496,388 @@ impl<'de> Deserialize<'de> for Delegation {
     }
 }
 
-#[cfg(test)]
-mod test {
-    use super::*;
-    use crate::crypto::SignatureScheme;
-    use crate::interchange::Json;
-    use chrono::prelude::*;
-    use maplit::{hashmap, hashset};
-    use serde_json::json;
+/// Helper to construct `MirrorsMetadata`.
+pub struct MirrorsMetadataBuilder {
+    spec_version: Option<String>,
+    version: u32,
+    expires: DateTime<Utc>,
+    mirrors: Vec<Mirror>,
+    extensions: HashMap<String, ExtensionValue>,
+}
+
+impl MirrorsMetadataBuilder {
+    /// Create a new `MirrorsMetadataBuilder`. It defaults to:
+    ///
+    /// * version: 1
+    /// * expires: 365 days from the current time.
+    pub fn new() -> Self {
+        MirrorsMetadataBuilder {
+            spec_version: Some(SPEC_VERSION.into()),
+            version: 1,
+            expires: expires_in(365),
+            mirrors: Vec::new(),
+            extensions: HashMap::new(),
+        }
+    }
+
+    /// Override the `spec_version` of the mirrors metadata. `build` rejects malformed versions.
+    pub fn spec_version(mut self, spec_version: String) -> Self {
+        self.spec_version = Some(spec_version);
+        self
+    }
+
+    /// Set the version number for this metadata.
+    pub fn version(mut self, version: u32) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Set the time this metadata expires.
+    pub fn expires(mut self, expires: DateTime<Utc>) -> Self {
+        self.expires = expires;
+        self
+    }
+
+    /// Add a mirror to the end of the list of mirrors.
+    pub fn mirror(mut self, mirror: Mirror) -> Self {
+        self.mirrors.push(mirror);
+        self
+    }
+
+    /// Add a mirrors metadata field this library has no model for.
+    pub fn extension(mut self, key: String, value: ExtensionValue) -> Self {
+        let _ = self.extensions.insert(key, value);
+        self
+    }
+
+    /// Construct a new `MirrorsMetadata`.
+    pub fn build(self) -> Result<MirrorsMetadata> {
+        let extensions = self.extensions;
+        MirrorsMetadata::new(self.version, self.expires, self.mirrors)?
+            .with_spec_version(self.spec_version)
+            .map(|mirrors| mirrors.with_extensions(extensions))
+    }
+
+    /// Construct a new `SignedMetadata<D, MirrorsMetadata>`.
+    pub async fn signed<'a, D>(
+        self,
+        signer: &'a (dyn Signer + 'a),
+    ) -> Result<SignedMetadata<D, MirrorsMetadata>>
+    where
+        D: DataInterchange,
+    {
+        Ok(await!(SignedMetadata::new(self.build()?, signer))?)
+    }
+}
+
+impl Default for MirrorsMetadataBuilder {
+    fn default() -> Self {
+        MirrorsMetadataBuilder::new()
+    }
+}
+
+impl From<MirrorsMetadata> for MirrorsMetadataBuilder {
+    fn from(meta: MirrorsMetadata) -> Self {
+        MirrorsMetadataBuilder {
+            spec_version: meta.spec_version,
+            version: meta.version,
+            expires: meta.expires,
+            mirrors: meta.mirrors,
+            extensions: meta.extensions,
+        }
+    }
+}
+
+/// Metadata for the mirrors role.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MirrorsMetadata {
+    spec_version: Option<String>,
+    version: u32,
+    expires: DateTime<Utc>,
+    mirrors: Vec<Mirror>,
+    extensions: HashMap<String, ExtensionValue>,
+}
+
+impl MirrorsMetadata {
+    /// Create new `MirrorsMetadata`.
+    pub fn new(version: u32, expires: DateTime<Utc>, mirrors: Vec<Mirror>) -> Result<Self> {
+        if version < 1 {
+            return Err(Error::IllegalArgument(format!(
+                "Metadata version must be greater than zero. Found: {}",
+                version
+            )));
+        }
+
+        Ok(MirrorsMetadata {
+            spec_version: Some(SPEC_VERSION.into()),
+            version,
+            expires,
+            mirrors,
+            extensions: HashMap::new(),
+        })
+    }
+
+    /// An immutable reference to the mirrors, in the order they should be tried.
+    pub fn mirrors(&self) -> &[Mirror] {
+        &self.mirrors
+    }
+
+    /// Set or clear the `spec_version` of the mirrors metadata.
+    pub fn with_spec_version(mut self, spec_version: Option<String>) -> Result<Self> {
+        if let Some(ref spec_version) = spec_version {
+            let _ = spec_major_version(spec_version)?;
+        }
+        self.spec_version = spec_version;
+        Ok(self)
+    }
+
+    /// Fields of the mirrors metadata that this library has no model for.
+    pub fn extensions(&self) -> &HashMap<String, ExtensionValue> {
+        &self.extensions
+    }
+
+    /// Replace the mirrors metadata's unmodeled fields.
+    pub fn with_extensions(mut self, extensions: HashMap<String, ExtensionValue>) -> Self {
+        self.extensions = extensions;
+        self
+    }
+}
+
+impl Metadata for MirrorsMetadata {
+    const ROLE: Role = Role::Mirrors;
+
+    fn spec_version(&self) -> Option<&str> {
+        self.spec_version.as_ref().map(|v| v.as_str())
+    }
+
+    fn version(&self) -> u32 {
+        self.version
+    }
+
+    fn expires(&self) -> &DateTime<Utc> {
+        &self.expires
+    }
+}
+
+impl Serialize for MirrorsMetadata {
+    fn serialize<S>(&self, ser: S) -> ::std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        shims::MirrorsMetadata::from(self)
+            .map_err(|e| SerializeError::custom(format!("{:?}", e)))?
+            .serialize(ser)
+    }
+}
+
+impl<'de> Deserialize<'de> for MirrorsMetadata {
+    fn deserialize<D: Deserializer<'de>>(de: D) -> ::std::result::Result<Self, D::Error> {
+        let intermediate: shims::MirrorsMetadata = Deserialize::deserialize(de)?;
+        intermediate
+            .try_into()
+            .map_err(|e| DeserializeError::custom(format!("{:?}", e)))
+    }
+}
+
+/// A mirror of the repository, as listed in the mirrors metadata.
+///
+/// Metadata is found under `url_base` joined with `meta_path`, and targets under `url_base`
+/// joined with `targets_path`. The content patterns describe which files the mirror serves. In a
+/// pattern, `*` matches any sequence of characters and `?` matches any single character.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mirror {
+    url_base: Url,
+    meta_path: String,
+    targets_path: String,
+    meta_content: Vec<String>,
+    targets_content: Vec<String>,
+}
+
+impl Mirror {
+    /// Create a new `Mirror`.
+    pub fn new(
+        url_base: Url,
+        meta_path: String,
+        targets_path: String,
+        meta_content: Vec<String>,
+        targets_content: Vec<String>,
+    ) -> Result<Self> {
+        if url_base.cannot_be_a_base() {
+            return Err(Error::IllegalArgument(format!(
+                "Mirror URL was 'cannot-be-a-base': {}",
+                url_base
+            )));
+        }
+
+        for path in &[&meta_path, &targets_path] {
+            if path.split('/').any(|c| c == "..") {
+                return Err(Error::IllegalArgument(format!(
+                    "Mirror path cannot contain '..': {:?}",
+                    path
+                )));
+            }
+        }
+
+        Ok(Mirror {
+            url_base,
+            meta_path,
+            targets_path,
+            meta_content,
+            targets_content,
+        })
+    }
+
+    /// The base URL of the mirror.
+    pub fn url_base(&self) -> &Url {
+        &self.url_base
+    }
+
+    /// The path of the metadata relative to `url_base`.
+    pub fn meta_path(&self) -> &str {
+        &self.meta_path
+    }
+
+    /// The path of the targets relative to `url_base`.
+    pub fn targets_path(&self) -> &str {
+        &self.targets_path
+    }
+
+    /// Patterns of the metadata files this mirror serves.
+    pub fn meta_content(&self) -> &[String] {
+        &self.meta_content
+    }
+
+    /// Patterns of the targets this mirror serves.
+    pub fn targets_content(&self) -> &[String] {
+        &self.targets_content
+    }
+
+    /// The URL under which this mirror serves metadata.
+    ///
+    /// ```
+    /// # use tuf::metadata::Mirror;
+    /// # use url::Url;
+    /// let mirror = Mirror::new(
+    ///     Url::parse("https://tuf.example.com/repo").unwrap(),
+    ///     "meta".into(),
+    ///     "targets".into(),
+    ///     vec!["*".into()],
+    ///     vec![],
+    /// ).unwrap();
+    /// assert_eq!(mirror.metadata_url().as_str(), "https://tuf.example.com/repo/meta");
+    /// ```
+    pub fn metadata_url(&self) -> Url {
+        self.join(&self.meta_path)
+    }
+
+    /// The URL under which this mirror serves targets.
+    pub fn targets_url(&self) -> Url {
+        self.join(&self.targets_path)
+    }
+
+    /// Whether this mirror serves the metadata at `path`. The patterns are matched against the
+    /// file name of the metadata, such as `root.json`.
+    pub fn serves_metadata<D>(&self, path: &MetadataPath) -> bool
+    where
+        D: DataInterchange,
+    {
+        let file = path.components::<D>(&MetadataVersion::None).join("/");
+        self.meta_content
+            .iter()
+            .any(|pattern| pattern_matches(pattern, &file))
+    }
+
+    /// Whether this mirror serves the target at `path`.
+    ///
+    /// ```
+    /// # use tuf::metadata::{Mirror, TargetPath};
+    /// # use url::Url;
+    /// let mirror = Mirror::new(
+    ///     Url::parse("https://tuf.example.com/").unwrap(),
+    ///     "".into(),
+    ///     "targets".into(),
+    ///     vec![],
+    ///     vec!["releases/*.tar.gz".into()],
+    /// ).unwrap();
+    /// assert!(mirror.serves_target(&TargetPath::new("releases/foo-1.0.tar.gz".into()).unwrap()));
+    /// assert!(!mirror.serves_target(&TargetPath::new("releases/foo-1.0.zip".into()).unwrap()));
+    /// ```
+    pub fn serves_target(&self, path: &TargetPath) -> bool {
+        self.targets_content
+            .iter()
+            .any(|pattern| pattern_matches(pattern, path.value()))
+    }
+
+    fn join(&self, path: &str) -> Url {
+        let mut url = self.url_base.clone();
+        // `new` checked that the URL can be a base
+        if let Ok(mut segments) = url.path_segments_mut() {
+            segments
+                .pop_if_empty()
+                .extend(path.split('/').filter(|s| !s.is_empty()));
+        }
+        url
+    }
+}
+
+impl Serialize for Mirror {
+    fn serialize<S>(&self, ser: S) -> ::std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        shims::Mirror::from(self).serialize(ser)
+    }
+}
+
+impl<'de> Deserialize<'de> for Mirror {
+    fn deserialize<D: Deserializer<'de>>(de: D) -> ::std::result::Result<Self, D::Error> {
+        let intermediate: shims::Mirror = Deserialize::deserialize(de)?;
+        intermediate
+            .try_into()
+            .map_err(|e| DeserializeError::custom(format!("{:?}", e)))
+    }
+}
+
+/// Match `s` against a pattern where `*` matches any sequence of characters and `?` matches any
+/// single character.
+fn pattern_matches(pattern: &str, s: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let s = s.chars().collect::<Vec<_>>();
+
+    let (mut p, mut i) = (0, 0);
+    // position of the last `*` in the pattern and where in `s` it started matching
+    let mut star: Option<(usize, usize)> = None;
+
+    while i < s.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == s[i]) {
+            p += 1;
+            i += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, i));
+            p += 1;
+        } else if let Some((star_p, star_i)) = star {
+            p = star_p + 1;
+            i = star_i + 1;
+            star = Some((star_p, star_i + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crypto::{PrivateKey, SignatureScheme};
+    #[cfg(feature = "cbor")]
+    use crate::interchange::Cbor;
+    use crate::interchange::{Json, LenientJson};
+    use chrono::prelude::*;
+    use futures::executor::block_on;
+    use maplit::{hashmap, hashset};
+    use serde_json::json;
 
     const ED25519_1_PK8: &'static [u8] = include_bytes!("../tests/ed25519/ed25519-1.pk8.der");
     const ED25519_2_PK8: &'static [u8] = include_bytes!("../tests/ed25519/ed25519-2.pk8.der");
@@ -1988,6 +3024,25 @@ mod test {
         assert!(serde_json::from_value::<RoleDefinition>(jsn).is_err());
     }
 
+    #[test]
+    #[cfg(not(feature = "ring"))]
+    fn root_metadata_unsupported_key_scheme() {
+        let ed25519_key = PrivateKey::from_pkcs8(ED25519_1_PK8, SignatureScheme::Ed25519).unwrap();
+        let ecdsa_key = PublicKey::from_spki(
+            include_bytes!("../tests/ecdsa/ecdsa-p256.spki.der"),
+            SignatureScheme::EcdsaP256Sha256,
+        )
+        .unwrap();
+
+        assert!(RootMetadataBuilder::new()
+            .root_key(ecdsa_key)
+            .snapshot_key(ed25519_key.public().clone())
+            .targets_key(ed25519_key.public().clone())
+            .timestamp_key(ed25519_key.public().clone())
+            .build()
+            .is_err());
+    }
+
     #[test]
     fn serde_root_metadata() {
         let root_key = PrivateKey::from_pkcs8(ED25519_1_PK8, SignatureScheme::Ed25519).unwrap();
@@ -2007,6 +3062,7 @@ mod test {
 
         let jsn = json!({
             "type": "root",
+            "spec_version": "1.0.0",
             "version": 1,
             "expires": "2017-01-01T00:00:00Z",
             "consistent_snapshot": false,
@@ -2076,6 +3132,7 @@ mod test {
 
         let jsn = json!({
             "type": "timestamp",
+            "spec_version": "1.0.0",
             "version": 1,
             "expires": "2017-01-01T00:00:00Z",
             "snapshot": {
@@ -2111,6 +3168,7 @@ mod test {
 
         let jsn = json!({
             "type": "snapshot",
+            "spec_version": "1.0.0",
             "version": 1,
             "expires": "2017-01-01T00:00:00Z",
             "meta": {
@@ -2143,6 +3201,7 @@ mod test {
 
         let jsn = json!({
             "type": "targets",
+            "spec_version": "1.0.0",
             "version": 1,
             "expires": "2017-01-01T00:00:00Z",
             "targets": {
@@ -2161,6 +3220,95 @@ mod test {
         assert_eq!(decoded, targets);
     }
 
+    #[test]
+    fn serde_mirrors_metadata() {
+        let mirrors = MirrorsMetadataBuilder::new()
+            .expires(Utc.ymd(2017, 1, 1).and_hms(0, 0, 0))
+            .mirror(
+                Mirror::new(
+                    Url::parse("https://mirror.example.com/tuf").unwrap(),
+                    "metadata".into(),
+                    "targets".into(),
+                    vec!["*.json".into()],
+                    vec!["releases/*".into()],
+                )
+                .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        let jsn = json!({
+            "type": "mirrors",
+            "spec_version": "1.0.0",
+            "version": 1,
+            "expires": "2017-01-01T00:00:00Z",
+            "mirrors": [
+                {
+                    "urlbase": "https://mirror.example.com/tuf",
+                    "metapath": "metadata",
+                    "targetspath": "targets",
+                    "metacontent": ["*.json"],
+                    "targetscontent": ["releases/*"],
+                },
+            ],
+        });
+
+        let encoded = serde_json::to_value(&mirrors).unwrap();
+        assert_eq!(encoded, jsn);
+        let decoded: MirrorsMetadata = serde_json::from_value(encoded).unwrap();
+        assert_eq!(decoded, mirrors);
+
+        let mirror = &decoded.mirrors()[0];
+        assert_eq!(
+            mirror.targets_url().as_str(),
+            "https://mirror.example.com/tuf/targets"
+        );
+        assert!(mirror.serves_metadata::<Json>(&MetadataPath::from_role(&Role::Root)));
+        assert!(!mirror.serves_target(&TargetPath::new("foo".into()).unwrap()));
+    }
+
+    #[test]
+    fn serde_root_with_mirrors_metadata() {
+        let key = PrivateKey::from_pkcs8(ED25519_1_PK8, SignatureScheme::Ed25519).unwrap();
+        let root = RootMetadataBuilder::new()
+            .root_key(key.public().clone())
+            .snapshot_key(key.public().clone())
+            .targets_key(key.public().clone())
+            .timestamp_key(key.public().clone())
+            .mirrors_threshold(1)
+            .mirrors_key(key.public().clone())
+            .build()
+            .unwrap();
+        assert!(root.mirrors().is_some());
+
+        let encoded = serde_json::to_value(&root).unwrap();
+        assert_eq!(
+            encoded["mirrors"],
+            json!({
+                "threshold": 1,
+                "key_ids": [key.key_id()],
+            })
+        );
+        let decoded: RootMetadata = serde_json::from_value(encoded).unwrap();
+        assert_eq!(decoded, root);
+
+        let encoded = serde_json::to_value(&RootMetadataBuilder::from(root).build().unwrap());
+        assert_eq!(encoded.unwrap()["mirrors"]["threshold"], json!(1));
+    }
+
+    #[test]
+    fn mirror_content_patterns() {
+        assert!(pattern_matches("*", ""));
+        assert!(pattern_matches("*", "foo/bar"));
+        assert!(pattern_matches("foo/*.tar.gz", "foo/bar.tar.gz"));
+        assert!(pattern_matches("foo/*.tar.gz", "foo/bar/baz.tar.gz"));
+        assert!(pattern_matches("?oo*", "foo"));
+        assert!(pattern_matches("*a*b", "xaxxab"));
+        assert!(!pattern_matches("foo/*.tar.gz", "foo/bar.zip"));
+        assert!(!pattern_matches("?oo", "oo"));
+        assert!(!pattern_matches("foo", "foobar"));
+    }
+
     #[test]
     fn serde_targets_with_delegations_metadata() {
         let key = PrivateKey::from_pkcs8(ED25519_1_PK8, SignatureScheme::Ed25519).unwrap();
@@ -2185,6 +3333,7 @@ mod test {
 
         let jsn = json!({
             "type": "targets",
+            "spec_version": "1.0.0",
             "version": 1,
             "expires": "2017-01-01T00:00:00Z",
             "targets": {},
@@ -2211,10 +3360,237 @@ mod test {
 
         let encoded = serde_json::to_value(&targets).unwrap();
         assert_eq!(encoded, jsn);
+        assert_eq!(
+            Json::to_canonical_bytes(&targets).unwrap(),
+            Json::canonicalize(&encoded).unwrap()
+        );
         let decoded: TargetsMetadata = serde_json::from_value(encoded).unwrap();
         assert_eq!(decoded, targets);
     }
 
+    #[test]
+    fn verify_received_bytes() {
+        let key = PrivateKey::from_pkcs8(ED25519_1_PK8, SignatureScheme::Ed25519).unwrap();
+        let snapshot = SnapshotMetadataBuilder::new()
+            .expires(Utc.ymd(2038, 1, 1).and_hms(0, 0, 0))
+            .build()
+            .unwrap();
+
+        // a field this version of the library does not know about
+        let mut signed = serde_json::to_value(&snapshot).unwrap();
+        let _ = signed
+            .as_object_mut()
+            .unwrap()
+            .insert("custom".into(), json!({"foo": "bar"}));
+        let sig = key.sign(&Json::canonicalize(&signed).unwrap()).unwrap();
+        let jsn = json!({ "signatures": [sig], "signed": signed });
+
+        let bytes = serde_json::to_vec(&jsn).unwrap();
+        let parsed = SignedMetadata::<Json, SnapshotMetadata>::from_slice(&bytes).unwrap();
+        assert!(parsed.verify(1, vec![key.public()]).is_ok());
+
+        // writing the metadata back out keeps the unknown field
+        let mut out = Vec::new();
+        Json::to_writer(&mut out, &parsed).unwrap();
+        assert_eq!(out, bytes);
+
+        // without the received bytes the unknown field is carried as an extension
+        let reparsed: SignedMetadata<Json, SnapshotMetadata> = Json::from_slice(&bytes).unwrap();
+        assert!(reparsed.verify(1, vec![key.public()]).is_ok());
+
+        // the received bytes must be canonical
+        let bytes = serde_json::to_vec_pretty(&jsn).unwrap();
+        assert!(SignedMetadata::<Json, SnapshotMetadata>::from_slice(&bytes).is_err());
+    }
+
+    #[test]
+    fn preserve_unknown_fields() {
+        let extension = json!({
+            "flag": true,
+            "count": 3,
+            "offset": -3,
+            "list": ["a", null],
+        });
+
+        for mut jsn in vec![
+            make_root(),
+            make_snapshot(),
+            make_timestamp(),
+            make_targets(),
+        ] {
+            let _ = jsn
+                .as_object_mut()
+                .unwrap()
+                .insert("x-extension".into(), extension.clone());
+
+            let encoded = match jsn["type"].as_str().unwrap() {
+                "root" => {
+                    let decoded: RootMetadata = serde_json::from_value(jsn.clone()).unwrap();
+                    serde_json::to_value(&decoded).unwrap()
+                }
+                "snapshot" => {
+                    let decoded: SnapshotMetadata = serde_json::from_value(jsn.clone()).unwrap();
+                    serde_json::to_value(&decoded).unwrap()
+                }
+                "timestamp" => {
+                    let decoded: TimestampMetadata = serde_json::from_value(jsn.clone()).unwrap();
+                    serde_json::to_value(&decoded).unwrap()
+                }
+                "targets" => {
+                    let decoded: TargetsMetadata = serde_json::from_value(jsn.clone()).unwrap();
+                    serde_json::to_value(&decoded).unwrap()
+                }
+                typ => panic!("unexpected type: {}", typ),
+            };
+            assert_eq!(encoded, jsn);
+        }
+    }
+
+    #[test]
+    fn edit_preserves_unknown_fields() {
+        let mut jsn = make_targets();
+        let _ = jsn
+            .as_object_mut()
+            .unwrap()
+            .insert("x-extension".into(), json!({"foo": "bar"}));
+        let targets: TargetsMetadata = serde_json::from_value(jsn).unwrap();
+        assert_eq!(
+            targets.extensions().get("x-extension"),
+            Some(&ExtensionValue::Object(
+                vec![("foo".into(), ExtensionValue::String("bar".into()))]
+                    .into_iter()
+                    .collect()
+            ))
+        );
+
+        let targets = TargetsMetadataBuilder::from(targets)
+            .version(2)
+            .insert_target_description(
+                VirtualTargetPath::new("foo".into()).unwrap(),
+                TargetDescription::from_reader(&b"foo"[..], &[HashAlgorithm::Sha256]).unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        let encoded = serde_json::to_value(&targets).unwrap();
+        assert_eq!(encoded["version"], json!(2));
+        assert_eq!(encoded["x-extension"], json!({"foo": "bar"}));
+    }
+
+    #[test]
+    fn preserve_unknown_nested_fields() {
+        let extension = json!({"foo": "bar"});
+
+        // keys and role definitions survive a rebuild of the root
+        let mut jsn = make_root();
+        let _ = jsn["keys"][0]
+            .as_object_mut()
+            .unwrap()
+            .insert("keyid_hash_algorithms".into(), json!(["sha256"]));
+        let _ = jsn["root"]
+            .as_object_mut()
+            .unwrap()
+            .insert("x-extension".into(), extension.clone());
+        let root: RootMetadata = serde_json::from_value(jsn.clone()).unwrap();
+        let root = RootMetadataBuilder::from(root).version(2).build().unwrap();
+        let mut encoded = serde_json::to_value(&root).unwrap();
+        set_version(&mut encoded, 1);
+        assert_eq!(encoded, jsn);
+
+        // so do target descriptions
+        let mut jsn = make_targets();
+        let mut description = serde_json::to_value(
+            TargetDescription::from_reader(&b"foo"[..], &[HashAlgorithm::Sha256]).unwrap(),
+        )
+        .unwrap();
+        let _ = description
+            .as_object_mut()
+            .unwrap()
+            .insert("custom".into(), extension.clone());
+        let _ = jsn["targets"]
+            .as_object_mut()
+            .unwrap()
+            .insert("foo".into(), description);
+        let targets: TargetsMetadata = serde_json::from_value(jsn.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&targets).unwrap(), jsn);
+
+        // and delegations
+        let mut jsn = make_delegations();
+        let _ = jsn["roles"][0]
+            .as_object_mut()
+            .unwrap()
+            .insert("path_hash_prefixes".into(), json!(["00"]));
+        let delegations: Delegations = serde_json::from_value(jsn.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&delegations).unwrap(), jsn);
+    }
+
+    #[test]
+    fn reject_bad_extensions() {
+        let mut jsn = make_snapshot();
+        let _ = jsn
+            .as_object_mut()
+            .unwrap()
+            .insert("x-extension".into(), json!(1.5));
+        assert!(serde_json::from_value::<SnapshotMetadata>(jsn).is_err());
+
+        let snapshot = SnapshotMetadataBuilder::new()
+            .extension("version".into(), ExtensionValue::Unsigned(2))
+            .build()
+            .unwrap();
+        assert!(serde_json::to_value(&snapshot).is_err());
+    }
+
+    fn sign_snapshot_with_expires(expires: &str) -> Vec<u8> {
+        let key = PrivateKey::from_pkcs8(ED25519_1_PK8, SignatureScheme::Ed25519).unwrap();
+        let mut signed = make_snapshot();
+        let _ = signed
+            .as_object_mut()
+            .unwrap()
+            .insert("expires".into(), json!(expires));
+        let sig = key.sign(&Json::canonicalize(&signed).unwrap()).unwrap();
+        Json::canonicalize(&json!({ "signatures": [sig], "signed": signed })).unwrap()
+    }
+
+    #[test]
+    fn lenient_datetimes() {
+        let key = PrivateKey::from_pkcs8(ED25519_1_PK8, SignatureScheme::Ed25519).unwrap();
+        for &(ts, expected) in &[
+            ("2038-01-01T00:00:00.25Z", "2038-01-01T00:00:00.250Z"),
+            ("2038-01-01T00:00:00+00:00", "2038-01-01T00:00:00Z"),
+        ] {
+            let bytes = sign_snapshot_with_expires(ts);
+            let snapshot =
+                SignedMetadata::<LenientJson, SnapshotMetadata>::from_slice(&bytes).unwrap();
+            assert!(snapshot.verify(1, vec![key.public()]).is_ok());
+
+            // fractional seconds are kept
+            let encoded = serde_json::to_value(snapshot.as_ref()).unwrap();
+            assert_eq!(encoded["expires"], json!(expected));
+        }
+
+        let bytes = sign_snapshot_with_expires("2038-01-01T01:00:00+01:00");
+        assert!(SignedMetadata::<LenientJson, SnapshotMetadata>::from_slice(&bytes).is_err());
+    }
+
+    #[test]
+    fn strict_datetimes() {
+        for ts in &["2038-01-01T00:00:00.25Z", "2038-01-01T00:00:00+00:00"] {
+            let bytes = sign_snapshot_with_expires(ts);
+            assert!(SignedMetadata::<Json, SnapshotMetadata>::from_slice(&bytes).is_err());
+            assert!(Json::from_slice::<SignedMetadata<Json, SnapshotMetadata>>(&bytes).is_err());
+        }
+
+        // what this library writes, it can read back
+        let snapshot = SnapshotMetadataBuilder::new()
+            .expires(Utc.ymd(2038, 1, 1).and_hms_milli(0, 0, 0, 250))
+            .build()
+            .unwrap();
+        let encoded = serde_json::to_value(&snapshot).unwrap();
+        assert_eq!(encoded["expires"], json!("2038-01-01T00:00:00.250Z"));
+        let decoded: SnapshotMetadata = serde_json::from_value(encoded).unwrap();
+        assert_eq!(decoded, snapshot);
+    }
+
     #[test]
     fn serde_signed_metadata() {
         let snapshot = SnapshotMetadataBuilder::new()
@@ -2233,18 +3609,19 @@ mod test {
 
         let key = PrivateKey::from_pkcs8(ED25519_1_PK8, SignatureScheme::Ed25519).unwrap();
 
-        let signed = SignedMetadata::<Json, _>::new(snapshot, &key).unwrap();
+        let signed = block_on(SignedMetadata::<Json, _>::new(snapshot, &key)).unwrap();
 
         let jsn = json!({
             "signatures": [
                 {
                     "key_id": "qfrfBrkB4lBBSDEBlZgaTGS_SrE6UfmON9kP4i3dJFY=",
-                    "value": "9QXO-Av15zaWEsheO9JbWdo8iAF9vEbUKVePJpGRX5s6b1G8eqH4kvAE2jZV349JvZ\
-                        -2yPGLE20V_7JwhMLYCQ==",
+                    "value": "cYDNPk3N7tpSTKltz6utLmvssIk-uXQQcwaVJjqHeql8c5hPrXPXpI9phwGwoeq_mp\
+                        -GvBP7oY77ouNq5-xWBA==",
                 }
             ],
             "signed": {
                 "type": "snapshot",
+                "spec_version": "1.0.0",
                 "version": 1,
                 "expires": "2017-01-01T00:00:00Z",
                 "meta": {
@@ -2396,6 +3773,38 @@ mod test {
         assert!(serde_json::from_value::<RootMetadata>(root_json).is_err());
     }
 
+    // Refuse to deserialize metadata with a malformed spec version
+    #[test]
+    fn deserialize_json_illegal_spec_version() {
+        let mut snapshot_json = make_snapshot();
+        let _ = snapshot_json
+            .as_object_mut()
+            .unwrap()
+            .insert("spec_version".into(), json!("one"));
+        assert!(serde_json::from_value::<SnapshotMetadata>(snapshot_json).is_err());
+
+        assert!(SnapshotMetadataBuilder::new()
+            .spec_version("one".into())
+            .build()
+            .is_err());
+    }
+
+    // Metadata written before `spec_version` existed still parses and keeps its bytes.
+    #[test]
+    fn deserialize_json_missing_spec_version() {
+        let mut snapshot_json = make_snapshot();
+        let _ = snapshot_json
+            .as_object_mut()
+            .unwrap()
+            .remove("spec_version");
+        let snapshot: SnapshotMetadata = serde_json::from_value(snapshot_json.clone()).unwrap();
+        assert_eq!(snapshot.spec_version(), None);
+        assert_eq!(serde_json::to_value(&snapshot).unwrap(), snapshot_json);
+
+        let snapshot = SnapshotMetadataBuilder::from(snapshot).build().unwrap();
+        assert_eq!(snapshot.spec_version(), None);
+    }
+
     // Refuse to deserialize root metadata if it contains duplicate keys
     #[test]
     fn deserialize_json_root_duplicate_keys() {
@@ -2721,4 +4130,90 @@ mod test {
             .push(dupe);
         assert!(serde_json::from_value::<Delegations>(delegations).is_err());
     }
+
+    #[cfg(feature = "cbor")]
+    fn assert_cbor_round_trip<M>(signed: &SignedMetadata<Cbor, M>, key: &PrivateKey)
+    where
+        M: Metadata,
+    {
+        let mut bytes = Vec::new();
+        Cbor::to_writer(&mut bytes, signed).unwrap();
+        assert_eq!(Cbor::to_canonical_bytes(signed).unwrap(), bytes);
+        assert_eq!(
+            Cbor::canonicalize(&Cbor::serialize(signed).unwrap()).unwrap(),
+            bytes
+        );
+
+        let decoded = SignedMetadata::<Cbor, M>::from_slice(&bytes).unwrap();
+        assert_eq!(&decoded, signed);
+        decoded.verify(1, vec![key.public()]).unwrap();
+
+        let decoded: SignedMetadata<Cbor, M> = Cbor::from_reader(&*bytes).unwrap();
+        assert_eq!(&decoded, signed);
+    }
+
+    #[test]
+    #[cfg(feature = "cbor")]
+    fn cbor_round_trip_all_metadata() {
+        let key = PrivateKey::from_pkcs8(ED25519_1_PK8, SignatureScheme::Ed25519).unwrap();
+        let expires = Utc.ymd(2038, 1, 1).and_hms(0, 0, 0);
+
+        let root = block_on(
+            RootMetadataBuilder::new()
+                .expires(expires)
+                .root_key(key.public().clone())
+                .snapshot_key(key.public().clone())
+                .targets_key(key.public().clone())
+                .timestamp_key(key.public().clone())
+                .signed::<Cbor>(&key),
+        )
+        .unwrap();
+        assert_cbor_round_trip(&root, &key);
+
+        let delegations = Delegations::new(
+            &hashset![key.public().clone()],
+            vec![Delegation::new(
+                MetadataPath::new("foo/bar".into()).unwrap(),
+                false,
+                1,
+                hashset!(key.key_id().clone()),
+                hashset!(VirtualTargetPath::new("baz/quux".into()).unwrap()),
+            )
+            .unwrap()],
+        )
+        .unwrap();
+        let targets = block_on(
+            TargetsMetadataBuilder::new()
+                .expires(expires)
+                .insert_target_from_reader(
+                    VirtualTargetPath::new("foo".into()).unwrap(),
+                    &b"foo"[..],
+                    &[HashAlgorithm::Sha256, HashAlgorithm::Sha512],
+                )
+                .unwrap()
+                .delegations(delegations)
+                .signed::<Cbor>(&key),
+        )
+        .unwrap();
+        assert_cbor_round_trip(&targets, &key);
+
+        let snapshot = block_on(
+            SnapshotMetadataBuilder::new()
+                .expires(expires)
+                .insert_metadata(&targets, &[HashAlgorithm::Sha256])
+                .unwrap()
+                .signed::<Cbor>(&key),
+        )
+        .unwrap();
+        assert_cbor_round_trip(&snapshot, &key);
+
+        let timestamp = block_on(
+            TimestampMetadataBuilder::from_snapshot(&snapshot, &[HashAlgorithm::Sha256])
+                .unwrap()
+                .expires(expires)
+                .signed::<Cbor>(&key),
+        )
+        .unwrap();
+        assert_cbor_round_trip(&timestamp, &key);
+    }
 }
@@ -1,29 +1,50 @@
 //! Interfaces for interacting with different types of TUF repositories.
 
+#[cfg(feature = "hyper")]
 use futures::compat::{Future01CompatExt, Stream01CompatExt};
 use futures::io::{AllowStdIo, AsyncRead};
 use futures::prelude::*;
-use http::{Response, StatusCode, Uri};
-use hyper::body::Body;
+use futures::task::LocalWaker;
+use futures::{try_ready, Poll};
+use http::header::{
+    HeaderMap, HeaderName, HeaderValue, ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_LENGTH, ETAG,
+    IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, RANGE,
+};
+use http::{Method, Request, Response, StatusCode, Uri};
+#[cfg(feature = "hyper")]
+use hyper::body::{Body, Chunk};
+#[cfg(feature = "hyper")]
 use hyper::client::connect::Connect;
+#[cfg(feature = "hyper")]
 use hyper::Client;
-use hyper::Request;
 use log::debug;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
 use std::collections::HashMap;
 use std::fs::{DirBuilder, File};
-use std::io::{self, Cursor};
+use std::io::{self, Cursor, Seek, SeekFrom};
 use std::marker::PhantomData;
 use std::path::{Path, PathBuf};
+use std::pin::Pin;
 use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 use tempfile::{self, NamedTempFile};
 
-use crate::crypto::{self, HashAlgorithm, HashValue};
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+use crate::compression::DecodingReader;
+#[cfg(feature = "gzip")]
+use crate::compression::GzipDecompress;
+#[cfg(feature = "zstd")]
+use crate::compression::ZstdDecompress;
+use crate::crypto::{HashAlgorithm, HashValue};
 use crate::error::Error;
 use crate::interchange::DataInterchange;
 use crate::metadata::{
-    Metadata, MetadataPath, MetadataVersion, SignedMetadata, TargetDescription, TargetPath,
+    self, Metadata, MetadataPath, MetadataVersion, Mirror, SignedMetadata, TargetDescription,
+    TargetPath,
 };
-use crate::util::SafeReader;
+use crate::util::{self, SafeReader};
+pub use crate::util::{SlowRetrievalPolicy, SystemTimeSource, TimeSource};
 use crate::{Result, TufFuture};
 use url::Url;
 
@@ -47,16 +68,56 @@ where
         M: Metadata + 'static;
 
     /// Fetch signed metadata.
+    ///
+    /// If `hashes` is given, the metadata is checked against every hash in it whose algorithm is
+    /// supported.
     fn fetch_metadata<'a, M>(
         &'a self,
         meta_path: &'a MetadataPath,
         version: &'a MetadataVersion,
         max_size: &'a Option<usize>,
-        hash_data: Option<(&'static HashAlgorithm, HashValue)>,
+        hashes: Option<&'a HashMap<HashAlgorithm, HashValue>>,
     ) -> TufFuture<'a, Result<SignedMetadata<D, M>>>
     where
         M: Metadata + 'static;
 
+    /// Fetch signed metadata like `fetch_metadata`, unless the repository knows the metadata has
+    /// not changed since it last returned it, in which case this returns `Ok(None)`. Only metadata
+    /// that was reported as verified with `metadata_verified` counts.
+    ///
+    /// By default the metadata is always fetched.
+    fn fetch_metadata_if_changed<'a, M>(
+        &'a self,
+        meta_path: &'a MetadataPath,
+        version: &'a MetadataVersion,
+        max_size: &'a Option<usize>,
+        hashes: Option<&'a HashMap<HashAlgorithm, HashValue>>,
+    ) -> TufFuture<'a, Result<Option<SignedMetadata<D, M>>>>
+    where
+        M: Metadata + 'static,
+    {
+        Box::pin(
+            async move {
+                let metadata = await!(self.fetch_metadata(meta_path, version, max_size, hashes))?;
+                Ok(Some(metadata))
+            },
+        )
+    }
+
+    /// Report whether the metadata that `fetch_metadata_if_changed` last returned for `meta_path`
+    /// and `version` was verified and is now trusted. If it was not, the repository must not
+    /// treat it as unchanged later on.
+    ///
+    /// By default this does nothing.
+    fn metadata_verified(
+        &self,
+        meta_path: &MetadataPath,
+        version: &MetadataVersion,
+        verified: bool,
+    ) {
+        let _ = (meta_path, version, verified);
+    }
+
     /// Store the given target.
     fn store_target<'a, R>(
         &'a self,
@@ -67,12 +128,48 @@ where
         R: AsyncRead + 'a;
 
     /// Fetch the given target.
+    ///
+    /// The target is checked against every hash in `target_description` whose algorithm is
+    /// supported, so callers choose which hashes are verified by which ones they pass in.
     fn fetch_target<'a>(
         &'a self,
         target_path: &'a TargetPath,
         target_description: &'a TargetDescription,
     ) -> TufFuture<'a, Result<Box<dyn AsyncRead>>>;
 
+    /// Fetch the given target, resuming an interrupted download.
+    ///
+    /// `partial` holds the first `partial_len` bytes of the target from an earlier attempt. The
+    /// returned reader yields the whole target, and its size and hash are verified across both
+    /// the bytes from `partial` and the bytes fetched now, as with `fetch_target`. Repositories
+    /// that can not fetch part of a target ignore `partial` and fetch the whole target, which is
+    /// what this does by default.
+    fn fetch_target_resume<'a, P>(
+        &'a self,
+        target_path: &'a TargetPath,
+        target_description: &'a TargetDescription,
+        partial: P,
+        partial_len: u64,
+    ) -> TufFuture<'a, Result<Box<dyn AsyncRead>>>
+    where
+        P: AsyncRead + 'static,
+    {
+        let _ = (partial, partial_len);
+        self.fetch_target(target_path, target_description)
+    }
+
+    /// A repository that fetches from `mirror` in the same way this one fetches from its own
+    /// location. A `Client` falls back on these repositories when fetching from this one fails.
+    ///
+    /// By default there is none, as not every kind of repository can reach a mirror.
+    fn mirror(&self, mirror: &Mirror) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        let _ = mirror;
+        None
+    }
+
     /// Perform a sanity check that `M`, `Role`, and `MetadataPath` all desrcribe the same entity.
     fn check<M>(meta_path: &MetadataPath) -> Result<()>
     where
@@ -96,6 +193,7 @@ where
     D: DataInterchange,
 {
     local_path: PathBuf,
+    versioned_metadata: bool,
     interchange: PhantomData<D>,
 }
 
@@ -113,9 +211,18 @@ where
 
         Ok(FileSystemRepository {
             local_path,
+            versioned_metadata: false,
             interchange: PhantomData,
         })
     }
+
+    /// Store and look up metadata in a directory named after the major version of the TUF
+    /// specification this library implements (e.g. `metadata/1/root.json`), as described in TAP
+    /// 14. Metadata that is not found in the versioned directory is looked up in `metadata/`.
+    pub fn versioned_metadata(mut self, versioned_metadata: bool) -> Self {
+        self.versioned_metadata = versioned_metadata;
+        self
+    }
 }
 
 impl<D> Repository<D> for FileSystemRepository<D>
@@ -136,6 +243,9 @@ where
                 Self::check::<M>(meta_path)?;
 
                 let mut path = self.local_path.join("metadata");
+                if self.versioned_metadata {
+                    path.push(spec_version_directory()?);
+                }
                 path.extend(meta_path.components::<D>(version));
 
                 if path.exists() {
@@ -157,7 +267,7 @@ where
         meta_path: &'a MetadataPath,
         version: &'a MetadataVersion,
         max_size: &'a Option<usize>,
-        hash_data: Option<(&'static HashAlgorithm, HashValue)>,
+        hashes: Option<&'a HashMap<HashAlgorithm, HashValue>>,
     ) -> TufFuture<'a, Result<SignedMetadata<D, M>>>
     where
         M: Metadata + 'static,
@@ -169,17 +279,31 @@ where
                 let mut path = self.local_path.join("metadata");
                 path.extend(meta_path.components::<D>(&version));
 
-                let mut reader = SafeReader::new(
-                    AllowStdIo::new(File::open(&path)?),
-                    max_size.unwrap_or(::std::usize::MAX) as u64,
-                    0,
-                    hash_data,
-                )?;
+                if self.versioned_metadata {
+                    let mut versioned = self.local_path.join("metadata");
+                    versioned.push(spec_version_directory()?);
+                    versioned.extend(meta_path.components::<D>(&version));
+                    if versioned.exists() {
+                        path = versioned;
+                    }
+                }
+
+                let read = AllowStdIo::new(File::open(&path)?);
+                let limit = max_size.unwrap_or(::std::usize::MAX) as u64;
+                let mut reader = match hashes {
+                    Some(hashes) => SafeReader::new_with_hashes(
+                        read,
+                        limit,
+                        SlowRetrievalPolicy::new(0),
+                        hashes,
+                    )?,
+                    None => SafeReader::new(read, limit, SlowRetrievalPolicy::new(0), None)?,
+                };
 
                 let mut buf = Vec::with_capacity(max_size.unwrap_or(0));
                 await!(reader.read_to_end(&mut buf))?;
 
-                Ok(D::from_slice(&buf)?)
+                SignedMetadata::from_slice(&buf)
             },
         )
     }
@@ -224,13 +348,50 @@ where
                     return Err(Error::NotFound);
                 }
 
-                let (alg, value) = crypto::hash_preference(target_description.hashes())?;
-
-                let reader: Box<dyn AsyncRead> = Box::new(SafeReader::new(
+                let reader: Box<dyn AsyncRead> = Box::new(SafeReader::new_with_hashes(
                     AllowStdIo::new(File::open(&path)?),
                     target_description.size(),
-                    0,
-                    Some((alg, value.clone())),
+                    SlowRetrievalPolicy::new(0),
+                    target_description.hashes(),
+                )?);
+
+                Ok(reader)
+            },
+        )
+    }
+
+    /// Reads the target from `partial_len` bytes into the file, after the bytes from `partial`.
+    fn fetch_target_resume<'a, P>(
+        &'a self,
+        target_path: &'a TargetPath,
+        target_description: &'a TargetDescription,
+        partial: P,
+        partial_len: u64,
+    ) -> TufFuture<'a, Result<Box<dyn AsyncRead>>>
+    where
+        P: AsyncRead + 'static,
+    {
+        Box::pin(
+            async move {
+                if partial_len == 0 || partial_len > target_description.size() {
+                    return await!(self.fetch_target(target_path, target_description));
+                }
+
+                let mut path = self.local_path.join("targets");
+                path.extend(target_path.components());
+
+                if !path.exists() {
+                    return Err(Error::NotFound);
+                }
+
+                let mut rest = File::open(&path)?;
+                let _ = rest.seek(SeekFrom::Start(partial_len))?;
+
+                let reader: Box<dyn AsyncRead> = Box::new(SafeReader::new_with_hashes(
+                    Chain::new(partial, AllowStdIo::new(rest)),
+                    target_description.size(),
+                    SlowRetrievalPolicy::new(0),
+                    target_description.hashes(),
                 )?);
 
                 Ok(reader)
@@ -239,6 +400,12 @@ where
     }
 }
 
+/// The name of the TAP 14 directory that holds metadata for the major version of the TUF
+/// specification this library implements.
+fn spec_version_directory() -> Result<String> {
+    Ok(metadata::spec_major_version(metadata::SPEC_VERSION)?.to_string())
+}
+
 fn create_temp_file(path: &Path) -> Result<NamedTempFile> {
     // We want to atomically write the file to make sure clients can never see a partially written
     // file.  In order to do this, we'll write to a temporary file in the same directory as our
@@ -253,34 +420,159 @@ fn create_temp_file(path: &Path) -> Result<NamedTempFile> {
     }
 }
 
-/// A builder to create a repository accessible over HTTP.
-pub struct HttpRepositoryBuilder<C, D>
+/// Sends the HTTP requests of a `HttpRepository`.
+///
+/// This is implemented for `hyper::Client` when the `hyper` feature is enabled. Other HTTP stacks,
+/// such as ones with custom TLS or proxy handling, can be used by implementing it for them.
+pub trait HttpTransport {
+    /// Send `request` and return the response, whose body is read as it arrives.
+    ///
+    /// The request body is `None` if the request has no body, and is otherwise read and sent as
+    /// the request is made. Responses with any status are returned as `Ok`. Errors should be
+    /// returned as `Error::Transport`, with `retryable` set to `false` if sending the request again
+    /// can not fix them, such as a malformed request. Requests that fail with any other error are
+    /// retried.
+    fn request<'a>(
+        &'a self,
+        request: Request<Option<Box<dyn AsyncRead + 'a>>>,
+    ) -> TufFuture<'a, Result<Response<Box<dyn AsyncRead>>>>;
+}
+
+#[cfg(feature = "hyper")]
+impl<C> HttpTransport for Client<C>
 where
     C: Connect + Sync + 'static,
+{
+    fn request<'a>(
+        &'a self,
+        request: Request<Option<Box<dyn AsyncRead + 'a>>>,
+    ) -> TufFuture<'a, Result<Response<Box<dyn AsyncRead>>>> {
+        Box::pin(
+            async move {
+                let (parts, read) = request.into_parts();
+                let mut read = match read {
+                    Some(read) => read,
+                    None => {
+                        let req = Request::from_parts(parts, Body::empty());
+                        let resp = await!(self.request(req).compat()).map_err(hyper_error)?;
+                        return Ok(response_reader(resp));
+                    }
+                };
+
+                let (mut sender, body) = Body::channel();
+                let req = Request::from_parts(parts, body);
+
+                let send_body = async move {
+                    let mut buf = vec![0; 8 * 1024];
+                    loop {
+                        let read_bytes = await!(read.read(&mut buf))?;
+                        if read_bytes == 0 {
+                            break;
+                        }
+                        await!(hyper::rt::poll_fn(|| sender.poll_ready()).compat())
+                            .map_err(hyper_error)?;
+                        if sender
+                            .send_data(Chunk::from(buf[..read_bytes].to_vec()))
+                            .is_err()
+                        {
+                            // The request is gone, and its error is returned with the response.
+                            break;
+                        }
+                    }
+                    // Dropping the sender ends the body.
+                    Ok::<(), Error>(())
+                };
+
+                // The request only finishes once the whole body has been sent, so both halves
+                // need to be driven together.
+                let (sent, resp) = await!(send_body.join(self.request(req).compat()));
+                let resp = resp.map_err(hyper_error)?;
+                sent?;
+
+                Ok(response_reader(resp))
+            },
+        )
+    }
+}
+
+#[cfg(feature = "hyper")]
+fn hyper_error(err: hyper::Error) -> Error {
+    Error::Transport {
+        message: format!("Hyper: {:?}", err),
+        retryable: !err.is_user(),
+    }
+}
+
+#[cfg(feature = "hyper")]
+fn response_reader(resp: Response<Body>) -> Response<Box<dyn AsyncRead>> {
+    resp.map(|body| {
+        let stream = body
+            .compat()
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err));
+        Box::new(stream.into_async_read()) as Box<dyn AsyncRead>
+    })
+}
+
+/// A builder to create a repository accessible over HTTP.
+pub struct HttpRepositoryBuilder<T, D>
+where
+    T: HttpTransport,
     D: DataInterchange,
 {
     url: Url,
-    client: Client<C>,
+    transport: T,
     interchange: PhantomData<D>,
     user_agent: Option<String>,
     metadata_prefix: Option<Vec<String>>,
-    min_bytes_per_second: u32,
+    targets_prefix: Option<Vec<String>>,
+    targets_url: Option<Url>,
+    versioned_metadata: bool,
+    slow_retrieval: SlowRetrievalPolicy,
+    upload_method: HttpUploadMethod,
+    headers: HeaderMap,
+    request_timeout: Option<Duration>,
+    total_timeout: Option<Duration>,
+    retries: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    retry_status_codes: Vec<StatusCode>,
+    content_encodings: Vec<HttpContentEncoding>,
 }
 
-impl<C, D> HttpRepositoryBuilder<C, D>
+impl<T, D> HttpRepositoryBuilder<T, D>
 where
-    C: Connect + Sync + 'static,
+    T: HttpTransport,
     D: DataInterchange,
 {
-    /// Create a new repository with the given `Url` and `Client`.
-    pub fn new(url: Url, client: Client<C>) -> Self {
+    /// Create a new repository with the given `Url` and `HttpTransport`, such as a
+    /// `hyper::Client`.
+    pub fn new(url: Url, transport: T) -> Self {
         HttpRepositoryBuilder {
             url: url,
-            client: client,
+            transport: transport,
             interchange: PhantomData,
             user_agent: None,
             metadata_prefix: None,
-            min_bytes_per_second: 4096,
+            targets_prefix: None,
+            targets_url: None,
+            versioned_metadata: false,
+            slow_retrieval: SlowRetrievalPolicy::new(4096),
+            upload_method: HttpUploadMethod::Put,
+            headers: HeaderMap::new(),
+            request_timeout: None,
+            total_timeout: None,
+            retries: 0,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            retry_status_codes: vec![
+                StatusCode::REQUEST_TIMEOUT,
+                StatusCode::TOO_MANY_REQUESTS,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                StatusCode::BAD_GATEWAY,
+                StatusCode::SERVICE_UNAVAILABLE,
+                StatusCode::GATEWAY_TIMEOUT,
+            ],
+            content_encodings: HttpContentEncoding::all(),
         }
     }
 
@@ -289,29 +581,127 @@ where
     /// Callers *should* include a custom User-Agent prefix to help maintainers of TUF repositories
     /// keep track of which client versions exist in the field.
     ///
-    pub fn user_agent<T: Into<String>>(mut self, user_agent: T) -> Self {
+    pub fn user_agent<U: Into<String>>(mut self, user_agent: U) -> Self {
         self.user_agent = Some(user_agent.into());
         self
     }
 
-    /// The argument `metadata_prefix` is used provide an alternate path where metadata is stored on
-    /// the repository. If `None`, this defaults to `/`. For example, if there is a TUF repository
-    /// at `https://tuf.example.com/`, but all metadata is stored at `/meta/`, then passing the
-    /// arg `Some("meta".into())` would cause `root.json` to be fetched from
+    /// The argument `metadata_prefix` is used to provide an alternate path where metadata is stored
+    /// on the repository. If `None`, this defaults to `/`. For example, if there is a TUF
+    /// repository at `https://tuf.example.com/`, but all metadata is stored at `/meta/`, then
+    /// passing the arg `Some("meta".into())` would cause `root.json` to be fetched from
     /// `https://tuf.example.com/meta/root.json`.
     pub fn metadata_prefix(mut self, metadata_prefix: Vec<String>) -> Self {
         self.metadata_prefix = Some(metadata_prefix);
         self
     }
 
+    /// The argument `targets_prefix` is used to provide an alternate path where targets are stored
+    /// on the repository, in the same way as `metadata_prefix`. If `None`, this defaults to `/`.
+    pub fn targets_prefix(mut self, targets_prefix: Vec<String>) -> Self {
+        self.targets_prefix = Some(targets_prefix);
+        self
+    }
+
+    /// Fetch and store targets at a different base URL than metadata, such as a CDN. The
+    /// `targets_prefix` is relative to this URL. Targets are still only trusted if they match the
+    /// metadata, wherever they are fetched from.
+    pub fn targets_url(mut self, targets_url: Url) -> Self {
+        self.targets_url = Some(targets_url);
+        self
+    }
+
+    /// Look up metadata in a directory named after the major version of the TUF specification this
+    /// library implements, as described in TAP 14. For example, `root.json` would first be fetched
+    /// from `https://tuf.example.com/1/root.json`, and then from
+    /// `https://tuf.example.com/root.json` if it was not found there. This directory comes after
+    /// the `metadata_prefix`.
+    pub fn versioned_metadata(mut self, versioned_metadata: bool) -> Self {
+        self.versioned_metadata = versioned_metadata;
+        self
+    }
+
     /// Set the minimum bytes per second for a read to be considered good.
     pub fn min_bytes_per_second(mut self, min: u32) -> Self {
-        self.min_bytes_per_second = min;
+        self.slow_retrieval.set_min_bytes_per_second(min);
+        self
+    }
+
+    /// Set the `SlowRetrievalPolicy` that downloads of metadata and targets must follow. It is
+    /// checked against the bytes received from the server, before they are decompressed. This
+    /// replaces any earlier `min_bytes_per_second`. Defaults to `SlowRetrievalPolicy::new(4096)`.
+    pub fn slow_retrieval_policy(mut self, policy: SlowRetrievalPolicy) -> Self {
+        self.slow_retrieval = policy;
+        self
+    }
+
+    /// Set how metadata and targets are uploaded to the repository. Defaults to
+    /// `HttpUploadMethod::Put`.
+    pub fn upload_method(mut self, upload_method: HttpUploadMethod) -> Self {
+        self.upload_method = upload_method;
+        self
+    }
+
+    /// Add a header that is sent with every upload to the repository, such as an `Authorization`
+    /// header needed to publish to it. It is not sent when fetching metadata or targets, nor with
+    /// uploads to a `targets_url` on a different origin than the repository's URL.
+    pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.append(name, value);
+        self
+    }
+
+    /// Set how long a single request to fetch metadata or a target may take to connect and receive
+    /// the response headers before it fails with `Error::Timeout`. The download of the body is
+    /// bounded by the `SlowRetrievalPolicy` instead. Defaults to no timeout.
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Set how long fetching metadata or a target may take across all retries, including the time
+    /// spent waiting between them and reading the response body. Requests that are still running
+    /// when it passes fail with `Error::Timeout`, and reads of the body fail with an `io::Error` of
+    /// kind `TimedOut`. Defaults to no timeout.
+    pub fn total_timeout(mut self, timeout: Duration) -> Self {
+        self.total_timeout = Some(timeout);
+        self
+    }
+
+    /// Set how many times a failed request to fetch or upload metadata or a target is retried.
+    /// Requests are retried when they time out, when the connection fails, or when the response
+    /// has one of the `retry_status_codes`. As an upload has to be sent again when it is retried,
+    /// uploads are first copied into a temporary file when this is not 0. Defaults to 0.
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Set the delay between retries. The `n`th retry waits for a random duration between zero
+    /// and `initial * 2^n`, capped at `max`. Defaults to 500 milliseconds and 30 seconds.
+    pub fn retry_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Set the response status codes that cause a request to be retried. Defaults to 408, 429,
+    /// 500, 502, 503, and 504.
+    pub fn retry_status_codes(mut self, status_codes: Vec<StatusCode>) -> Self {
+        self.retry_status_codes = status_codes;
+        self
+    }
+
+    /// Set the encodings the server is asked to send metadata and targets in. Size limits and
+    /// hashes are checked against the decompressed bytes, while the `SlowRetrievalPolicy` is
+    /// checked against the bytes as they are received. Defaults to every compressed encoding
+    /// enabled by cargo features.
+    pub fn content_encodings(mut self, content_encodings: Vec<HttpContentEncoding>) -> Self {
+        self.content_encodings = content_encodings;
         self
     }
 
     /// Build a `HttpRepository`.
-    pub fn build(self) -> HttpRepository<C, D> {
+    pub fn build(self) -> HttpRepository<T, D> {
         let user_agent = match self.user_agent {
             Some(user_agent) => user_agent,
             None => "rust-tuf".into(),
@@ -319,173 +709,880 @@ where
 
         HttpRepository {
             url: self.url,
-            client: self.client,
+            transport: Arc::new(self.transport),
             interchange: self.interchange,
             user_agent: user_agent,
             metadata_prefix: self.metadata_prefix,
-            min_bytes_per_second: self.min_bytes_per_second,
+            targets_prefix: self.targets_prefix,
+            targets_url: self.targets_url,
+            versioned_metadata: self.versioned_metadata,
+            slow_retrieval: self.slow_retrieval,
+            upload_method: self.upload_method,
+            headers: self.headers,
+            request_timeout: self.request_timeout,
+            total_timeout: self.total_timeout,
+            retries: self.retries,
+            initial_backoff: self.initial_backoff,
+            max_backoff: self.max_backoff,
+            retry_status_codes: self.retry_status_codes,
+            content_encodings: self.content_encodings,
+            validators: RwLock::new(HashMap::new()),
+            unverified_validators: RwLock::new(HashMap::new()),
+        }
+    }
+}
+
+/// The ways a `HttpRepository` can upload metadata and targets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HttpUploadMethod {
+    /// `PUT` the file to its URL with the file as the request body.
+    Put,
+    /// `POST` the file to its URL as a `multipart/form-data` body with a single part named `file`.
+    PostMultipart,
+}
+
+/// The encodings a `HttpRepository` can receive metadata and targets in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HttpContentEncoding {
+    /// `identity`, the file as it is, without compression.
+    Identity,
+    /// `gzip`, enabled by the `gzip` feature.
+    #[cfg(feature = "gzip")]
+    Gzip,
+    /// `zstd`, enabled by the `zstd` feature.
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+impl HttpContentEncoding {
+    /// Every compressed encoding enabled by cargo features.
+    fn all() -> Vec<Self> {
+        vec![
+            #[cfg(feature = "gzip")]
+            HttpContentEncoding::Gzip,
+            #[cfg(feature = "zstd")]
+            HttpContentEncoding::Zstd,
+        ]
+    }
+
+    /// The name of the encoding in the `Accept-Encoding` and `Content-Encoding` headers.
+    fn name(&self) -> &'static str {
+        match *self {
+            HttpContentEncoding::Identity => "identity",
+            #[cfg(feature = "gzip")]
+            HttpContentEncoding::Gzip => "gzip",
+            #[cfg(feature = "zstd")]
+            HttpContentEncoding::Zstd => "zstd",
+        }
+    }
+
+    /// The encoding named `name` in a `Content-Encoding` header, if it is enabled.
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "identity" => Some(HttpContentEncoding::Identity),
+            #[cfg(feature = "gzip")]
+            "gzip" | "x-gzip" => Some(HttpContentEncoding::Gzip),
+            #[cfg(feature = "zstd")]
+            "zstd" => Some(HttpContentEncoding::Zstd),
+            _ => None,
+        }
+    }
+
+    /// Wrap `body` to read it decoded.
+    fn decode(&self, body: Box<dyn AsyncRead>) -> Result<Box<dyn AsyncRead>> {
+        match *self {
+            HttpContentEncoding::Identity => Ok(body),
+            #[cfg(feature = "gzip")]
+            HttpContentEncoding::Gzip => Ok(Box::new(DecodingReader::new(
+                body,
+                GzipDecompress::new(),
+            ))),
+            #[cfg(feature = "zstd")]
+            HttpContentEncoding::Zstd => Ok(Box::new(DecodingReader::new(
+                body,
+                ZstdDecompress::new()?,
+            ))),
+        }
+    }
+}
+
+/// Replace the body of `resp` with one that decodes its `Content-Encoding`.
+fn decode_content(mut resp: Response<Box<dyn AsyncRead>>) -> Result<Response<Box<dyn AsyncRead>>> {
+    let encoding = match resp.headers().get(CONTENT_ENCODING) {
+        Some(encoding) => encoding
+            .to_str()
+            .map_err(|_| Error::Opaque(format!("Bad Content-Encoding: {:?}", encoding)))?
+            .trim()
+            .to_ascii_lowercase(),
+        None => return Ok(resp),
+    };
+    let encoding = match HttpContentEncoding::from_name(&encoding) {
+        Some(encoding) => encoding,
+        None => {
+            return Err(Error::Opaque(format!(
+                "Unsupported Content-Encoding: {:?}",
+                encoding
+            )))
         }
+    };
+    if encoding == HttpContentEncoding::Identity {
+        return Ok(resp);
     }
+
+    // The bytes of a partial response are a range of the encoded file, which can not be decoded
+    // on their own or joined with the decoded bytes that are already there.
+    if resp.status() == StatusCode::PARTIAL_CONTENT {
+        return Err(Error::Opaque(format!(
+            "Partial response with Content-Encoding {:?}",
+            encoding.name()
+        )));
+    }
+
+    let _ = resp.headers_mut().remove(CONTENT_ENCODING);
+    let _ = resp.headers_mut().remove(CONTENT_LENGTH);
+    let (parts, body) = resp.into_parts();
+    Ok(Response::from_parts(parts, encoding.decode(body)?))
 }
 
 /// A repository accessible over HTTP.
-pub struct HttpRepository<C, D>
+pub struct HttpRepository<T, D>
 where
-    C: Connect + Sync + 'static,
+    T: HttpTransport,
     D: DataInterchange,
 {
     url: Url,
-    client: Client<C>,
+    transport: Arc<T>,
     user_agent: String,
     metadata_prefix: Option<Vec<String>>,
-    min_bytes_per_second: u32,
+    targets_prefix: Option<Vec<String>>,
+    targets_url: Option<Url>,
+    versioned_metadata: bool,
+    slow_retrieval: SlowRetrievalPolicy,
+    upload_method: HttpUploadMethod,
+    headers: HeaderMap,
+    request_timeout: Option<Duration>,
+    total_timeout: Option<Duration>,
+    retries: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    retry_status_codes: Vec<StatusCode>,
+    content_encodings: Vec<HttpContentEncoding>,
+    validators: RwLock<HashMap<String, Validators>>,
+    unverified_validators: RwLock<HashMap<(MetadataPath, MetadataVersion), (String, Validators)>>,
     interchange: PhantomData<D>,
 }
 
-impl<C, D> HttpRepository<C, D>
+/// The validators a server sent with a file, used to ask it whether the file has changed.
+struct Validators {
+    etag: Option<HeaderValue>,
+    last_modified: Option<HeaderValue>,
+}
+
+/// Fails reads with `io::ErrorKind::TimedOut` once a deadline has passed, even if the reader it
+/// wraps never becomes ready.
+struct DeadlineReader {
+    inner: Box<dyn AsyncRead>,
+    delay: Box<dyn Future<Output = ()> + Unpin>,
+}
+
+impl DeadlineReader {
+    fn new(inner: Box<dyn AsyncRead>, deadline: Instant) -> Self {
+        let now = Instant::now();
+        let remaining = if deadline > now {
+            deadline - now
+        } else {
+            Duration::from_secs(0)
+        };
+        DeadlineReader {
+            inner,
+            delay: Box::new(util::sleep(remaining)),
+        }
+    }
+}
+
+impl AsyncRead for DeadlineReader {
+    fn poll_read(&mut self, lw: &LocalWaker, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        if let Poll::Ready(()) = Pin::new(&mut *self.delay).poll(lw) {
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "The total timeout passed while reading the response.",
+            )));
+        }
+        self.inner.poll_read(lw, buf)
+    }
+}
+
+/// Reads everything from `first`, and then everything from `second`.
+struct Chain<A, B> {
+    first: A,
+    second: B,
+    first_done: bool,
+}
+
+impl<A, B> Chain<A, B> {
+    fn new(first: A, second: B) -> Self {
+        Chain {
+            first,
+            second,
+            first_done: false,
+        }
+    }
+}
+
+impl<A, B> AsyncRead for Chain<A, B>
 where
-    C: Connect + Sync + 'static,
+    A: AsyncRead,
+    B: AsyncRead,
+{
+    fn poll_read(&mut self, lw: &LocalWaker, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        if !self.first_done {
+            match try_ready!(self.first.poll_read(lw, buf)) {
+                0 if !buf.is_empty() => self.first_done = true,
+                read_bytes => return Poll::Ready(Ok(read_bytes)),
+            }
+        }
+        self.second.poll_read(lw, buf)
+    }
+}
+
+/// Why a single HTTP request failed.
+enum RequestFailure {
+    /// The request may succeed if it is tried again.
+    Transient(Error),
+    /// Trying the request again would fail the same way.
+    Permanent(Error),
+}
+
+impl<T, D> HttpRepository<T, D>
+where
+    T: HttpTransport,
     D: DataInterchange,
 {
-    async fn get<'a>(
-        &'a self,
-        prefix: &'a Option<Vec<String>>,
-        components: &'a [String],
-    ) -> Result<Response<Body>> {
-        let mut url = self.url.clone();
+    fn metadata_uri(&self, components: &[String]) -> Result<Uri> {
+        Self::uri(&self.url, &self.metadata_prefix, components)
+    }
+
+    fn target_uri(&self, components: &[String]) -> Result<Uri> {
+        let base = self.targets_url.as_ref().unwrap_or(&self.url);
+        Self::uri(base, &self.targets_prefix, components)
+    }
+
+    fn uri(base: &Url, prefix: &Option<Vec<String>>, components: &[String]) -> Result<Uri> {
+        let mut url = base.clone();
         {
             let mut segments = url.path_segments_mut().map_err(|_| {
-                Error::IllegalArgument(format!("URL was 'cannot-be-a-base': {:?}", self.url))
+                Error::IllegalArgument(format!("URL was 'cannot-be-a-base': {:?}", base))
             })?;
+            // Drop the empty segment left by a trailing slash, so that `http://tuf.example.com/a/`
+            // and `http://tuf.example.com/a` both resolve to `http://tuf.example.com/a/root.json`
+            // and not to `http://tuf.example.com/a//root.json`.
+            let _ = segments.pop_if_empty();
             if let Some(ref prefix) = prefix {
                 segments.extend(prefix);
             }
             segments.extend(components);
         }
 
-        let uri: Uri = url.into_string().parse().map_err(|_| {
-            Error::IllegalArgument(format!("URL was 'cannot-be-a-base': {:?}", self.url))
-        })?;
+        url.into_string()
+            .parse()
+            .map_err(|_| Error::IllegalArgument(format!("URL was 'cannot-be-a-base': {:?}", base)))
+    }
 
-        let req = Request::builder()
-            .uri(uri)
-            .header("User-Agent", &*self.user_agent)
-            .body(Body::default())?;
+    /// Whether `uri` is on the same origin as the repository's URL, and so may be sent the headers
+    /// added with `HttpRepositoryBuilder::header`.
+    fn is_repository_origin(&self, uri: &Uri) -> bool {
+        Url::parse(&uri.to_string())
+            .map(|url| url.origin() == self.url.origin())
+            .unwrap_or(false)
+    }
 
-        let resp = await!(self.client.request(req).compat())?;
-        let status = resp.status();
+    async fn get<'a>(&'a self, uri: &'a Uri) -> Result<Response<Box<dyn AsyncRead>>> {
+        await!(self.get_with_headers(uri, HeaderMap::new()))
+    }
 
-        if !status.is_success() {
-            if status == StatusCode::NOT_FOUND {
-                Err(Error::NotFound)
-            } else {
-                Err(Error::Opaque(format!(
-                    "Error getting {:?}: {:?}",
-                    self.url, resp
-                )))
+    /// Fetch metadata, asking the server to respond with `304 Not Modified` instead if it has
+    /// not changed since the last time it was fetched and `conditional` is set. Returns the URI
+    /// the metadata was fetched from along with the response.
+    async fn get_metadata<'a>(
+        &'a self,
+        components: &'a [String],
+        conditional: bool,
+    ) -> Result<(String, Response<Box<dyn AsyncRead>>)> {
+        let uri = self.metadata_uri(components)?;
+
+        let mut headers = HeaderMap::new();
+        if conditional {
+            if let Some(validators) = self.validators.read().unwrap().get(&uri.to_string()) {
+                if let Some(ref etag) = validators.etag {
+                    let _ = headers.insert(IF_NONE_MATCH, etag.clone());
+                }
+                if let Some(ref last_modified) = validators.last_modified {
+                    let _ = headers.insert(IF_MODIFIED_SINCE, last_modified.clone());
+                }
             }
-        } else {
-            Ok(resp)
         }
-    }
-}
 
-impl<C, D> Repository<D> for HttpRepository<C, D>
-where
-    C: Connect + Sync + 'static,
-    D: DataInterchange,
-{
-    /// This always returns `Err` as storing over HTTP is not yet supported.
-    fn store_metadata<'a, M>(
-        &'a self,
-        _: &'a MetadataPath,
-        _: &'a MetadataVersion,
-        _: &'a SignedMetadata<D, M>,
-    ) -> TufFuture<'a, Result<()>>
-    where
-        M: Metadata + 'static,
-    {
-        Box::pin(
-            async {
-                Err(Error::Opaque(
-                    "Http repo store metadata not implemented".to_string(),
-                ))
-            },
-        )
+        let resp = await!(self.get_with_headers(&uri, headers))?;
+        Ok((uri.to_string(), resp))
     }
 
-    fn fetch_metadata<'a, M>(
+    async fn fetch_metadata_from_server<'a, M>(
         &'a self,
         meta_path: &'a MetadataPath,
         version: &'a MetadataVersion,
         max_size: &'a Option<usize>,
-        hash_data: Option<(&'static HashAlgorithm, HashValue)>,
-    ) -> TufFuture<'a, Result<SignedMetadata<D, M>>>
+        hashes: Option<&'a HashMap<HashAlgorithm, HashValue>>,
+        conditional: bool,
+    ) -> Result<Option<SignedMetadata<D, M>>>
     where
         M: Metadata + 'static,
     {
-        Box::pin(
-            async move {
-                Self::check::<M>(meta_path)?;
+        Self::check::<M>(meta_path)?;
+
+        let components = meta_path.components::<D>(&version);
+        let (uri, resp) = if self.versioned_metadata {
+            let mut versioned = vec![spec_version_directory()?];
+            versioned.extend(components.iter().cloned());
+            match await!(self.get_metadata(&versioned, conditional)) {
+                Err(Error::NotFound) => await!(self.get_metadata(&components, conditional))?,
+                resp => resp?,
+            }
+        } else {
+            await!(self.get_metadata(&components, conditional))?
+        };
 
-                let components = meta_path.components::<D>(&version);
-                let resp = await!(self.get(&self.metadata_prefix, &components))?;
+        if resp.status() == StatusCode::NOT_MODIFIED {
+            if conditional {
+                return Ok(None);
+            }
+            return Err(Error::Opaque(format!(
+                "Unconditional request to {} was answered with {}",
+                uri,
+                resp.status()
+            )));
+        }
 
-                let stream = resp
-                    .into_body()
-                    .compat()
-                    .map_err(|err| io::Error::new(io::ErrorKind::Other, err));
+        // The validators are only used once the client has verified the new metadata, so that
+        // metadata that fails to verify is fetched again instead of being reported as unchanged.
+        let _ = self.validators.write().unwrap().remove(&uri);
+        let validators = Validators {
+            etag: resp.headers().get(ETAG).cloned(),
+            last_modified: resp.headers().get(LAST_MODIFIED).cloned(),
+        };
 
-                let mut reader = SafeReader::new(
-                    stream.into_async_read(),
-                    max_size.unwrap_or(::std::usize::MAX) as u64,
-                    self.min_bytes_per_second,
-                    hash_data,
-                )?;
+        let read = resp.into_body();
+        let limit = max_size.unwrap_or(::std::usize::MAX) as u64;
+        let mut reader = match hashes {
+            Some(hashes) => {
+                SafeReader::new_with_hashes(read, limit, SlowRetrievalPolicy::new(0), hashes)?
+            }
+            None => SafeReader::new(read, limit, SlowRetrievalPolicy::new(0), None)?,
+        };
 
-                let mut buf = Vec::new();
-                await!(reader.read_to_end(&mut buf))?;
+        let mut buf = Vec::new();
+        await!(reader.read_to_end(&mut buf))?;
 
-                Ok(D::from_slice(&buf)?)
-            },
-        )
-    }
+        let metadata = SignedMetadata::from_slice(&buf)?;
 
-    /// This always returns `Err` as storing over HTTP is not yet supported.
-    fn store_target<'a, R>(&'a self, _: R, _: &'a TargetPath) -> TufFuture<'a, Result<()>>
-    where
-        R: AsyncRead + 'a,
-    {
-        Box::pin(async { Err(Error::Opaque("Http repo store not implemented".to_string())) })
+        if conditional && (validators.etag.is_some() || validators.last_modified.is_some()) {
+            let _ = self
+                .unverified_validators
+                .write()
+                .unwrap()
+                .insert((meta_path.clone(), version.clone()), (uri, validators));
+        }
+
+        Ok(Some(metadata))
     }
 
-    fn fetch_target<'a>(
+    /// Fetch a file, sending `headers` along with the request.
+    async fn get_with_headers<'a>(
         &'a self,
-        target_path: &'a TargetPath,
-        target_description: &'a TargetDescription,
-    ) -> TufFuture<'a, Result<Box<dyn AsyncRead>>> {
-        Box::pin(
-            async move {
-                let (alg, value) = crypto::hash_preference(target_description.hashes())?;
-                let components = target_path.components();
-                let resp = await!(self.get(&None, &components))?;
+        uri: &'a Uri,
+        headers: HeaderMap,
+    ) -> Result<Response<Box<dyn AsyncRead>>> {
+        let deadline = self.total_timeout.map(|timeout| Instant::now() + timeout);
+
+        let mut attempt = 0;
+        loop {
+            let err = match await!(self.try_get(uri, &headers, deadline)) {
+                Ok(resp) => {
+                    return Ok(match deadline {
+                        Some(deadline) => resp.map(|body| {
+                            Box::new(DeadlineReader::new(body, deadline)) as Box<dyn AsyncRead>
+                        }),
+                        None => resp,
+                    });
+                }
+                Err(RequestFailure::Permanent(err)) => return Err(err),
+                Err(RequestFailure::Transient(err)) => err,
+            };
 
-                let stream = resp
-                    .into_body()
-                    .compat()
-                    .map_err(|err| io::Error::new(io::ErrorKind::Other, err));
+            if attempt >= self.retries {
+                return Err(err);
+            }
 
-                let reader = SafeReader::new(
-                    stream.into_async_read(),
-                    target_description.size(),
-                    self.min_bytes_per_second,
-                    Some((alg, value.clone())),
+            let backoff = self.backoff(attempt);
+            if let Some(deadline) = deadline {
+                if Instant::now() + backoff >= deadline {
+                    return Err(Error::Timeout);
+                }
+            }
+
+            debug!("Retrying {} in {:?} after error: {}", uri, backoff, err);
+            await!(util::sleep(backoff));
+            attempt += 1;
+        }
+    }
+
+    async fn try_get<'a>(
+        &'a self,
+        uri: &'a Uri,
+        headers: &'a HeaderMap,
+        deadline: Option<Instant>,
+    ) -> ::std::result::Result<Response<Box<dyn AsyncRead>>, RequestFailure> {
+        let mut req = Request::builder()
+            .uri(uri.clone())
+            .header("User-Agent", &*self.user_agent)
+            .body(None)
+            .map_err(|err| RequestFailure::Permanent(err.into()))?;
+        req.headers_mut().extend(headers.clone());
+
+        if !self.content_encodings.is_empty() && !req.headers().contains_key(ACCEPT_ENCODING) {
+            // Ranges of an encoded file can't be decoded, so a resumed download must not be
+            // encoded.
+            let accept_encoding = if req.headers().contains_key(RANGE) {
+                HeaderValue::from_static("identity")
+            } else {
+                let names = self
+                    .content_encodings
+                    .iter()
+                    .map(|encoding| encoding.name())
+                    .collect::<Vec<_>>();
+                HeaderValue::from_str(&names.join(", "))
+                    .map_err(|e| RequestFailure::Permanent(Error::Opaque(format!("{:?}", e))))?
+            };
+            let _ = req.headers_mut().insert(ACCEPT_ENCODING, accept_encoding);
+        }
+
+        let mut timeout = self.request_timeout;
+        if let Some(deadline) = deadline {
+            let now = Instant::now();
+            let remaining = if deadline > now {
+                deadline - now
+            } else {
+                Duration::from_secs(0)
+            };
+            timeout = Some(timeout.map_or(remaining, |timeout| timeout.min(remaining)));
+        }
+
+        let request = self.transport.request(req);
+        let resp = match timeout {
+            Some(timeout) => match await!(util::timeout(request, timeout)) {
+                Some(resp) => resp,
+                None => return Err(RequestFailure::Transient(Error::Timeout)),
+            },
+            None => await!(request),
+        };
+        let resp = resp.map_err(transport_failure)?;
+
+        let status = resp.status();
+        if status.is_success() || status == StatusCode::NOT_MODIFIED {
+            // The download rate is measured on the bytes that are received, before they are
+            // decoded, so that a well compressed body can't hide how slowly it is sent.
+            let (parts, body) = resp.into_parts();
+            let body = SafeReader::new(body, ::std::u64::MAX, self.slow_retrieval.clone(), None)
+                .map_err(RequestFailure::Permanent)?;
+            decode_content(Response::from_parts(parts, Box::new(body)))
+                .map_err(RequestFailure::Permanent)
+        } else if status == StatusCode::NOT_FOUND {
+            Err(RequestFailure::Permanent(Error::NotFound))
+        } else {
+            let err = Error::Opaque(format!("Error getting {}: {}", uri, status));
+            Err(self.status_failure(status, err))
+        }
+    }
+
+    /// Whether a response with `status` should be retried.
+    fn status_failure(&self, status: StatusCode, err: Error) -> RequestFailure {
+        if self.retry_status_codes.contains(&status) {
+            RequestFailure::Transient(err)
+        } else {
+            RequestFailure::Permanent(err)
+        }
+    }
+
+    /// How long to wait before the retry following the given (zero-indexed) attempt, using
+    /// exponential backoff with full jitter.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let ceiling = 2u32
+            .checked_pow(attempt)
+            .and_then(|factor| self.initial_backoff.checked_mul(factor))
+            .map_or(self.max_backoff, |backoff| backoff.min(self.max_backoff));
+        let ceiling_millis = ceiling.as_secs() * 1000 + u64::from(ceiling.subsec_millis());
+        Duration::from_millis(rand::thread_rng().gen_range(0, ceiling_millis + 1))
+    }
+
+    /// Upload the contents of `read` as `file_name`. Without retries it is streamed into the
+    /// request body as it is read.
+    async fn upload<'a, R>(&'a self, uri: Uri, file_name: &'a str, mut read: R) -> Result<()>
+    where
+        R: AsyncRead + 'a,
+    {
+        if self.retries == 0 {
+            return match await!(self.try_upload(&uri, file_name, Box::new(read))) {
+                Ok(()) => Ok(()),
+                Err(RequestFailure::Transient(err)) | Err(RequestFailure::Permanent(err)) => {
+                    Err(err)
+                }
+            };
+        }
+
+        let mut temp_file = tempfile::tempfile()?;
+        {
+            let mut write = AllowStdIo::new(&mut temp_file);
+            await!(read.copy_into(&mut write))?;
+        }
+
+        let mut attempt = 0;
+        loop {
+            let _ = temp_file.seek(SeekFrom::Start(0))?;
+            let body = AllowStdIo::new(temp_file.try_clone()?);
+            let err = match await!(self.try_upload(&uri, file_name, Box::new(body))) {
+                Ok(()) => return Ok(()),
+                Err(RequestFailure::Permanent(err)) => return Err(err),
+                Err(RequestFailure::Transient(err)) => err,
+            };
+
+            if attempt >= self.retries {
+                return Err(err);
+            }
+
+            let backoff = self.backoff(attempt);
+            debug!(
+                "Retrying upload to {} in {:?} after error: {}",
+                uri, backoff, err
+            );
+            await!(util::sleep(backoff));
+            attempt += 1;
+        }
+    }
+
+    async fn try_upload<'a>(
+        &'a self,
+        uri: &'a Uri,
+        file_name: &'a str,
+        read: Box<dyn AsyncRead + 'a>,
+    ) -> ::std::result::Result<(), RequestFailure> {
+        let mut req = Request::builder()
+            .uri(uri.clone())
+            .header("User-Agent", &*self.user_agent)
+            .body(None)
+            .map_err(|err| RequestFailure::Permanent(err.into()))?;
+
+        let (prologue, epilogue) = match self.upload_method {
+            HttpUploadMethod::Put => {
+                *req.method_mut() = Method::PUT;
+                (String::new(), String::new())
+            }
+            HttpUploadMethod::PostMultipart => {
+                let boundary = rand::thread_rng()
+                    .sample_iter(&Alphanumeric)
+                    .take(32)
+                    .collect::<String>();
+                *req.method_mut() = Method::POST;
+                req.headers_mut().insert(
+                    "Content-Type",
+                    HeaderValue::from_str(&format!("multipart/form-data; boundary={}", boundary))
+                        .map_err(|e| RequestFailure::Permanent(Error::Opaque(format!("{:?}", e))))?,
+                );
+                (
+                    format!(
+                        "--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\n\
+                         Content-Type: application/octet-stream\r\n\r\n",
+                        boundary,
+                        escape_multipart_file_name(file_name)
+                    ),
+                    format!("\r\n--{}--\r\n", boundary),
+                )
+            }
+        };
+        if self.is_repository_origin(uri) {
+            req.headers_mut().extend(self.headers.clone());
+        }
+
+        let body = Chain::new(
+            Chain::new(Cursor::new(prologue), read),
+            Cursor::new(epilogue),
+        );
+        *req.body_mut() = Some(Box::new(body) as Box<dyn AsyncRead + 'a>);
+
+        let resp = await!(self.transport.request(req)).map_err(transport_failure)?;
+
+        let status = resp.status();
+        if status.is_success() {
+            Ok(())
+        } else {
+            let err = Error::Opaque(format!("Error uploading to {}: {}", uri, status));
+            Err(self.status_failure(status, err))
+        }
+    }
+}
+
+/// Whether a request that failed with the transport error `err` should be retried.
+fn transport_failure(err: Error) -> RequestFailure {
+    match err {
+        Error::Transport {
+            retryable: false, ..
+        } => RequestFailure::Permanent(err),
+        _ => RequestFailure::Transient(err),
+    }
+}
+
+/// Escape a file name for the `filename` parameter of a `Content-Disposition` header in a
+/// `multipart/form-data` body, in the same way browsers do, so that it can not end the parameter
+/// or the header.
+fn escape_multipart_file_name(file_name: &str) -> String {
+    file_name
+        .replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+impl<T, D> Repository<D> for HttpRepository<T, D>
+where
+    T: HttpTransport,
+    D: DataInterchange,
+{
+    fn store_metadata<'a, M>(
+        &'a self,
+        meta_path: &'a MetadataPath,
+        version: &'a MetadataVersion,
+        metadata: &'a SignedMetadata<D, M>,
+    ) -> TufFuture<'a, Result<()>>
+    where
+        M: Metadata + 'static,
+    {
+        Box::pin(
+            async move {
+                Self::check::<M>(meta_path)?;
+
+                let mut components = Vec::new();
+                if self.versioned_metadata {
+                    components.push(spec_version_directory()?);
+                }
+                components.extend(meta_path.components::<D>(version));
+
+                let mut buf = Vec::new();
+                D::to_writer(&mut buf, metadata)?;
+
+                let uri = self.metadata_uri(&components)?;
+                let file_name = components.last().map(|c| c.as_str()).unwrap_or("");
+                await!(self.upload(uri, file_name, buf.as_slice()))
+            },
+        )
+    }
+
+    fn fetch_metadata<'a, M>(
+        &'a self,
+        meta_path: &'a MetadataPath,
+        version: &'a MetadataVersion,
+        max_size: &'a Option<usize>,
+        hashes: Option<&'a HashMap<HashAlgorithm, HashValue>>,
+    ) -> TufFuture<'a, Result<SignedMetadata<D, M>>>
+    where
+        M: Metadata + 'static,
+    {
+        Box::pin(
+            async move {
+                let metadata = await!(self.fetch_metadata_from_server(
+                    meta_path,
+                    version,
+                    max_size,
+                    hashes,
+                    false,
+                ))?;
+                metadata.ok_or_else(|| {
+                    Error::Programming(
+                        "Unconditional metadata request reported the metadata as unchanged".into(),
+                    )
+                })
+            },
+        )
+    }
+
+    /// Sends `If-None-Match` and `If-Modified-Since` with the `ETag` and `Last-Modified` headers
+    /// the server sent the last time this metadata was fetched and then verified, and returns
+    /// `Ok(None)` if the server responds with `304 Not Modified`.
+    fn fetch_metadata_if_changed<'a, M>(
+        &'a self,
+        meta_path: &'a MetadataPath,
+        version: &'a MetadataVersion,
+        max_size: &'a Option<usize>,
+        hashes: Option<&'a HashMap<HashAlgorithm, HashValue>>,
+    ) -> TufFuture<'a, Result<Option<SignedMetadata<D, M>>>>
+    where
+        M: Metadata + 'static,
+    {
+        Box::pin(self.fetch_metadata_from_server(meta_path, version, max_size, hashes, true))
+    }
+
+    fn metadata_verified(
+        &self,
+        meta_path: &MetadataPath,
+        version: &MetadataVersion,
+        verified: bool,
+    ) {
+        let unverified = self
+            .unverified_validators
+            .write()
+            .unwrap()
+            .remove(&(meta_path.clone(), version.clone()));
+        if let Some((uri, validators)) = unverified {
+            if verified {
+                let _ = self.validators.write().unwrap().insert(uri, validators);
+            }
+        }
+    }
+
+    fn store_target<'a, R>(
+        &'a self,
+        read: R,
+        target_path: &'a TargetPath,
+    ) -> TufFuture<'a, Result<()>>
+    where
+        R: AsyncRead + 'a,
+    {
+        Box::pin(
+            async move {
+                let components = target_path.components();
+                let uri = self.target_uri(&components)?;
+                let file_name = components.last().map(|c| c.as_str()).unwrap_or("");
+                await!(self.upload(uri, file_name, read))
+            },
+        )
+    }
+
+    fn fetch_target<'a>(
+        &'a self,
+        target_path: &'a TargetPath,
+        target_description: &'a TargetDescription,
+    ) -> TufFuture<'a, Result<Box<dyn AsyncRead>>> {
+        Box::pin(
+            async move {
+                let uri = self.target_uri(&target_path.components())?;
+                let resp = await!(self.get(&uri))?;
+
+                let reader = SafeReader::new_with_hashes(
+                    resp.into_body(),
+                    target_description.size(),
+                    SlowRetrievalPolicy::new(0),
+                    target_description.hashes(),
                 )?;
 
                 Ok(Box::new(reader) as Box<dyn AsyncRead>)
             },
         )
     }
+
+    fn fetch_target_resume<'a, P>(
+        &'a self,
+        target_path: &'a TargetPath,
+        target_description: &'a TargetDescription,
+        partial: P,
+        partial_len: u64,
+    ) -> TufFuture<'a, Result<Box<dyn AsyncRead>>>
+    where
+        P: AsyncRead + 'static,
+    {
+        Box::pin(
+            async move {
+                if partial_len == 0 || partial_len > target_description.size() {
+                    return await!(self.fetch_target(target_path, target_description));
+                }
+
+                let read: Box<dyn AsyncRead> = if partial_len == target_description.size() {
+                    Box::new(partial)
+                } else {
+                    let uri = self.target_uri(&target_path.components())?;
+                    let mut headers = HeaderMap::new();
+                    let _ = headers.insert(
+                        RANGE,
+                        HeaderValue::from_str(&format!("bytes={}-", partial_len))
+                            .map_err(|e| Error::Opaque(format!("{:?}", e)))?,
+                    );
+                    let resp = await!(self.get_with_headers(&uri, headers))?;
+                    let resumed = resp.status() == StatusCode::PARTIAL_CONTENT;
+
+                    if resumed {
+                        let expected = format!("bytes {}-", partial_len);
+                        let content_range = resp
+                            .headers()
+                            .get("Content-Range")
+                            .and_then(|v| v.to_str().ok())
+                            .unwrap_or("");
+                        if !content_range.starts_with(&expected) {
+                            return Err(Error::Opaque(format!(
+                                "Asked {} for {}, but it sent {:?}",
+                                uri, expected, content_range
+                            )));
+                        }
+                    }
+
+                    let rest = resp.into_body();
+
+                    if resumed {
+                        Box::new(Chain::new(partial, rest))
+                    } else {
+                        Box::new(rest)
+                    }
+                };
+
+                let reader = SafeReader::new_with_hashes(
+                    read,
+                    target_description.size(),
+                    SlowRetrievalPolicy::new(0),
+                    target_description.hashes(),
+                )?;
+
+                Ok(Box::new(reader) as Box<dyn AsyncRead>)
+            },
+        )
+    }
+
+    /// The mirror is fetched from with the same transport and settings as this repository, except
+    /// that the headers added with `HttpRepositoryBuilder::header` are not sent to it.
+    fn mirror(&self, mirror: &Mirror) -> Option<Self> {
+        let path_components = |path: &str| {
+            path.split('/')
+                .filter(|c| !c.is_empty())
+                .map(|c| c.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        Some(HttpRepository {
+            url: mirror.url_base().clone(),
+            transport: self.transport.clone(),
+            user_agent: self.user_agent.clone(),
+            metadata_prefix: Some(path_components(mirror.meta_path())),
+            targets_prefix: Some(path_components(mirror.targets_path())),
+            targets_url: None,
+            versioned_metadata: self.versioned_metadata,
+            slow_retrieval: self.slow_retrieval.clone(),
+            upload_method: self.upload_method,
+            headers: HeaderMap::new(),
+            request_timeout: self.request_timeout,
+            total_timeout: self.total_timeout,
+            retries: self.retries,
+            initial_backoff: self.initial_backoff,
+            max_backoff: self.max_backoff,
+            retry_status_codes: self.retry_status_codes.clone(),
+            content_encodings: self.content_encodings.clone(),
+            validators: RwLock::new(HashMap::new()),
+            unverified_validators: RwLock::new(HashMap::new()),
+            interchange: PhantomData,
+        })
+    }
 }
 
 type ArcHashMap<K, V> = Arc<RwLock<HashMap<K, V>>>;
@@ -553,7 +1650,7 @@ where
         meta_path: &'a MetadataPath,
         version: &'a MetadataVersion,
         max_size: &'a Option<usize>,
-        hash_data: Option<(&'static HashAlgorithm, HashValue)>,
+        hashes: Option<&'a HashMap<HashAlgorithm, HashValue>>,
     ) -> TufFuture<'a, Result<SignedMetadata<D, M>>>
     where
         M: Metadata + 'static,
@@ -565,17 +1662,23 @@ where
                 let metadata = self.metadata.read().unwrap();
                 match metadata.get(&(meta_path.clone(), version.clone())) {
                     Some(bytes) => {
-                        let mut reader = SafeReader::new(
-                            &**bytes,
-                            max_size.unwrap_or(::std::usize::MAX) as u64,
-                            0,
-                            hash_data,
-                        )?;
+                        let limit = max_size.unwrap_or(::std::usize::MAX) as u64;
+                        let mut reader = match hashes {
+                            Some(hashes) => SafeReader::new_with_hashes(
+                                &**bytes,
+                                limit,
+                                SlowRetrievalPolicy::new(0),
+                                hashes,
+                            )?,
+                            None => {
+                                SafeReader::new(&**bytes, limit, SlowRetrievalPolicy::new(0), None)?
+                            }
+                        };
 
                         let mut buf = Vec::with_capacity(max_size.unwrap_or(0));
                         await!(reader.read_to_end(&mut buf))?;
 
-                        D::from_slice(&buf)
+                        SignedMetadata::from_slice(&buf)
                     }
                     None => Err(Error::NotFound),
                 }
@@ -614,13 +1717,11 @@ where
                 match targets.get(target_path) {
                     Some(bytes) => {
                         let cur = Cursor::new(bytes.clone());
-                        let (alg, value) = crypto::hash_preference(target_description.hashes())?;
-
-                        let reader: Box<dyn AsyncRead> = Box::new(SafeReader::new(
+                        let reader: Box<dyn AsyncRead> = Box::new(SafeReader::new_with_hashes(
                             cur,
                             target_description.size(),
-                            0,
-                            Some((alg, value.clone())),
+                            SlowRetrievalPolicy::new(0),
+                            target_description.hashes(),
                         )?);
 
                         Ok(reader)
@@ -635,10 +1736,77 @@ where
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::crypto::{PrivateKey, SignatureScheme};
     use crate::interchange::Json;
+    use crate::metadata::{Role, RootMetadata, RootMetadataBuilder};
     use futures::executor::block_on;
+    use futures::future;
     use futures::io::AsyncReadExt;
+    use http::request::Parts;
+    #[cfg(feature = "hyper")]
+    use hyper::rt::{Future as _, Stream as _};
+    #[cfg(feature = "hyper")]
+    use hyper::service::service_fn;
+    #[cfg(feature = "hyper")]
+    use hyper::Server;
+    #[cfg(feature = "hyper")]
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
     use tempfile;
+    #[cfg(feature = "hyper")]
+    use tokio::runtime::current_thread::Runtime;
+
+    /// A transport that answers every request with `respond` instead of going over the network.
+    struct MockTransport<F>
+    where
+        F: Fn(Parts, Vec<u8>) -> Response<Vec<u8>>,
+    {
+        respond: F,
+    }
+
+    impl<F> HttpTransport for MockTransport<F>
+    where
+        F: Fn(Parts, Vec<u8>) -> Response<Vec<u8>>,
+    {
+        fn request<'a>(
+            &'a self,
+            request: Request<Option<Box<dyn AsyncRead + 'a>>>,
+        ) -> TufFuture<'a, Result<Response<Box<dyn AsyncRead>>>> {
+            Box::pin(
+                async move {
+                    let (parts, read) = request.into_parts();
+                    let mut body = Vec::new();
+                    if let Some(mut read) = read {
+                        await!(read.read_to_end(&mut body))?;
+                    }
+                    let resp = (self.respond)(parts, body);
+                    Ok(resp.map(|body| Box::new(Cursor::new(body)) as Box<dyn AsyncRead>))
+                },
+            )
+        }
+    }
+
+    /// Start a server that answers every request with `respond`.
+    #[cfg(feature = "hyper")]
+    fn test_server<F>(runtime: &mut Runtime, respond: F) -> Url
+    where
+        F: Fn(Parts, Vec<u8>) -> Response<Body> + Send + Sync + 'static,
+    {
+        let respond = Arc::new(respond);
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(move || {
+            let respond = respond.clone();
+            service_fn(move |req: Request<Body>| {
+                let respond = respond.clone();
+                let (parts, body) = req.into_parts();
+                body.concat2().map(move |body| respond(parts, body.to_vec()))
+            })
+        });
+
+        let url = Url::parse(&format!("http://{}/", server.local_addr())).unwrap();
+        runtime.spawn(server.map_err(|err| panic!("test server failed: {}", err)));
+        url
+    }
 
     #[test]
     fn ephemeral_repo_targets() {
@@ -665,6 +1833,38 @@ mod test {
         )
     }
 
+    #[test]
+    fn ephemeral_repo_checks_every_hash() {
+        block_on(
+            async {
+                let repo = EphemeralRepository::<Json>::new();
+
+                let data: &[u8] = b"like tears in the rain";
+                let path = TargetPath::new("batty".into()).unwrap();
+                await!(repo.store_target(data, &path)).unwrap();
+
+                let target_description = TargetDescription::from_reader(
+                    data,
+                    &[HashAlgorithm::Sha512, HashAlgorithm::Blake2b],
+                )
+                .unwrap();
+                let mut read = await!(repo.fetch_target(&path, &target_description)).unwrap();
+                let mut buf = Vec::new();
+                await!(read.read_to_end(&mut buf)).unwrap();
+                assert_eq!(buf.as_slice(), data);
+
+                // SHA-512 is preferred, but the wrong SHA-256 is still checked
+                let mut hashes = target_description.hashes().clone();
+                let _ = hashes.insert(HashAlgorithm::Sha256, HashValue::new(vec![0; 32]));
+                let target_description = TargetDescription::new(data.len() as u64, hashes).unwrap();
+
+                let mut read = await!(repo.fetch_target(&path, &target_description)).unwrap();
+                let mut buf = Vec::new();
+                assert!(await!(read.read_to_end(&mut buf)).is_err());
+            },
+        )
+    }
+
     #[test]
     fn file_system_repo_targets() {
         block_on(
@@ -712,4 +1912,986 @@ mod test {
             },
         )
     }
+
+    #[test]
+    fn file_system_repo_resume() {
+        block_on(
+            async {
+                let temp_dir = tempfile::Builder::new()
+                    .prefix("rust-tuf")
+                    .tempdir()
+                    .unwrap();
+                let repo =
+                    FileSystemRepository::<Json>::new(temp_dir.path().to_path_buf()).unwrap();
+
+                let data: &[u8] = b"like tears in the rain";
+                let target_description =
+                    TargetDescription::from_reader(data, &[HashAlgorithm::Sha256]).unwrap();
+                let path = TargetPath::new("batty".into()).unwrap();
+                await!(repo.store_target(data, &path)).unwrap();
+
+                // the partial bytes come first and the rest is read from where they end
+                {
+                    let partial: &[u8] = b"like tears";
+                    let mut read = await!(repo.fetch_target_resume(
+                        &path,
+                        &target_description,
+                        partial,
+                        partial.len() as u64,
+                    ))
+                    .unwrap();
+                    let mut buf = Vec::new();
+                    await!(read.read_to_end(&mut buf)).unwrap();
+                    assert_eq!(buf.as_slice(), data);
+                }
+
+                // the resumed bytes are verified along with the rest
+                let partial: &[u8] = b"like fears";
+                let mut read = await!(repo.fetch_target_resume(
+                    &path,
+                    &target_description,
+                    partial,
+                    partial.len() as u64,
+                ))
+                .unwrap();
+                let mut buf = Vec::new();
+                assert!(await!(read.read_to_end(&mut buf)).is_err());
+            },
+        )
+    }
+
+    #[test]
+    fn file_system_repo_versioned_metadata() {
+        block_on(
+            async {
+                let temp_dir = tempfile::Builder::new()
+                    .prefix("rust-tuf")
+                    .tempdir()
+                    .unwrap();
+                let repo =
+                    FileSystemRepository::<Json>::new(temp_dir.path().to_path_buf()).unwrap();
+                let versioned_repo =
+                    FileSystemRepository::<Json>::new(temp_dir.path().to_path_buf())
+                        .unwrap()
+                        .versioned_metadata(true);
+
+                let key = PrivateKey::from_pkcs8(
+                    include_bytes!("../tests/ed25519/ed25519-1.pk8.der"),
+                    SignatureScheme::Ed25519,
+                )
+                .unwrap();
+                let root = await!(RootMetadataBuilder::new()
+                    .root_key(key.public().clone())
+                    .snapshot_key(key.public().clone())
+                    .targets_key(key.public().clone())
+                    .timestamp_key(key.public().clone())
+                    .signed::<Json>(&key))
+                .unwrap();
+                let path = MetadataPath::from_role(&Role::Root);
+                let version = MetadataVersion::None;
+
+                // metadata outside of the versioned directory is still found
+                await!(repo.store_metadata(&path, &version, &root)).unwrap();
+                let fetched: SignedMetadata<Json, RootMetadata> =
+                    await!(versioned_repo.fetch_metadata(&path, &version, &None, None)).unwrap();
+                assert_eq!(fetched, root);
+
+                await!(versioned_repo.store_metadata(&path, &version, &root)).unwrap();
+                assert!(temp_dir
+                    .path()
+                    .join("metadata")
+                    .join("1")
+                    .join("root.json")
+                    .exists());
+            },
+        )
+    }
+
+    #[test]
+    #[cfg(feature = "hyper")]
+    fn http_repo_upload() {
+        let mut runtime = Runtime::new().unwrap();
+        let uploads = Arc::new(Mutex::new(Vec::new()));
+        let server_uploads = uploads.clone();
+        let url = test_server(&mut runtime, move |parts, body| {
+            server_uploads.lock().unwrap().push((
+                parts.method,
+                parts.uri.path().to_string(),
+                parts.headers,
+                body,
+            ));
+            Response::new(Body::empty())
+        });
+
+        let key = PrivateKey::from_pkcs8(
+            include_bytes!("../tests/ed25519/ed25519-1.pk8.der"),
+            SignatureScheme::Ed25519,
+        )
+        .unwrap();
+        let data: &[u8] = b"like tears in the rain";
+        let path = TargetPath::new("foo/bar".into()).unwrap();
+        let root_path = MetadataPath::from_role(&Role::Root);
+
+        let put_repo = HttpRepositoryBuilder::<_, Json>::new(url.clone(), Client::new())
+            .metadata_prefix(vec!["meta".into()])
+            .versioned_metadata(true)
+            .header(
+                http::header::AUTHORIZATION,
+                HeaderValue::from_static("Bearer hunter2"),
+            )
+            .build();
+        let post_repo = HttpRepositoryBuilder::<_, Json>::new(url, Client::new())
+            .upload_method(HttpUploadMethod::PostMultipart)
+            .build();
+
+        let root = runtime
+            .block_on(
+                Box::pin(
+                    async {
+                        let root = await!(RootMetadataBuilder::new()
+                            .root_key(key.public().clone())
+                            .snapshot_key(key.public().clone())
+                            .targets_key(key.public().clone())
+                            .timestamp_key(key.public().clone())
+                            .signed::<Json>(&key))?;
+
+                        await!(put_repo.store_metadata(
+                            &root_path,
+                            &MetadataVersion::Number(1),
+                            &root,
+                        ))?;
+                        await!(put_repo.store_target(data, &path))?;
+                        await!(post_repo.store_target(data, &path))?;
+
+                        Ok::<_, Error>(root)
+                    },
+                )
+                .compat(),
+            )
+            .unwrap();
+
+        let uploads = uploads.lock().unwrap();
+        assert_eq!(uploads.len(), 3);
+
+        let (ref method, ref uri_path, ref headers, ref body) = uploads[0];
+        assert_eq!(method, &Method::PUT);
+        assert_eq!(uri_path, "/meta/1/1.root.json");
+        assert_eq!(headers["Authorization"], "Bearer hunter2");
+        let mut expected = Vec::new();
+        Json::to_writer(&mut expected, &root).unwrap();
+        assert_eq!(body, &expected);
+
+        let (ref method, ref uri_path, ref headers, ref body) = uploads[1];
+        assert_eq!(method, &Method::PUT);
+        assert_eq!(uri_path, "/foo/bar");
+        assert_eq!(headers["Authorization"], "Bearer hunter2");
+        assert_eq!(body.as_slice(), data);
+
+        let (ref method, ref uri_path, ref headers, ref body) = uploads[2];
+        assert_eq!(method, &Method::POST);
+        assert_eq!(uri_path, "/foo/bar");
+        assert!(!headers.contains_key("Authorization"));
+        let content_type = headers["Content-Type"].to_str().unwrap();
+        let boundary = content_type
+            .trim_start_matches("multipart/form-data; boundary=")
+            .to_string();
+        assert_ne!(boundary, content_type);
+        let mut expected = format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"bar\"\r\n\
+             Content-Type: application/octet-stream\r\n\r\n",
+            boundary
+        )
+        .into_bytes();
+        expected.extend_from_slice(data);
+        expected.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+        assert_eq!(body, &expected);
+    }
+
+    #[test]
+    fn http_repo_upload_retries() {
+        let data: &[u8] = b"like tears in the rain";
+        let uploads = Arc::new(Mutex::new(Vec::new()));
+        let transport_uploads = uploads.clone();
+        let transport = MockTransport {
+            respond: move |parts: Parts, body| {
+                let mut uploads = transport_uploads.lock().unwrap();
+                uploads.push((parts.headers, body));
+                let status = if uploads.len() < 2 {
+                    StatusCode::SERVICE_UNAVAILABLE
+                } else {
+                    StatusCode::OK
+                };
+                Response::builder().status(status).body(Vec::new()).unwrap()
+            },
+        };
+        let repo = HttpRepositoryBuilder::<_, Json>::new(
+            Url::parse("http://tuf.invalid/").unwrap(),
+            transport,
+        )
+        .upload_method(HttpUploadMethod::PostMultipart)
+        .retries(1)
+        .retry_backoff(Duration::from_millis(1), Duration::from_millis(10))
+        .build();
+
+        let path = TargetPath::new("foo/bar".into()).unwrap();
+        block_on(repo.store_target(data, &path)).unwrap();
+
+        // the whole body is sent again on the retry
+        let uploads = uploads.lock().unwrap();
+        assert_eq!(uploads.len(), 2);
+        for (ref headers, ref body) in uploads.iter() {
+            let boundary = headers["Content-Type"]
+                .to_str()
+                .unwrap()
+                .trim_start_matches("multipart/form-data; boundary=")
+                .to_string();
+            let mut expected = format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"bar\"\r\n\
+                 Content-Type: application/octet-stream\r\n\r\n",
+                boundary
+            )
+            .into_bytes();
+            expected.extend_from_slice(data);
+            expected.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+            assert_eq!(body, &expected);
+        }
+    }
+
+    #[test]
+    fn multipart_file_name() {
+        assert_eq!(escape_multipart_file_name("bar.json"), "bar.json");
+        assert_eq!(
+            escape_multipart_file_name("bar\"\r\nX-Evil: 1"),
+            "bar%22%0D%0AX-Evil: 1"
+        );
+    }
+
+    #[test]
+    fn http_repo_retries() {
+        let data: &[u8] = b"like tears in the rain";
+        let requests = Arc::new(AtomicUsize::new(0));
+        let repo = |retries| {
+            let requests = requests.clone();
+            let transport = MockTransport {
+                respond: move |_, _| {
+                    if requests.fetch_add(1, Ordering::SeqCst) < 2 {
+                        Response::builder()
+                            .status(StatusCode::SERVICE_UNAVAILABLE)
+                            .body(Vec::new())
+                            .unwrap()
+                    } else {
+                        Response::new(data.to_vec())
+                    }
+                },
+            };
+            let url = Url::parse("http://tuf.invalid/").unwrap();
+            HttpRepositoryBuilder::<_, Json>::new(url, transport)
+                .retries(retries)
+                .retry_backoff(Duration::from_millis(1), Duration::from_millis(10))
+                .build()
+        };
+
+        let target_description =
+            TargetDescription::from_reader(data, &[HashAlgorithm::Sha256]).unwrap();
+        let path = TargetPath::new("batty".into()).unwrap();
+
+        let patient_repo = repo(2);
+        let buf = block_on(
+            async {
+                let mut read = await!(patient_repo.fetch_target(&path, &target_description))?;
+                let mut buf = Vec::new();
+                await!(read.read_to_end(&mut buf))?;
+                Ok::<_, Error>(buf)
+            },
+        )
+        .unwrap();
+        assert_eq!(buf.as_slice(), data);
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+
+        requests.store(0, Ordering::SeqCst);
+        let impatient_repo = repo(1);
+        let result = block_on(
+            async { await!(impatient_repo.fetch_target(&path, &target_description)).map(|_| ()) },
+        );
+        match result {
+            Err(Error::Opaque(_)) => (),
+            x => panic!("expected a server error, got {:?}", x),
+        }
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    /// Fails every request with an `Error::Transport` that is `retryable` or not.
+    struct FailingTransport {
+        retryable: bool,
+        requests: AtomicUsize,
+    }
+
+    impl HttpTransport for FailingTransport {
+        fn request<'a>(
+            &'a self,
+            _: Request<Option<Box<dyn AsyncRead + 'a>>>,
+        ) -> TufFuture<'a, Result<Response<Box<dyn AsyncRead>>>> {
+            let _ = self.requests.fetch_add(1, Ordering::SeqCst);
+            Box::pin(future::ready(Err(Error::Transport {
+                message: "connection reset".into(),
+                retryable: self.retryable,
+            })))
+        }
+    }
+
+    #[test]
+    fn http_repo_transport_errors() {
+        let data: &[u8] = b"like tears in the rain";
+        let target_description =
+            TargetDescription::from_reader(data, &[HashAlgorithm::Sha256]).unwrap();
+        let path = TargetPath::new("batty".into()).unwrap();
+
+        for &retryable in &[true, false] {
+            let transport = FailingTransport {
+                retryable,
+                requests: AtomicUsize::new(0),
+            };
+            let repo = HttpRepositoryBuilder::<_, Json>::new(
+                Url::parse("http://tuf.invalid/").unwrap(),
+                transport,
+            )
+            .retries(2)
+            .retry_backoff(Duration::from_millis(1), Duration::from_millis(10))
+            .build();
+
+            let result = block_on(
+                async { await!(repo.fetch_target(&path, &target_description)).map(|_| ()) },
+            );
+            assert_eq!(
+                result,
+                Err(Error::Transport {
+                    message: "connection reset".into(),
+                    retryable,
+                })
+            );
+            let expected = if retryable { 3 } else { 1 };
+            assert_eq!(repo.transport.requests.load(Ordering::SeqCst), expected);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "hyper")]
+    fn http_repo_timeouts() {
+        let mut runtime = Runtime::new().unwrap();
+
+        // Connections are queued by the OS but never answered.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+
+        let data: &[u8] = b"like tears in the rain";
+        let target_description =
+            TargetDescription::from_reader(data, &[HashAlgorithm::Sha256]).unwrap();
+        let path = TargetPath::new("batty".into()).unwrap();
+
+        let request_timeout_repo =
+            HttpRepositoryBuilder::<_, Json>::new(url.clone(), Client::new())
+                .request_timeout(Duration::from_millis(50))
+                .retries(1)
+                .retry_backoff(Duration::from_millis(1), Duration::from_millis(1))
+                .build();
+        let total_timeout_repo = HttpRepositoryBuilder::<_, Json>::new(url, Client::new())
+            .request_timeout(Duration::from_millis(50))
+            .total_timeout(Duration::from_millis(200))
+            .retries(::std::u32::MAX)
+            .retry_backoff(Duration::from_millis(1), Duration::from_millis(1))
+            .build();
+
+        for repo in &[request_timeout_repo, total_timeout_repo] {
+            let result = runtime.block_on(
+                Box::pin(
+                    async { await!(repo.fetch_target(&path, &target_description)).map(|_| ()) },
+                )
+                .compat(),
+            );
+            assert_eq!(result, Err(Error::Timeout));
+        }
+    }
+
+    /// A response body that never has any data.
+    struct StalledBody;
+
+    impl AsyncRead for StalledBody {
+        fn poll_read(&mut self, _: &LocalWaker, _: &mut [u8]) -> Poll<io::Result<usize>> {
+            Poll::Pending
+        }
+    }
+
+    /// A transport that answers every request with a `StalledBody`.
+    struct StalledTransport;
+
+    impl HttpTransport for StalledTransport {
+        fn request<'a>(
+            &'a self,
+            _: Request<Option<Box<dyn AsyncRead + 'a>>>,
+        ) -> TufFuture<'a, Result<Response<Box<dyn AsyncRead>>>> {
+            let resp = Response::new(Box::new(StalledBody) as Box<dyn AsyncRead>);
+            Box::pin(future::ready(Ok(resp)))
+        }
+    }
+
+    #[test]
+    fn http_repo_total_timeout_covers_body() {
+        let repo = HttpRepositoryBuilder::<_, Json>::new(
+            Url::parse("http://tuf.invalid/").unwrap(),
+            StalledTransport,
+        )
+        .min_bytes_per_second(0)
+        .total_timeout(Duration::from_millis(100))
+        .build();
+
+        let data: &[u8] = b"like tears in the rain";
+        let target_description =
+            TargetDescription::from_reader(data, &[HashAlgorithm::Sha256]).unwrap();
+        let path = TargetPath::new("batty".into()).unwrap();
+        let err = block_on(
+            async {
+                let mut read = await!(repo.fetch_target(&path, &target_description)).unwrap();
+                let mut buf = Vec::new();
+                await!(read.read_to_end(&mut buf)).unwrap_err()
+            },
+        );
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    }
+
+    #[test]
+    #[cfg(feature = "hyper")]
+    fn http_repo_resume() {
+        let data: &[u8] = b"like tears in the rain";
+        let mut runtime = Runtime::new().unwrap();
+        let ranges = Arc::new(Mutex::new(Vec::new()));
+        let server_ranges = ranges.clone();
+        let ranged_url = test_server(&mut runtime, move |parts, _| {
+            let range = parts.headers["Range"].to_str().unwrap().to_string();
+            let start = range
+                .trim_start_matches("bytes=")
+                .trim_end_matches('-')
+                .parse::<usize>()
+                .unwrap();
+            server_ranges.lock().unwrap().push(range);
+            Response::builder()
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(
+                    "Content-Range",
+                    format!("bytes {}-{}/{}", start, data.len() - 1, data.len()),
+                )
+                .body(Body::from(&data[start..]))
+                .unwrap()
+        });
+        let unranged_url = test_server(&mut runtime, move |_, _| Response::new(Body::from(data)));
+
+        let target_description =
+            TargetDescription::from_reader(data, &[HashAlgorithm::Sha256]).unwrap();
+        let path = TargetPath::new("batty".into()).unwrap();
+        let ranged_repo = HttpRepositoryBuilder::<_, Json>::new(ranged_url, Client::new()).build();
+        let unranged_repo =
+            HttpRepositoryBuilder::<_, Json>::new(unranged_url, Client::new()).build();
+
+        let mut fetch = |repo: &HttpRepository<_, Json>, partial: &'static [u8]| {
+            runtime.block_on(
+                Box::pin(
+                    async {
+                        let mut read = await!(repo.fetch_target_resume(
+                            &path,
+                            &target_description,
+                            partial,
+                            partial.len() as u64,
+                        ))?;
+                        let mut buf = Vec::new();
+                        await!(read.read_to_end(&mut buf))?;
+                        Ok::<_, Error>(buf)
+                    },
+                )
+                .compat(),
+            )
+        };
+
+        assert_eq!(fetch(&ranged_repo, b"like tears").unwrap().as_slice(), data);
+        assert_eq!(*ranges.lock().unwrap(), vec!["bytes=10-".to_string()]);
+
+        // the resumed bytes are verified along with the rest
+        assert!(fetch(&ranged_repo, b"like fears").is_err());
+
+        // a server that does not support ranges sends everything again
+        assert_eq!(
+            fetch(&unranged_repo, b"like fears").unwrap().as_slice(),
+            data
+        );
+    }
+
+    #[test]
+    fn http_repo_conditional_metadata() {
+        let key = PrivateKey::from_pkcs8(
+            include_bytes!("../tests/ed25519/ed25519-1.pk8.der"),
+            SignatureScheme::Ed25519,
+        )
+        .unwrap();
+        let root = block_on(
+            RootMetadataBuilder::new()
+                .root_key(key.public().clone())
+                .snapshot_key(key.public().clone())
+                .targets_key(key.public().clone())
+                .timestamp_key(key.public().clone())
+                .signed::<Json>(&key),
+        )
+        .unwrap();
+        let mut root_bytes = Vec::new();
+        Json::to_writer(&mut root_bytes, &root).unwrap();
+
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let server_requests = requests.clone();
+        let transport = MockTransport {
+            respond: move |parts: Parts, _| {
+                let unchanged =
+                    parts.headers.get("If-None-Match").map(|v| v == "\"v1\"") == Some(true);
+                server_requests.lock().unwrap().push(parts.headers);
+                if unchanged {
+                    Response::builder()
+                        .status(StatusCode::NOT_MODIFIED)
+                        .body(Vec::new())
+                        .unwrap()
+                } else {
+                    Response::builder()
+                        .header("ETag", "\"v1\"")
+                        .header("Last-Modified", "Wed, 21 Oct 2015 07:28:00 GMT")
+                        .body(root_bytes.clone())
+                        .unwrap()
+                }
+            },
+        };
+
+        let repo = HttpRepositoryBuilder::<_, Json>::new(
+            Url::parse("http://tuf.invalid/").unwrap(),
+            transport,
+        )
+        .build();
+        let path = MetadataPath::from_role(&Role::Root);
+        let version = MetadataVersion::None;
+        let (rejected, verified, unchanged, unconditional) = block_on(
+            async {
+                let rejected: Option<SignedMetadata<Json, RootMetadata>> =
+                    await!(repo.fetch_metadata_if_changed(&path, &version, &None, None))?;
+                repo.metadata_verified(&path, &version, false);
+                let verified: Option<SignedMetadata<Json, RootMetadata>> =
+                    await!(repo.fetch_metadata_if_changed(&path, &version, &None, None))?;
+                repo.metadata_verified(&path, &version, true);
+                let unchanged: Option<SignedMetadata<Json, RootMetadata>> =
+                    await!(repo.fetch_metadata_if_changed(&path, &version, &None, None))?;
+                let unconditional: SignedMetadata<Json, RootMetadata> =
+                    await!(repo.fetch_metadata(&path, &version, &None, None))?;
+                Ok::<_, Error>((rejected, verified, unchanged, unconditional))
+            },
+        )
+        .unwrap();
+
+        assert_eq!(rejected, Some(root.clone()));
+        assert_eq!(verified, Some(root.clone()));
+        assert_eq!(unchanged, None);
+        assert_eq!(unconditional, root);
+
+        // metadata that was not verified is fetched again in full
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 4);
+        assert!(!requests[0].contains_key("If-None-Match"));
+        assert!(!requests[1].contains_key("If-None-Match"));
+        assert_eq!(requests[2]["If-None-Match"], "\"v1\"");
+        assert_eq!(
+            requests[2]["If-Modified-Since"],
+            "Wed, 21 Oct 2015 07:28:00 GMT"
+        );
+        assert!(!requests[3].contains_key("If-None-Match"));
+    }
+
+    #[test]
+    #[cfg(feature = "hyper")]
+    fn http_repo_targets_url() {
+        let data: &[u8] = b"like tears in the rain";
+        let mut runtime = Runtime::new().unwrap();
+        let paths = Arc::new(Mutex::new(Vec::new()));
+        let server_paths = paths.clone();
+        let targets_url = test_server(&mut runtime, move |parts, _| {
+            server_paths
+                .lock()
+                .unwrap()
+                .push(parts.uri.path().to_string());
+            Response::new(Body::from(data))
+        });
+
+        // nothing listens here, so only the targets server can be used
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        drop(listener);
+
+        let repo = HttpRepositoryBuilder::<_, Json>::new(url, Client::new())
+            .metadata_prefix(vec!["meta".into()])
+            .targets_url(targets_url.join("cdn/").unwrap())
+            .targets_prefix(vec!["targets".into()])
+            .build();
+
+        let target_description =
+            TargetDescription::from_reader(data, &[HashAlgorithm::Sha256]).unwrap();
+        let path = TargetPath::new("batty".into()).unwrap();
+        let buf = runtime
+            .block_on(
+                Box::pin(
+                    async {
+                        let mut read = await!(repo.fetch_target(&path, &target_description))?;
+                        let mut buf = Vec::new();
+                        await!(read.read_to_end(&mut buf))?;
+                        Ok::<_, Error>(buf)
+                    },
+                )
+                .compat(),
+            )
+            .unwrap();
+
+        assert_eq!(buf.as_slice(), data);
+        assert_eq!(
+            *paths.lock().unwrap(),
+            vec!["/cdn/targets/batty".to_string()]
+        );
+    }
+
+    #[test]
+    fn http_repo_upload_headers_origin() {
+        let data: &[u8] = b"like tears in the rain";
+        let uploads = Arc::new(Mutex::new(Vec::new()));
+        let transport_uploads = uploads.clone();
+        let transport = MockTransport {
+            respond: move |parts: Parts, _| {
+                transport_uploads
+                    .lock()
+                    .unwrap()
+                    .push((parts.uri.to_string(), parts.headers));
+                Response::new(Vec::new())
+            },
+        };
+        let repo = HttpRepositoryBuilder::<_, Json>::new(
+            Url::parse("http://tuf.invalid/").unwrap(),
+            transport,
+        )
+        .targets_url(Url::parse("http://cdn.invalid/").unwrap())
+        .header(
+            HeaderName::from_static("authorization"),
+            HeaderValue::from_static("Bearer hunter2"),
+        )
+        .build();
+
+        let path = TargetPath::new("batty".into()).unwrap();
+        block_on(repo.store_target(data, &path)).unwrap();
+
+        let uploads = uploads.lock().unwrap();
+        assert_eq!(uploads.len(), 1);
+        assert_eq!(uploads[0].0, "http://cdn.invalid/batty");
+        assert!(!uploads[0].1.contains_key("Authorization"));
+    }
+
+    #[test]
+    fn http_repo_uri_trailing_slash() {
+        type Repo = HttpRepository<MockTransport<fn(Parts, Vec<u8>) -> Response<Vec<u8>>>, Json>;
+        let components = vec!["root.json".to_string()];
+        let prefix = Some(vec!["meta".to_string()]);
+        for base in &["http://tuf.invalid/tuf", "http://tuf.invalid/tuf/"] {
+            let base = Url::parse(base).unwrap();
+            let uri = Repo::uri(&base, &prefix, &components).unwrap();
+            assert_eq!(uri.to_string(), "http://tuf.invalid/tuf/meta/root.json");
+        }
+
+        let base = Url::parse("http://tuf.invalid/").unwrap();
+        let uri = Repo::uri(&base, &None, &components).unwrap();
+        assert_eq!(uri.to_string(), "http://tuf.invalid/root.json");
+    }
+
+    #[test]
+    fn http_repo_mirror() {
+        let data: &[u8] = b"like tears in the rain";
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let transport_requests = requests.clone();
+        let transport = MockTransport {
+            respond: move |parts: Parts, _| {
+                transport_requests
+                    .lock()
+                    .unwrap()
+                    .push((parts.uri.to_string(), parts.headers));
+                Response::new(data.to_vec())
+            },
+        };
+        let repo = HttpRepositoryBuilder::<_, Json>::new(
+            Url::parse("http://tuf.invalid/").unwrap(),
+            transport,
+        )
+        .user_agent("replicant")
+        .header(
+            HeaderName::from_static("authorization"),
+            HeaderValue::from_static("Bearer hunter2"),
+        )
+        .build();
+
+        let mirror = Mirror::new(
+            Url::parse("http://mirror.invalid/tuf").unwrap(),
+            "/meta/".into(),
+            "targets".into(),
+            vec!["*".into()],
+            vec!["*".into()],
+        )
+        .unwrap();
+        let mirror_repo = repo.mirror(&mirror).unwrap();
+
+        let target_description =
+            TargetDescription::from_reader(data, &[HashAlgorithm::Sha256]).unwrap();
+        let path = TargetPath::new("batty".into()).unwrap();
+        let root_path = MetadataPath::from_role(&Role::Root);
+        let buf = block_on(
+            async {
+                let _ = await!(mirror_repo.fetch_metadata::<RootMetadata>(
+                    &root_path,
+                    &MetadataVersion::None,
+                    &None,
+                    None,
+                ));
+                let mut read = await!(mirror_repo.fetch_target(&path, &target_description))?;
+                let mut buf = Vec::new();
+                await!(read.read_to_end(&mut buf))?;
+                Ok::<_, Error>(buf)
+            },
+        )
+        .unwrap();
+        assert_eq!(buf.as_slice(), data);
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].0, "http://mirror.invalid/tuf/meta/root.json");
+        assert_eq!(requests[1].0, "http://mirror.invalid/tuf/targets/batty");
+        for (_, headers) in requests.iter() {
+            assert_eq!(headers["User-Agent"], "replicant");
+            assert!(!headers.contains_key("Authorization"));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "gzip")]
+    fn http_repo_content_encoding() {
+        let data: &[u8] = b"like tears in the rain";
+        // `data` compressed with gzip
+        let gzipped: &[u8] = b"\x1f\x8b\x08\x00\x00\x00\x00\x00\x02\x03\xcb\xc9\xcc\x4e\x55\x28\
+            \x49\x4d\x2c\x2a\x56\xc8\xcc\x53\x28\xc9\x48\x55\x28\x4a\xcc\xcc\x03\x00\xe3\x6f\xa9\
+            \x81\x16\x00\x00\x00";
+
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let server_requests = requests.clone();
+        let transport = MockTransport {
+            respond: move |parts: Parts, _| {
+                server_requests.lock().unwrap().push(parts.headers);
+                Response::builder()
+                    .header("Content-Encoding", "gzip")
+                    .body(gzipped.to_vec())
+                    .unwrap()
+            },
+        };
+        let repo = HttpRepositoryBuilder::<_, Json>::new(
+            Url::parse("http://tuf.invalid/").unwrap(),
+            transport,
+        )
+        .build();
+
+        let path = TargetPath::new("batty".into()).unwrap();
+        let fetch = |target_data: &[u8]| {
+            let target_description =
+                TargetDescription::from_reader(target_data, &[HashAlgorithm::Sha256]).unwrap();
+            block_on(
+                async {
+                    let mut read = await!(repo.fetch_target(&path, &target_description))?;
+                    let mut buf = Vec::new();
+                    await!(read.read_to_end(&mut buf))?;
+                    Ok::<_, Error>(buf)
+                },
+            )
+        };
+
+        // the size and hashes are of the decompressed bytes
+        assert_eq!(fetch(data).unwrap().as_slice(), data);
+        assert!(fetch(b"like tears").is_err());
+
+        let requests = requests.lock().unwrap();
+        assert!(requests[0]["Accept-Encoding"]
+            .to_str()
+            .unwrap()
+            .contains("gzip"));
+    }
+
+    #[test]
+    #[cfg(feature = "zstd")]
+    fn http_repo_zstd_content_encoding() {
+        let data: &[u8] = b"like tears in the rain";
+        // `data` compressed with zstd
+        let compressed: &[u8] = b"\x28\xb5\x2f\xfd\x00\x58\xb1\x00\x00\x6c\x69\x6b\x65\x20\
+            \x74\x65\x61\x72\x73\x20\x69\x6e\x20\x74\x68\x65\x20\x72\x61\x69\x6e";
+
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let server_requests = requests.clone();
+        let transport = MockTransport {
+            respond: move |parts: Parts, _| {
+                server_requests.lock().unwrap().push(parts.headers);
+                Response::builder()
+                    .header("Content-Encoding", "zstd")
+                    .body(compressed.to_vec())
+                    .unwrap()
+            },
+        };
+        let repo = HttpRepositoryBuilder::<_, Json>::new(
+            Url::parse("http://tuf.invalid/").unwrap(),
+            transport,
+        )
+        .build();
+
+        let target_description =
+            TargetDescription::from_reader(data, &[HashAlgorithm::Sha256]).unwrap();
+        let path = TargetPath::new("batty".into()).unwrap();
+        let buf = block_on(
+            async {
+                let mut read = await!(repo.fetch_target(&path, &target_description))?;
+                let mut buf = Vec::new();
+                await!(read.read_to_end(&mut buf))?;
+                Ok::<_, Error>(buf)
+            },
+        )
+        .unwrap();
+        assert_eq!(buf.as_slice(), data);
+
+        let requests = requests.lock().unwrap();
+        assert!(requests[0]["Accept-Encoding"]
+            .to_str()
+            .unwrap()
+            .contains("zstd"));
+    }
+
+    /// A `TimeSource` that only moves when it is told to.
+    #[cfg(feature = "gzip")]
+    struct FakeTime(Mutex<Instant>);
+
+    #[cfg(feature = "gzip")]
+    impl TimeSource for FakeTime {
+        fn now(&self) -> Instant {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    /// A response body that arrives one byte a second, by the clock of `time`.
+    #[cfg(feature = "gzip")]
+    struct TrickleBody {
+        time: Arc<FakeTime>,
+        body: Cursor<Vec<u8>>,
+    }
+
+    #[cfg(feature = "gzip")]
+    impl AsyncRead for TrickleBody {
+        fn poll_read(&mut self, lw: &LocalWaker, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+            *self.time.0.lock().unwrap() += Duration::from_secs(1);
+            let len = buf.len().min(1);
+            self.body.poll_read(lw, &mut buf[..len])
+        }
+    }
+
+    /// Answers every request with `body`, encoded with gzip and sent as a `TrickleBody`.
+    #[cfg(feature = "gzip")]
+    struct TrickleTransport {
+        time: Arc<FakeTime>,
+        body: Vec<u8>,
+    }
+
+    #[cfg(feature = "gzip")]
+    impl HttpTransport for TrickleTransport {
+        fn request<'a>(
+            &'a self,
+            _: Request<Option<Box<dyn AsyncRead + 'a>>>,
+        ) -> TufFuture<'a, Result<Response<Box<dyn AsyncRead>>>> {
+            let body = TrickleBody {
+                time: self.time.clone(),
+                body: Cursor::new(self.body.clone()),
+            };
+            let resp = Response::builder()
+                .header("Content-Encoding", "gzip")
+                .body(Box::new(body) as Box<dyn AsyncRead>)
+                .unwrap();
+            Box::pin(future::ready(Ok(resp)))
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "gzip")]
+    fn http_repo_slow_retrieval_counts_received_bytes() {
+        let data = vec![0; 10000];
+        // `data` compressed with gzip
+        let gzipped = b"\x1f\x8b\x08\x00\x00\x00\x00\x00\x02\xff\xed\xc1\x01\x0d\x00\x00\x00\
+            \xc2\xa0\xf7\x4f\x6d\x0e\x37\xa0\x00\x00\x00\x00\x00\x00\x00\x00\x00\xe0\xdf\x00\
+            \x2e\xca\x3b\x4d\x10\x27\x00\x00";
+
+        let time = Arc::new(FakeTime(Mutex::new(Instant::now())));
+        let transport = TrickleTransport {
+            time: time.clone(),
+            body: gzipped.to_vec(),
+        };
+        // the decompressed bytes arrive at over 200 bytes a second, but only one byte a second is
+        // received
+        let repo = HttpRepositoryBuilder::<_, Json>::new(
+            Url::parse("http://tuf.invalid/").unwrap(),
+            transport,
+        )
+        .slow_retrieval_policy(
+            SlowRetrievalPolicy::new(100)
+                .grace_period(Duration::from_secs(0))
+                .time_source(time),
+        )
+        .build();
+
+        let target_description =
+            TargetDescription::from_reader(data.as_slice(), &[HashAlgorithm::Sha256]).unwrap();
+        let path = TargetPath::new("batty".into()).unwrap();
+        let result = block_on(
+            async {
+                let mut read = await!(repo.fetch_target(&path, &target_description))?;
+                let mut buf = Vec::new();
+                await!(read.read_to_end(&mut buf))?;
+                Ok::<_, Error>(buf)
+            },
+        );
+        match result {
+            Err(Error::Opaque(ref msg)) if msg.contains("Bitrate too low") => (),
+            x => panic!("expected a slow retrieval error, got {:?}", x),
+        }
+    }
+
+    #[test]
+    fn http_repo_unsupported_content_encoding() {
+        let transport = MockTransport {
+            respond: |_, _| {
+                Response::builder()
+                    .header("Content-Encoding", "compress")
+                    .body(b"like tears in the rain".to_vec())
+                    .unwrap()
+            },
+        };
+        let repo = HttpRepositoryBuilder::<_, Json>::new(
+            Url::parse("http://tuf.invalid/").unwrap(),
+            transport,
+        )
+        .retries(1)
+        .build();
+
+        let data: &[u8] = b"like tears in the rain";
+        let target_description =
+            TargetDescription::from_reader(data, &[HashAlgorithm::Sha256]).unwrap();
+        let path = TargetPath::new("batty".into()).unwrap();
+        let result = block_on(
+            async { await!(repo.fetch_target(&path, &target_description)).map(|_| ()) },
+        );
+        match result {
+            Err(Error::Opaque(_)) => (),
+            x => panic!("expected an unsupported encoding error, got {:?}", x),
+        }
+    }
 }
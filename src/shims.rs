@@ -1,36 +1,74 @@
 use chrono::offset::Utc;
 use chrono::prelude::*;
+use chrono::SecondsFormat;
 use data_encoding::BASE64URL;
 use serde_derive::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::iter::FromIterator;
+use url::Url;
 
 use crate::crypto;
 use crate::error::Error;
 use crate::metadata::{self, Metadata};
 use crate::Result;
 
+/// Parse a timestamp in the form written by `format_datetime`.
 fn parse_datetime(ts: &str) -> Result<DateTime<Utc>> {
-    Utc.datetime_from_str(ts, "%FT%TZ")
-        .map_err(|e| Error::Encoding(format!("Can't parse DateTime: {:?}", e)))
+    let datetime = parse_rfc3339_utc(ts)?;
+    if format_datetime(&datetime) != ts {
+        return Err(Error::Encoding(format!(
+            "DateTime was not canonical: {:?}",
+            ts
+        )));
+    }
+    Ok(datetime)
+}
+
+/// Parse any RFC 3339 timestamp whose offset is equivalent to UTC.
+fn parse_rfc3339_utc(ts: &str) -> Result<DateTime<Utc>> {
+    let datetime = DateTime::parse_from_rfc3339(ts)
+        .map_err(|e| Error::Encoding(format!("Can't parse DateTime: {:?}", e)))?;
+    if datetime.offset().local_minus_utc() != 0 {
+        return Err(Error::Encoding(format!(
+            "DateTime must be in UTC. Found: {:?}",
+            ts
+        )));
+    }
+    Ok(datetime.with_timezone(&Utc))
+}
+
+/// Rewrite any RFC 3339 timestamp whose offset is equivalent to UTC into the form written by
+/// `format_datetime`.
+pub fn canonicalize_datetime(ts: &str) -> Result<String> {
+    Ok(format_datetime(&parse_rfc3339_utc(ts)?))
+}
+
+fn check_extensions(
+    extensions: &HashMap<String, metadata::ExtensionValue>,
+    fields: &[&str],
+) -> Result<()> {
+    for key in extensions.keys() {
+        if fields.contains(&key.as_str()) {
+            return Err(Error::IllegalArgument(format!(
+                "Extension field {:?} collides with a field defined by the specification",
+                key
+            )));
+        }
+    }
+    Ok(())
 }
 
+/// Write a timestamp as `YYYY-MM-DDThh:mm:ssZ`, keeping fractional seconds if there are any.
 fn format_datetime(ts: &DateTime<Utc>) -> String {
-    format!(
-        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
-        ts.year(),
-        ts.month(),
-        ts.day(),
-        ts.hour(),
-        ts.minute(),
-        ts.second()
-    )
+    ts.to_rfc3339_opts(SecondsFormat::AutoSi, true)
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RootMetadata {
     #[serde(rename = "type")]
     typ: metadata::Role,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    spec_version: Option<String>,
     version: u32,
     consistent_snapshot: bool,
     expires: String,
@@ -39,6 +77,10 @@ pub struct RootMetadata {
     snapshot: metadata::RoleDefinition,
     targets: metadata::RoleDefinition,
     timestamp: metadata::RoleDefinition,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mirrors: Option<metadata::RoleDefinition>,
+    #[serde(flatten)]
+    extensions: HashMap<String, metadata::ExtensionValue>,
 }
 
 impl RootMetadata {
@@ -49,9 +91,26 @@ impl RootMetadata {
             .map(|(_, v)| v.clone())
             .collect::<Vec<crypto::PublicKey>>();
         keys.sort_by_key(|k| k.key_id().clone());
+        check_extensions(
+            meta.extensions(),
+            &[
+                "type",
+                "spec_version",
+                "version",
+                "consistent_snapshot",
+                "expires",
+                "keys",
+                "root",
+                "snapshot",
+                "targets",
+                "timestamp",
+                "mirrors",
+            ],
+        )?;
 
         Ok(RootMetadata {
             typ: metadata::Role::Root,
+            spec_version: meta.spec_version().map(|v| v.into()),
             version: meta.version(),
             expires: format_datetime(&meta.expires()),
             consistent_snapshot: meta.consistent_snapshot(),
@@ -60,6 +119,8 @@ impl RootMetadata {
             snapshot: meta.snapshot().clone(),
             targets: meta.targets().clone(),
             timestamp: meta.timestamp().clone(),
+            mirrors: meta.mirrors().cloned(),
+            extensions: meta.extensions().clone(),
         })
     }
 
@@ -78,6 +139,7 @@ impl RootMetadata {
             return Err(Error::IllegalArgument("Cannot have duplicate keys".into()));
         }
 
+        let extensions = self.extensions;
         metadata::RootMetadata::new(
             self.version,
             parse_datetime(&self.expires)?,
@@ -87,7 +149,10 @@ impl RootMetadata {
             self.snapshot,
             self.targets,
             self.timestamp,
-        )
+        )?
+        .with_mirrors(self.mirrors)
+        .with_spec_version(self.spec_version)
+        .map(|root| root.with_extensions(extensions))
     }
 }
 
@@ -95,6 +160,8 @@ impl RootMetadata {
 pub struct RoleDefinition {
     threshold: u32,
     key_ids: Vec<crypto::KeyId>,
+    #[serde(flatten)]
+    extensions: HashMap<String, metadata::ExtensionValue>,
 }
 
 impl RoleDefinition {
@@ -105,10 +172,12 @@ impl RoleDefinition {
             .cloned()
             .collect::<Vec<crypto::KeyId>>();
         key_ids.sort();
+        check_extensions(role.extensions(), &["threshold", "key_ids"])?;
 
         Ok(RoleDefinition {
             threshold: role.threshold(),
             key_ids,
+            extensions: role.extensions().clone(),
         })
     }
 
@@ -130,7 +199,8 @@ impl RoleDefinition {
             )));
         }
 
-        Ok(metadata::RoleDefinition::new(self.threshold, key_ids)?)
+        metadata::RoleDefinition::new(self.threshold, key_ids)
+            .map(|role| role.with_extensions(self.extensions))
     }
 }
 
@@ -138,18 +208,29 @@ impl RoleDefinition {
 pub struct TimestampMetadata {
     #[serde(rename = "type")]
     typ: metadata::Role,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    spec_version: Option<String>,
     version: u32,
     expires: String,
     snapshot: metadata::MetadataDescription,
+    #[serde(flatten)]
+    extensions: HashMap<String, metadata::ExtensionValue>,
 }
 
 impl TimestampMetadata {
     pub fn from(metadata: &metadata::TimestampMetadata) -> Result<Self> {
+        check_extensions(
+            metadata.extensions(),
+            &["type", "spec_version", "version", "expires", "snapshot"],
+        )?;
+
         Ok(TimestampMetadata {
             typ: metadata::Role::Timestamp,
+            spec_version: metadata.spec_version().map(|v| v.into()),
             version: metadata.version(),
             expires: format_datetime(metadata.expires()),
             snapshot: metadata.snapshot().clone(),
+            extensions: metadata.extensions().clone(),
         })
     }
 
@@ -161,11 +242,14 @@ impl TimestampMetadata {
             )));
         }
 
+        let extensions = self.extensions;
         metadata::TimestampMetadata::new(
             self.version,
             parse_datetime(&self.expires)?,
             self.snapshot,
-        )
+        )?
+        .with_spec_version(self.spec_version)
+        .map(|timestamp| timestamp.with_extensions(extensions))
     }
 }
 
@@ -173,18 +257,29 @@ impl TimestampMetadata {
 pub struct SnapshotMetadata {
     #[serde(rename = "type")]
     typ: metadata::Role,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    spec_version: Option<String>,
     version: u32,
     expires: String,
     meta: HashMap<metadata::MetadataPath, metadata::MetadataDescription>,
+    #[serde(flatten)]
+    extensions: HashMap<String, metadata::ExtensionValue>,
 }
 
 impl SnapshotMetadata {
     pub fn from(metadata: &metadata::SnapshotMetadata) -> Result<Self> {
+        check_extensions(
+            metadata.extensions(),
+            &["type", "spec_version", "version", "expires", "meta"],
+        )?;
+
         Ok(SnapshotMetadata {
             typ: metadata::Role::Snapshot,
+            spec_version: metadata.spec_version().map(|v| v.into()),
             version: metadata.version(),
             expires: format_datetime(&metadata.expires()),
             meta: metadata.meta().clone(),
+            extensions: metadata.extensions().clone(),
         })
     }
 
@@ -196,7 +291,10 @@ impl SnapshotMetadata {
             )));
         }
 
-        metadata::SnapshotMetadata::new(self.version, parse_datetime(&self.expires)?, self.meta)
+        let extensions = self.extensions;
+        metadata::SnapshotMetadata::new(self.version, parse_datetime(&self.expires)?, self.meta)?
+            .with_spec_version(self.spec_version)
+            .map(|snapshot| snapshot.with_extensions(extensions))
     }
 }
 
@@ -204,21 +302,39 @@ impl SnapshotMetadata {
 pub struct TargetsMetadata {
     #[serde(rename = "type")]
     typ: metadata::Role,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    spec_version: Option<String>,
     version: u32,
     expires: String,
     targets: HashMap<metadata::VirtualTargetPath, metadata::TargetDescription>,
     #[serde(skip_serializing_if = "Option::is_none")]
     delegations: Option<metadata::Delegations>,
+    #[serde(flatten)]
+    extensions: HashMap<String, metadata::ExtensionValue>,
 }
 
 impl TargetsMetadata {
     pub fn from(metadata: &metadata::TargetsMetadata) -> Result<Self> {
+        check_extensions(
+            metadata.extensions(),
+            &[
+                "type",
+                "spec_version",
+                "version",
+                "expires",
+                "targets",
+                "delegations",
+            ],
+        )?;
+
         Ok(TargetsMetadata {
             typ: metadata::Role::Targets,
+            spec_version: metadata.spec_version().map(|v| v.into()),
             version: metadata.version(),
             expires: format_datetime(&metadata.expires()),
             targets: metadata.targets().clone(),
             delegations: metadata.delegations().cloned(),
+            extensions: metadata.extensions().clone(),
         })
     }
 
@@ -230,11 +346,93 @@ impl TargetsMetadata {
             )));
         }
 
+        let extensions = self.extensions;
         metadata::TargetsMetadata::new(
             self.version,
             parse_datetime(&self.expires)?,
             self.targets,
             self.delegations,
+        )?
+        .with_spec_version(self.spec_version)
+        .map(|targets| targets.with_extensions(extensions))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MirrorsMetadata {
+    #[serde(rename = "type")]
+    typ: metadata::Role,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    spec_version: Option<String>,
+    version: u32,
+    expires: String,
+    mirrors: Vec<metadata::Mirror>,
+    #[serde(flatten)]
+    extensions: HashMap<String, metadata::ExtensionValue>,
+}
+
+impl MirrorsMetadata {
+    pub fn from(metadata: &metadata::MirrorsMetadata) -> Result<Self> {
+        check_extensions(
+            metadata.extensions(),
+            &["type", "spec_version", "version", "expires", "mirrors"],
+        )?;
+
+        Ok(MirrorsMetadata {
+            typ: metadata::Role::Mirrors,
+            spec_version: metadata.spec_version().map(|v| v.into()),
+            version: metadata.version(),
+            expires: format_datetime(&metadata.expires()),
+            mirrors: metadata.mirrors().to_vec(),
+            extensions: metadata.extensions().clone(),
+        })
+    }
+
+    pub fn try_into(self) -> Result<metadata::MirrorsMetadata> {
+        if self.typ != metadata::Role::Mirrors {
+            return Err(Error::Encoding(format!(
+                "Attempted to decode mirrors metdata labeled as {:?}",
+                self.typ
+            )));
+        }
+
+        let extensions = self.extensions;
+        metadata::MirrorsMetadata::new(self.version, parse_datetime(&self.expires)?, self.mirrors)?
+            .with_spec_version(self.spec_version)
+            .map(|mirrors| mirrors.with_extensions(extensions))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Mirror {
+    urlbase: String,
+    metapath: String,
+    targetspath: String,
+    metacontent: Vec<String>,
+    targetscontent: Vec<String>,
+}
+
+impl Mirror {
+    pub fn from(mirror: &metadata::Mirror) -> Self {
+        Mirror {
+            urlbase: mirror.url_base().to_string(),
+            metapath: mirror.meta_path().into(),
+            targetspath: mirror.targets_path().into(),
+            metacontent: mirror.meta_content().to_vec(),
+            targetscontent: mirror.targets_content().to_vec(),
+        }
+    }
+
+    pub fn try_into(self) -> Result<metadata::Mirror> {
+        let url_base = Url::parse(&self.urlbase)
+            .map_err(|e| Error::Encoding(format!("Can't parse mirror URL: {:?}", e)))?;
+
+        metadata::Mirror::new(
+            url_base,
+            self.metapath,
+            self.targetspath,
+            self.metacontent,
+            self.targetscontent,
         )
     }
 }
@@ -245,6 +443,8 @@ pub struct PublicKey {
     typ: crypto::KeyType,
     scheme: crypto::SignatureScheme,
     public_key: String,
+    #[serde(flatten)]
+    extensions: HashMap<String, metadata::ExtensionValue>,
 }
 
 impl PublicKey {
@@ -252,12 +452,16 @@ impl PublicKey {
         typ: crypto::KeyType,
         scheme: crypto::SignatureScheme,
         public_key_bytes: &[u8],
-    ) -> Self {
-        PublicKey {
+        extensions: &HashMap<String, metadata::ExtensionValue>,
+    ) -> Result<Self> {
+        check_extensions(extensions, &["type", "scheme", "public_key"])?;
+
+        Ok(PublicKey {
             typ,
             scheme,
             public_key: BASE64URL.encode(public_key_bytes),
-        }
+            extensions: extensions.clone(),
+        })
     }
 
     pub fn public_key(&self) -> &String {
@@ -271,6 +475,10 @@ impl PublicKey {
     pub fn typ(&self) -> &crypto::KeyType {
         &self.typ
     }
+
+    pub fn into_extensions(self) -> HashMap<String, metadata::ExtensionValue> {
+        self.extensions
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -280,10 +488,12 @@ pub struct Delegation {
     threshold: u32,
     key_ids: Vec<crypto::KeyId>,
     paths: Vec<metadata::VirtualTargetPath>,
+    #[serde(flatten)]
+    extensions: HashMap<String, metadata::ExtensionValue>,
 }
 
 impl Delegation {
-    pub fn from(meta: &metadata::Delegation) -> Self {
+    pub fn from(meta: &metadata::Delegation) -> Result<Self> {
         let mut paths = meta
             .paths()
             .iter()
@@ -296,14 +506,19 @@ impl Delegation {
             .cloned()
             .collect::<Vec<crypto::KeyId>>();
         key_ids.sort();
+        check_extensions(
+            meta.extensions(),
+            &["role", "terminating", "threshold", "key_ids", "paths"],
+        )?;
 
-        Delegation {
+        Ok(Delegation {
             role: meta.role().clone(),
             terminating: meta.terminating(),
             threshold: meta.threshold(),
             key_ids,
             paths,
-        }
+            extensions: meta.extensions().clone(),
+        })
     }
 
     pub fn try_into(self) -> Result<metadata::Delegation> {
@@ -325,7 +540,9 @@ impl Delegation {
             return Err(Error::Encoding("Non-unique delegation key IDs.".into()));
         }
 
+        let extensions = self.extensions;
         metadata::Delegation::new(self.role, self.terminating, self.threshold, key_ids, paths)
+            .map(|delegation| delegation.with_extensions(extensions))
     }
 }
 
@@ -360,15 +577,29 @@ impl Delegations {
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize)]
 pub struct TargetDescription {
     size: u64,
     hashes: HashMap<crypto::HashAlgorithm, crypto::HashValue>,
+    #[serde(flatten)]
+    extensions: HashMap<String, metadata::ExtensionValue>,
 }
 
 impl TargetDescription {
+    pub fn from(description: &metadata::TargetDescription) -> Result<Self> {
+        check_extensions(description.extensions(), &["size", "hashes"])?;
+
+        Ok(TargetDescription {
+            size: description.size(),
+            hashes: description.hashes().clone(),
+            extensions: description.extensions().clone(),
+        })
+    }
+
     pub fn try_into(self) -> Result<metadata::TargetDescription> {
+        let extensions = self.extensions;
         metadata::TargetDescription::new(self.size, self.hashes)
+            .map(|description| description.with_extensions(extensions))
     }
 }
 
@@ -9,8 +9,8 @@ use crate::crypto::KeyId;
 use crate::error::Error;
 use crate::interchange::DataInterchange;
 use crate::metadata::{
-    Delegations, Metadata, MetadataPath, Role, RootMetadata, SignedMetadata, SnapshotMetadata,
-    TargetDescription, TargetsMetadata, TimestampMetadata, VirtualTargetPath,
+    self, Delegations, Metadata, MetadataPath, MirrorsMetadata, Role, RootMetadata, SignedMetadata,
+    SnapshotMetadata, TargetDescription, TargetsMetadata, TimestampMetadata, VirtualTargetPath,
 };
 use crate::Result;
 
@@ -22,6 +22,7 @@ pub struct Tuf<D: DataInterchange> {
     targets: Option<SignedMetadata<D, TargetsMetadata>>,
     timestamp: Option<SignedMetadata<D, TimestampMetadata>>,
     delegations: HashMap<MetadataPath, SignedMetadata<D, TargetsMetadata>>,
+    mirrors: Option<SignedMetadata<D, MirrorsMetadata>>,
     interchange: PhantomData<D>,
 }
 
@@ -50,6 +51,7 @@ impl<D: DataInterchange> Tuf<D> {
     pub fn from_root(signed_root: SignedMetadata<D, RootMetadata>) -> Result<Self> {
         {
             let root = signed_root.as_ref();
+            check_spec_version(root)?;
             signed_root.verify(
                 root.root().threshold(),
                 root.keys().iter().filter_map(|(k, v)| {
@@ -67,6 +69,7 @@ impl<D: DataInterchange> Tuf<D> {
             targets: None,
             timestamp: None,
             delegations: HashMap::new(),
+            mirrors: None,
             interchange: PhantomData,
         })
     }
@@ -116,6 +119,16 @@ impl<D: DataInterchange> Tuf<D> {
         self.timestamp.as_ref()
     }
 
+    /// An immutable reference to the optional mirrors metadata.
+    pub fn mirrors(&self) -> Option<&MirrorsMetadata> {
+        self.mirrors.as_ref().map(|m| m.as_ref())
+    }
+
+    /// An immutable reference to the optional signed mirrors metadata.
+    pub fn signed_mirrors(&self) -> Option<&SignedMetadata<D, MirrorsMetadata>> {
+        self.mirrors.as_ref()
+    }
+
     fn current_timestamp_version(&self) -> u32 {
         self.timestamp
             .as_ref()
@@ -137,6 +150,13 @@ impl<D: DataInterchange> Tuf<D> {
             .unwrap_or(0)
     }
 
+    fn current_mirrors_version(&self) -> u32 {
+        self.mirrors
+            .as_ref()
+            .map(|m| m.as_ref().version())
+            .unwrap_or(0)
+    }
+
     fn current_delegation_version(&self, role: &MetadataPath) -> u32 {
         self.delegations
             .get(role)
@@ -177,6 +197,9 @@ impl<D: DataInterchange> Tuf<D> {
                 )));
             }
 
+            // Next, make sure the new root is in a format we understand.
+            check_spec_version(new_root)?;
+
             // Finally, make sure the new root was signed by the keys in the new root.
             signed_root.verify(
                 new_root.root().threshold(),
@@ -217,6 +240,9 @@ impl<D: DataInterchange> Tuf<D> {
                 }),
             )?;
 
+            // Next, make sure the timestamp is in a format we understand.
+            check_spec_version(timestamp)?;
+
             // Next, make sure the timestamp hasn't expired.
             if timestamp.expires() <= &Utc::now() {
                 return Err(Error::ExpiredMetadata(Role::Timestamp));
@@ -276,6 +302,7 @@ impl<D: DataInterchange> Tuf<D> {
             )?;
 
             let snapshot = signed_snapshot.as_ref();
+            check_spec_version(snapshot)?;
 
             if snapshot.version() != timestamp.snapshot().version() {
                 return Err(Error::VerificationFailure(format!(
@@ -378,6 +405,7 @@ impl<D: DataInterchange> Tuf<D> {
             )?;
 
             let targets = signed_targets.as_ref();
+            check_spec_version(targets)?;
 
             if targets.version() != targets_description.version() {
                 return Err(Error::VerificationFailure(format!(
@@ -464,6 +492,8 @@ impl<D: DataInterchange> Tuf<D> {
             }
 
             let delegation = signed_delegation.as_ref();
+            check_spec_version(delegation)?;
+
             if delegation.version() != delegation_description.version() {
                 return Err(Error::VerificationFailure(format!(
                     "The snapshot metadata reported that the delegation {:?} should be at \
@@ -485,6 +515,55 @@ impl<D: DataInterchange> Tuf<D> {
         Ok(true)
     }
 
+    /// Verify and update the mirrors metadata. This fails if the root metadata does not define a
+    /// mirrors role.
+    pub fn update_mirrors(
+        &mut self,
+        signed_mirrors: SignedMetadata<D, MirrorsMetadata>,
+    ) -> Result<bool> {
+        {
+            let root = self.safe_root_ref()?;
+            let definition = root.mirrors().ok_or_else(|| {
+                Error::VerificationFailure(
+                    "The root metadata does not define a mirrors role".into(),
+                )
+            })?;
+
+            signed_mirrors.verify(
+                definition.threshold(),
+                root.keys().iter().filter_map(|(k, v)| {
+                    if definition.key_ids().contains(k) {
+                        Some(v)
+                    } else {
+                        None
+                    }
+                }),
+            )?;
+
+            let mirrors = signed_mirrors.as_ref();
+            check_spec_version(mirrors)?;
+
+            let current_version = self.current_mirrors_version();
+
+            if mirrors.version() < current_version {
+                return Err(Error::VerificationFailure(format!(
+                    "Attempted to roll back mirrors metadata at version {} to {}.",
+                    current_version,
+                    mirrors.version()
+                )));
+            } else if mirrors.version() == current_version {
+                return Ok(false);
+            }
+
+            if mirrors.expires() <= &Utc::now() {
+                return Err(Error::ExpiredMetadata(Role::Mirrors));
+            }
+        }
+
+        self.mirrors = Some(signed_mirrors);
+        Ok(true)
+    }
+
     /// Get a reference to the description needed to verify the target defined by the given
     /// `VirtualTargetPath`. Returns an `Error` if the target is not defined in the trusted
     /// metadata. This may mean the target exists somewhere in the metadata, but the chain of trust
@@ -571,6 +650,7 @@ impl<D: DataInterchange> Tuf<D> {
         self.targets = None;
         self.timestamp = None;
         self.delegations.clear();
+        self.mirrors = None;
     }
 
     fn safe_root_ref(&self) -> Result<&RootMetadata> {
@@ -620,16 +700,34 @@ impl<D: DataInterchange> Tuf<D> {
     }
 }
 
+/// Make sure the metadata was written for the major version of the TUF specification that this
+/// library implements.
+fn check_spec_version<M>(metadata: &M) -> Result<()>
+where
+    M: Metadata,
+{
+    let supported = metadata::spec_major_version(metadata::SPEC_VERSION)?;
+    let spec_version = metadata
+        .spec_version()
+        .unwrap_or(metadata::LEGACY_SPEC_VERSION);
+    match metadata::spec_major_version(spec_version) {
+        Ok(major) if major == supported => Ok(()),
+        _ => Err(Error::UnsupportedSpecVersion(spec_version.into())),
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::crypto::{HashAlgorithm, PrivateKey, SignatureScheme};
     use crate::interchange::Json;
     use crate::metadata::{
-        RootMetadataBuilder, SnapshotMetadataBuilder, TargetsMetadataBuilder,
-        TimestampMetadataBuilder,
+        Mirror, MirrorsMetadataBuilder, RootMetadataBuilder, SnapshotMetadataBuilder,
+        TargetsMetadataBuilder, TimestampMetadataBuilder,
     };
+    use futures::executor::block_on;
     use lazy_static::lazy_static;
+    use url::Url;
 
     lazy_static! {
         static ref KEYS: Vec<PrivateKey> = {
@@ -650,53 +748,180 @@ mod test {
     #[test]
     fn root_pinned_success() {
         let root_key = &KEYS[0];
-        let root = RootMetadataBuilder::new()
-            .root_key(KEYS[0].public().clone())
-            .snapshot_key(KEYS[0].public().clone())
-            .targets_key(KEYS[0].public().clone())
-            .timestamp_key(KEYS[0].public().clone())
-            .signed::<Json>(&root_key)
-            .unwrap();
+        let root = block_on(
+            RootMetadataBuilder::new()
+                .root_key(KEYS[0].public().clone())
+                .snapshot_key(KEYS[0].public().clone())
+                .targets_key(KEYS[0].public().clone())
+                .timestamp_key(KEYS[0].public().clone())
+                .signed::<Json>(root_key),
+        )
+        .unwrap();
 
         assert!(Tuf::from_root_pinned(root, &[root_key.key_id().clone()]).is_ok());
     }
 
     #[test]
     fn root_pinned_failure() {
-        let root = RootMetadataBuilder::new()
-            .root_key(KEYS[0].public().clone())
-            .snapshot_key(KEYS[0].public().clone())
-            .targets_key(KEYS[0].public().clone())
-            .timestamp_key(KEYS[0].public().clone())
-            .signed::<Json>(&KEYS[0])
-            .unwrap();
+        let root = block_on(
+            RootMetadataBuilder::new()
+                .root_key(KEYS[0].public().clone())
+                .snapshot_key(KEYS[0].public().clone())
+                .targets_key(KEYS[0].public().clone())
+                .timestamp_key(KEYS[0].public().clone())
+                .signed::<Json>(&KEYS[0]),
+        )
+        .unwrap();
 
         assert!(Tuf::from_root_pinned(root, &[KEYS[1].key_id().clone()]).is_err());
     }
 
     #[test]
-    fn good_root_rotation() {
+    fn unsupported_spec_version() {
+        let root = block_on(
+            RootMetadataBuilder::new()
+                .spec_version("2.0.0".into())
+                .root_key(KEYS[0].public().clone())
+                .snapshot_key(KEYS[0].public().clone())
+                .targets_key(KEYS[0].public().clone())
+                .timestamp_key(KEYS[0].public().clone())
+                .signed::<Json>(&KEYS[0]),
+        )
+        .unwrap();
+
+        assert_eq!(
+            Tuf::from_root(root).unwrap_err(),
+            Error::UnsupportedSpecVersion("2.0.0".into())
+        );
+
+        let root = block_on(
+            RootMetadataBuilder::new()
+                .spec_version("1.1.0".into())
+                .root_key(KEYS[0].public().clone())
+                .snapshot_key(KEYS[0].public().clone())
+                .targets_key(KEYS[0].public().clone())
+                .timestamp_key(KEYS[0].public().clone())
+                .signed::<Json>(&KEYS[0]),
+        )
+        .unwrap();
+
+        assert!(Tuf::from_root(root).is_ok());
+
+        // metadata without a `spec_version` is treated as `LEGACY_SPEC_VERSION`
         let root = RootMetadataBuilder::new()
             .root_key(KEYS[0].public().clone())
             .snapshot_key(KEYS[0].public().clone())
             .targets_key(KEYS[0].public().clone())
             .timestamp_key(KEYS[0].public().clone())
-            .signed::<Json>(&KEYS[0])
+            .build()
+            .unwrap()
+            .with_spec_version(None)
             .unwrap();
+        let root = block_on(SignedMetadata::<Json, _>::new(root, &KEYS[0])).unwrap();
+
+        assert!(Tuf::from_root(root).is_ok());
+    }
+
+    #[test]
+    fn good_mirrors_update() {
+        let root = block_on(
+            RootMetadataBuilder::new()
+                .root_key(KEYS[0].public().clone())
+                .snapshot_key(KEYS[0].public().clone())
+                .targets_key(KEYS[0].public().clone())
+                .timestamp_key(KEYS[0].public().clone())
+                .mirrors_key(KEYS[1].public().clone())
+                .signed::<Json>(&KEYS[0]),
+        )
+        .unwrap();
 
         let mut tuf = Tuf::from_root(root).unwrap();
 
-        let mut root = RootMetadataBuilder::new()
-            .version(2)
-            .root_key(KEYS[1].public().clone())
-            .snapshot_key(KEYS[1].public().clone())
-            .targets_key(KEYS[1].public().clone())
-            .timestamp_key(KEYS[1].public().clone())
-            .signed::<Json>(&KEYS[1])
-            .unwrap();
+        let mirror = Mirror::new(
+            Url::parse("https://mirror.example.com/").unwrap(),
+            "metadata".into(),
+            "targets".into(),
+            vec!["*".into()],
+            vec!["*".into()],
+        )
+        .unwrap();
+        let mirrors = block_on(
+            MirrorsMetadataBuilder::new()
+                .mirror(mirror.clone())
+                .signed::<Json>(&KEYS[1]),
+        )
+        .unwrap();
+
+        assert_eq!(tuf.update_mirrors(mirrors.clone()), Ok(true));
+        assert_eq!(tuf.mirrors().unwrap().mirrors(), &[mirror]);
+
+        // second update should do nothing
+        assert_eq!(tuf.update_mirrors(mirrors), Ok(false));
+    }
+
+    #[test]
+    fn bad_mirrors_update() {
+        let root = block_on(
+            RootMetadataBuilder::new()
+                .root_key(KEYS[0].public().clone())
+                .snapshot_key(KEYS[0].public().clone())
+                .targets_key(KEYS[0].public().clone())
+                .timestamp_key(KEYS[0].public().clone())
+                .signed::<Json>(&KEYS[0]),
+        )
+        .unwrap();
+
+        let mut tuf = Tuf::from_root(root).unwrap();
+
+        // the root does not define a mirrors role
+        let mirrors = block_on(MirrorsMetadataBuilder::new().signed::<Json>(&KEYS[0])).unwrap();
+        assert!(tuf.update_mirrors(mirrors).is_err());
+
+        let root = block_on(
+            RootMetadataBuilder::new()
+                .version(2)
+                .root_key(KEYS[0].public().clone())
+                .snapshot_key(KEYS[0].public().clone())
+                .targets_key(KEYS[0].public().clone())
+                .timestamp_key(KEYS[0].public().clone())
+                .mirrors_key(KEYS[1].public().clone())
+                .signed::<Json>(&KEYS[0]),
+        )
+        .unwrap();
+        assert_eq!(tuf.update_root(root), Ok(true));
+
+        // signed with the wrong key
+        let mirrors = block_on(MirrorsMetadataBuilder::new().signed::<Json>(&KEYS[0])).unwrap();
+        assert!(tuf.update_mirrors(mirrors).is_err());
+    }
+
+    #[test]
+    fn good_root_rotation() {
+        let root = block_on(
+            RootMetadataBuilder::new()
+                .root_key(KEYS[0].public().clone())
+                .snapshot_key(KEYS[0].public().clone())
+                .targets_key(KEYS[0].public().clone())
+                .timestamp_key(KEYS[0].public().clone())
+                .signed::<Json>(&KEYS[0]),
+        )
+        .unwrap();
+
+        let mut tuf = Tuf::from_root(root).unwrap();
+
+        let mut root = block_on(
+            RootMetadataBuilder::new()
+                .version(2)
+                .root_key(KEYS[1].public().clone())
+                .snapshot_key(KEYS[1].public().clone())
+                .targets_key(KEYS[1].public().clone())
+                .timestamp_key(KEYS[1].public().clone())
+                .signed::<Json>(&KEYS[1]),
+        )
+        .unwrap();
 
         // add the original key's signature to make it cross signed
-        root.add_signature(&KEYS[0]).unwrap();
+        block_on(root.add_signature(&KEYS[0])).unwrap();
 
         assert_eq!(tuf.update_root(root.clone()), Ok(true));
 
@@ -706,48 +931,53 @@ mod test {
 
     #[test]
     fn no_cross_sign_root_rotation() {
-        let root = RootMetadataBuilder::new()
-            .root_key(KEYS[0].public().clone())
-            .snapshot_key(KEYS[0].public().clone())
-            .targets_key(KEYS[0].public().clone())
-            .timestamp_key(KEYS[0].public().clone())
-            .signed::<Json>(&KEYS[0])
-            .unwrap();
+        let root = block_on(
+            RootMetadataBuilder::new()
+                .root_key(KEYS[0].public().clone())
+                .snapshot_key(KEYS[0].public().clone())
+                .targets_key(KEYS[0].public().clone())
+                .timestamp_key(KEYS[0].public().clone())
+                .signed::<Json>(&KEYS[0]),
+        )
+        .unwrap();
 
         let mut tuf = Tuf::from_root(root).unwrap();
 
-        let root = RootMetadataBuilder::new()
-            .root_key(KEYS[1].public().clone())
-            .snapshot_key(KEYS[1].public().clone())
-            .targets_key(KEYS[1].public().clone())
-            .timestamp_key(KEYS[1].public().clone())
-            .signed::<Json>(&KEYS[1])
-            .unwrap();
+        let root = block_on(
+            RootMetadataBuilder::new()
+                .root_key(KEYS[1].public().clone())
+                .snapshot_key(KEYS[1].public().clone())
+                .targets_key(KEYS[1].public().clone())
+                .timestamp_key(KEYS[1].public().clone())
+                .signed::<Json>(&KEYS[1]),
+        )
+        .unwrap();
 
         assert!(tuf.update_root(root).is_err());
     }
 
     #[test]
     fn good_timestamp_update() {
-        let root = RootMetadataBuilder::new()
-            .root_key(KEYS[0].public().clone())
-            .snapshot_key(KEYS[1].public().clone())
-            .targets_key(KEYS[1].public().clone())
-            .timestamp_key(KEYS[1].public().clone())
-            .signed::<Json>(&KEYS[0])
-            .unwrap();
+        let root = block_on(
+            RootMetadataBuilder::new()
+                .root_key(KEYS[0].public().clone())
+                .snapshot_key(KEYS[1].public().clone())
+                .targets_key(KEYS[1].public().clone())
+                .timestamp_key(KEYS[1].public().clone())
+                .signed::<Json>(&KEYS[0]),
+        )
+        .unwrap();
 
         let mut tuf = Tuf::from_root(root).unwrap();
 
-        let snapshot = SnapshotMetadataBuilder::new()
-            .signed::<Json>(&KEYS[1])
-            .unwrap();
+        let snapshot = block_on(SnapshotMetadataBuilder::new().signed::<Json>(&KEYS[1])).unwrap();
 
-        let timestamp =
+        let timestamp = block_on(
             TimestampMetadataBuilder::from_snapshot(&snapshot, &[HashAlgorithm::Sha256])
                 .unwrap()
-                .signed::<Json>(&KEYS[1])
-                .unwrap();
+                .signed::<Json>(&KEYS[1]),
+        )
+        .unwrap();
 
         assert_eq!(tuf.update_timestamp(timestamp.clone()), Ok(true));
 
@@ -757,49 +987,53 @@ mod test {
 
     #[test]
     fn bad_timestamp_update_wrong_key() {
-        let root = RootMetadataBuilder::new()
-            .root_key(KEYS[0].public().clone())
-            .snapshot_key(KEYS[1].public().clone())
-            .targets_key(KEYS[1].public().clone())
-            .timestamp_key(KEYS[1].public().clone())
-            .signed::<Json>(&KEYS[0])
-            .unwrap();
+        let root = block_on(
+            RootMetadataBuilder::new()
+                .root_key(KEYS[0].public().clone())
+                .snapshot_key(KEYS[1].public().clone())
+                .targets_key(KEYS[1].public().clone())
+                .timestamp_key(KEYS[1].public().clone())
+                .signed::<Json>(&KEYS[0]),
+        )
+        .unwrap();
 
         let mut tuf = Tuf::from_root(root).unwrap();
 
-        let snapshot = SnapshotMetadataBuilder::new()
-            .signed::<Json>(&KEYS[1])
-            .unwrap();
+        let snapshot = block_on(SnapshotMetadataBuilder::new().signed::<Json>(&KEYS[1])).unwrap();
 
-        let timestamp =
+        let timestamp = block_on(
             TimestampMetadataBuilder::from_snapshot(&snapshot, &[HashAlgorithm::Sha256])
                 .unwrap()
                 // sign it with the root key
-                .signed::<Json>(&KEYS[0])
-                .unwrap();
+                .signed::<Json>(&KEYS[0]),
+        )
+        .unwrap();
 
         assert!(tuf.update_timestamp(timestamp).is_err())
     }
 
     #[test]
     fn good_snapshot_update() {
-        let root = RootMetadataBuilder::new()
-            .root_key(KEYS[0].public().clone())
-            .snapshot_key(KEYS[1].public().clone())
-            .targets_key(KEYS[2].public().clone())
-            .timestamp_key(KEYS[2].public().clone())
-            .signed::<Json>(&KEYS[0])
-            .unwrap();
+        let root = block_on(
+            RootMetadataBuilder::new()
+                .root_key(KEYS[0].public().clone())
+                .snapshot_key(KEYS[1].public().clone())
+                .targets_key(KEYS[2].public().clone())
+                .timestamp_key(KEYS[2].public().clone())
+                .signed::<Json>(&KEYS[0]),
+        )
+        .unwrap();
 
         let mut tuf = Tuf::from_root(root).unwrap();
 
-        let snapshot = SnapshotMetadataBuilder::new().signed(&KEYS[1]).unwrap();
+        let snapshot = block_on(SnapshotMetadataBuilder::new().signed(&KEYS[1])).unwrap();
 
-        let timestamp =
+        let timestamp = block_on(
             TimestampMetadataBuilder::from_snapshot(&snapshot, &[HashAlgorithm::Sha256])
                 .unwrap()
-                .signed::<Json>(&KEYS[2])
-                .unwrap();
+                .signed::<Json>(&KEYS[2]),
+        )
+        .unwrap();
 
         tuf.update_timestamp(timestamp).unwrap();
 
@@ -811,26 +1045,27 @@ mod test {
 
     #[test]
     fn bad_snapshot_update_wrong_key() {
-        let root = RootMetadataBuilder::new()
-            .root_key(KEYS[0].public().clone())
-            .snapshot_key(KEYS[1].public().clone())
-            .targets_key(KEYS[2].public().clone())
-            .timestamp_key(KEYS[2].public().clone())
-            .signed::<Json>(&KEYS[0])
-            .unwrap();
+        let root = block_on(
+            RootMetadataBuilder::new()
+                .root_key(KEYS[0].public().clone())
+                .snapshot_key(KEYS[1].public().clone())
+                .targets_key(KEYS[2].public().clone())
+                .timestamp_key(KEYS[2].public().clone())
+                .signed::<Json>(&KEYS[0]),
+        )
+        .unwrap();
 
         let mut tuf = Tuf::from_root(root).unwrap();
 
-        let snapshot = SnapshotMetadataBuilder::new()
-            .signed::<Json>(&KEYS[2])
-            .unwrap();
+        let snapshot = block_on(SnapshotMetadataBuilder::new().signed::<Json>(&KEYS[2])).unwrap();
 
-        let timestamp =
+        let timestamp = block_on(
             TimestampMetadataBuilder::from_snapshot(&snapshot, &[HashAlgorithm::Sha256])
                 .unwrap()
                 // sign it with the targets key
-                .signed::<Json>(&KEYS[2])
-                .unwrap();
+                .signed::<Json>(&KEYS[2]),
+        )
+        .unwrap();
 
         tuf.update_timestamp(timestamp).unwrap();
 
@@ -839,62 +1074,72 @@ mod test {
 
     #[test]
     fn bad_snapshot_update_wrong_version() {
-        let root = RootMetadataBuilder::new()
-            .root_key(KEYS[0].public().clone())
-            .snapshot_key(KEYS[1].public().clone())
-            .targets_key(KEYS[2].public().clone())
-            .timestamp_key(KEYS[2].public().clone())
-            .signed::<Json>(&KEYS[0])
-            .unwrap();
+        let root = block_on(
+            RootMetadataBuilder::new()
+                .root_key(KEYS[0].public().clone())
+                .snapshot_key(KEYS[1].public().clone())
+                .targets_key(KEYS[2].public().clone())
+                .timestamp_key(KEYS[2].public().clone())
+                .signed::<Json>(&KEYS[0]),
+        )
+        .unwrap();
 
         let mut tuf = Tuf::from_root(root).unwrap();
 
-        let snapshot = SnapshotMetadataBuilder::new()
-            .version(2)
-            .signed::<Json>(&KEYS[2])
-            .unwrap();
+        let snapshot = block_on(
+            SnapshotMetadataBuilder::new()
+                .version(2)
+                .signed::<Json>(&KEYS[2]),
+        )
+        .unwrap();
 
-        let timestamp =
+        let timestamp = block_on(
             TimestampMetadataBuilder::from_snapshot(&snapshot, &[HashAlgorithm::Sha256])
                 .unwrap()
-                .signed::<Json>(&KEYS[2])
-                .unwrap();
+                .signed::<Json>(&KEYS[2]),
+        )
+        .unwrap();
 
         tuf.update_timestamp(timestamp).unwrap();
 
-        let snapshot = SnapshotMetadataBuilder::new()
-            .version(1)
-            .signed::<Json>(&KEYS[1])
-            .unwrap();
+        let snapshot = block_on(
+            SnapshotMetadataBuilder::new()
+                .version(1)
+                .signed::<Json>(&KEYS[1]),
+        )
+        .unwrap();
 
         assert!(tuf.update_snapshot(snapshot).is_err());
     }
 
     #[test]
     fn good_targets_update() {
-        let root = RootMetadataBuilder::new()
-            .root_key(KEYS[0].public().clone())
-            .snapshot_key(KEYS[1].public().clone())
-            .targets_key(KEYS[2].public().clone())
-            .timestamp_key(KEYS[3].public().clone())
-            .signed::<Json>(&KEYS[0])
-            .unwrap();
-
-        let targets = TargetsMetadataBuilder::new()
-            .signed::<Json>(&KEYS[2])
-            .unwrap();
-
-        let snapshot = SnapshotMetadataBuilder::new()
-            .insert_metadata(&targets, &[HashAlgorithm::Sha256])
-            .unwrap()
-            .signed::<Json>(&KEYS[1])
-            .unwrap();
+        let root = block_on(
+            RootMetadataBuilder::new()
+                .root_key(KEYS[0].public().clone())
+                .snapshot_key(KEYS[1].public().clone())
+                .targets_key(KEYS[2].public().clone())
+                .timestamp_key(KEYS[3].public().clone())
+                .signed::<Json>(&KEYS[0]),
+        )
+        .unwrap();
+
+        let targets = block_on(TargetsMetadataBuilder::new().signed::<Json>(&KEYS[2])).unwrap();
+
+        let snapshot = block_on(
+            SnapshotMetadataBuilder::new()
+                .insert_metadata(&targets, &[HashAlgorithm::Sha256])
+                .unwrap()
+                .signed::<Json>(&KEYS[1]),
+        )
+        .unwrap();
 
-        let timestamp =
+        let timestamp = block_on(
             TimestampMetadataBuilder::from_snapshot(&snapshot, &[HashAlgorithm::Sha256])
                 .unwrap()
-                .signed::<Json>(&KEYS[3])
-                .unwrap();
+                .signed::<Json>(&KEYS[3]),
+        )
+        .unwrap();
 
         let mut tuf = Tuf::from_root(root).unwrap();
 
@@ -909,32 +1154,39 @@ mod test {
 
     #[test]
     fn bad_targets_update_wrong_key() {
-        let root = RootMetadataBuilder::new()
-            .root_key(KEYS[0].public().clone())
-            .snapshot_key(KEYS[1].public().clone())
-            .targets_key(KEYS[2].public().clone())
-            .timestamp_key(KEYS[3].public().clone())
-            .signed::<Json>(&KEYS[0])
-            .unwrap();
+        let root = block_on(
+            RootMetadataBuilder::new()
+                .root_key(KEYS[0].public().clone())
+                .snapshot_key(KEYS[1].public().clone())
+                .targets_key(KEYS[2].public().clone())
+                .timestamp_key(KEYS[3].public().clone())
+                .signed::<Json>(&KEYS[0]),
+        )
+        .unwrap();
 
         let mut tuf = Tuf::from_root(root).unwrap();
 
-        let targets = TargetsMetadataBuilder::new()
-            // sign it with the timestamp key
-            .signed::<Json>(&KEYS[3])
-            .unwrap();
+        let targets = block_on(
+            TargetsMetadataBuilder::new()
+                // sign it with the timestamp key
+                .signed::<Json>(&KEYS[3]),
+        )
+        .unwrap();
 
-        let snapshot = SnapshotMetadataBuilder::new()
-            .insert_metadata(&targets, &[HashAlgorithm::Sha256])
-            .unwrap()
-            .signed::<Json>(&KEYS[1])
-            .unwrap();
+        let snapshot = block_on(
+            SnapshotMetadataBuilder::new()
+                .insert_metadata(&targets, &[HashAlgorithm::Sha256])
+                .unwrap()
+                .signed::<Json>(&KEYS[1]),
+        )
+        .unwrap();
 
-        let timestamp =
+        let timestamp = block_on(
             TimestampMetadataBuilder::from_snapshot(&snapshot, &[HashAlgorithm::Sha256])
                 .unwrap()
-                .signed::<Json>(&KEYS[3])
-                .unwrap();
+                .signed::<Json>(&KEYS[3]),
+        )
+        .unwrap();
 
         tuf.update_timestamp(timestamp).unwrap();
         tuf.update_snapshot(snapshot).unwrap();
@@ -944,40 +1196,49 @@ mod test {
 
     #[test]
     fn bad_targets_update_wrong_version() {
-        let root = RootMetadataBuilder::new()
-            .root_key(KEYS[0].public().clone())
-            .snapshot_key(KEYS[1].public().clone())
-            .targets_key(KEYS[2].public().clone())
-            .timestamp_key(KEYS[3].public().clone())
-            .signed::<Json>(&KEYS[0])
-            .unwrap();
+        let root = block_on(
+            RootMetadataBuilder::new()
+                .root_key(KEYS[0].public().clone())
+                .snapshot_key(KEYS[1].public().clone())
+                .targets_key(KEYS[2].public().clone())
+                .timestamp_key(KEYS[3].public().clone())
+                .signed::<Json>(&KEYS[0]),
+        )
+        .unwrap();
 
         let mut tuf = Tuf::from_root(root).unwrap();
 
-        let targets = TargetsMetadataBuilder::new()
-            .version(2)
-            .signed::<Json>(&KEYS[2])
-            .unwrap();
+        let targets = block_on(
+            TargetsMetadataBuilder::new()
+                .version(2)
+                .signed::<Json>(&KEYS[2]),
+        )
+        .unwrap();
 
-        let snapshot = SnapshotMetadataBuilder::new()
-            .insert_metadata(&targets, &[HashAlgorithm::Sha256])
-            .unwrap()
-            .signed::<Json>(&KEYS[1])
-            .unwrap();
+        let snapshot = block_on(
+            SnapshotMetadataBuilder::new()
+                .insert_metadata(&targets, &[HashAlgorithm::Sha256])
+                .unwrap()
+                .signed::<Json>(&KEYS[1]),
+        )
+        .unwrap();
 
-        let timestamp =
+        let timestamp = block_on(
             TimestampMetadataBuilder::from_snapshot(&snapshot, &[HashAlgorithm::Sha256])
                 .unwrap()
-                .signed::<Json>(&KEYS[3])
-                .unwrap();
+                .signed::<Json>(&KEYS[3]),
+        )
+        .unwrap();
 
         tuf.update_timestamp(timestamp).unwrap();
         tuf.update_snapshot(snapshot).unwrap();
 
-        let targets = TargetsMetadataBuilder::new()
-            .version(1)
-            .signed::<Json>(&KEYS[2])
-            .unwrap();
+        let targets = block_on(
+            TargetsMetadataBuilder::new()
+                .version(1)
+                .signed::<Json>(&KEYS[2]),
+        )
+        .unwrap();
 
         assert!(tuf.update_targets(targets).is_err());
     }
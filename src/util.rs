@@ -1,32 +1,224 @@
-use chrono::offset::Utc;
-use chrono::DateTime;
+use futures::compat::Future01CompatExt;
+use futures::future::{self, FutureExt};
 use futures::io::AsyncRead;
 use futures::task::LocalWaker;
-use futures::{try_ready, Poll};
-use ring::digest::{self, SHA256, SHA512};
+use futures::{Future, Poll};
+use futures_timer::Delay;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{self, Debug};
 use std::io::{self, ErrorKind};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use crate::crypto::provider::{default_provider, DigestContext};
 use crate::crypto::{HashAlgorithm, HashValue};
 use crate::error::Error;
 use crate::Result;
 
+/// A source of the current time, used to measure how fast data is downloaded.
+pub trait TimeSource: Send + Sync {
+    /// The current time.
+    fn now(&self) -> Instant;
+}
+
+/// A `TimeSource` that reads the system's monotonic clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemTimeSource;
+
+impl TimeSource for SystemTimeSource {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Limits on how slowly metadata and targets may be downloaded, which protect against slow
+/// retrieval attacks where a server sends data too slowly for a download to ever finish.
+///
+/// Once the grace period has passed, the average rate over the most recent window must stay above
+/// the minimum, so a server can't send quickly at first and then trickle data forever. Separately,
+/// a download can be capped to a total amount of time. Both are checked whenever data arrives, and
+/// at least once a second while waiting for it.
+#[derive(Clone)]
+pub struct SlowRetrievalPolicy {
+    min_bytes_per_second: u32,
+    grace_period: Duration,
+    window: Duration,
+    max_duration: Option<Duration>,
+    time_source: Arc<dyn TimeSource>,
+}
+
+impl SlowRetrievalPolicy {
+    /// Create a new `SlowRetrievalPolicy` requiring at least `min_bytes_per_second`, or no
+    /// minimum if it is 0. The grace period and window default to 30 seconds, and there is no
+    /// total time limit.
+    pub fn new(min_bytes_per_second: u32) -> Self {
+        SlowRetrievalPolicy {
+            min_bytes_per_second,
+            grace_period: Duration::from_secs(30),
+            window: Duration::from_secs(30),
+            max_duration: None,
+            time_source: Arc::new(SystemTimeSource),
+        }
+    }
+
+    /// Set how long a download may run before its rate is checked.
+    pub fn grace_period(mut self, grace_period: Duration) -> Self {
+        self.grace_period = grace_period;
+        self
+    }
+
+    /// Set the length of the sliding window the rate is averaged over.
+    pub fn window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Set the total time a download may take, however fast it is.
+    pub fn max_duration(mut self, max_duration: Duration) -> Self {
+        self.max_duration = Some(max_duration);
+        self
+    }
+
+    /// Set the `TimeSource` used to measure downloads. Defaults to `SystemTimeSource`.
+    pub fn time_source(mut self, time_source: Arc<dyn TimeSource>) -> Self {
+        self.time_source = time_source;
+        self
+    }
+
+    /// The minimum average bytes per second, or 0 if there is none.
+    pub fn min_bytes_per_second(&self) -> u32 {
+        self.min_bytes_per_second
+    }
+
+    /// Set the minimum average bytes per second, or 0 for none.
+    pub(crate) fn set_min_bytes_per_second(&mut self, min_bytes_per_second: u32) {
+        self.min_bytes_per_second = min_bytes_per_second;
+    }
+
+    /// The longest a download of at most `size` bytes may take while following this policy: the
+    /// grace period followed by `size` bytes at the minimum rate, or the total time limit if that
+    /// is shorter. `None` if it may take forever.
+    pub(crate) fn time_limit(&self, size: Option<u64>) -> Option<Duration> {
+        let min = u64::from(self.min_bytes_per_second);
+        let rate_limit = match size {
+            Some(size) if min > 0 => {
+                Some(self.grace_period + Duration::from_secs((size + min - 1) / min))
+            }
+            _ => None,
+        };
+        match (rate_limit, self.max_duration) {
+            (Some(rate_limit), Some(max_duration)) => Some(rate_limit.min(max_duration)),
+            (rate_limit, max_duration) => rate_limit.or(max_duration),
+        }
+    }
+}
+
+impl Debug for SlowRetrievalPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SlowRetrievalPolicy")
+            .field("min_bytes_per_second", &self.min_bytes_per_second)
+            .field("grace_period", &self.grace_period)
+            .field("window", &self.window)
+            .field("max_duration", &self.max_duration)
+            .finish()
+    }
+}
+
+/// Policies are equal when their limits are, whichever `TimeSource` they measure time with.
+impl PartialEq for SlowRetrievalPolicy {
+    fn eq(&self, other: &Self) -> bool {
+        self.min_bytes_per_second == other.min_bytes_per_second
+            && self.grace_period == other.grace_period
+            && self.window == other.window
+            && self.max_duration == other.max_duration
+    }
+}
+
+/// Why a `SafeReader` aborted a read. This is the inner error of the `io::Error` it returns, which
+/// is `ErrorKind::InvalidData` if the data was wrong and `ErrorKind::TimedOut` if it was too slow.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ReadAborted {
+    /// More bytes were read than allowed.
+    TooLarge,
+    /// The data did not match a hash.
+    HashMismatch,
+    /// The data arrived more slowly than the `SlowRetrievalPolicy` allows.
+    TooSlow,
+    /// The read took longer than the `SlowRetrievalPolicy` allows.
+    TookTooLong,
+}
+
+impl ReadAborted {
+    /// Why `err` was returned by a `SafeReader`, if it was.
+    pub(crate) fn of(err: &io::Error) -> Option<Self> {
+        err.get_ref()
+            .and_then(|err| err.downcast_ref::<ReadAborted>())
+            .cloned()
+    }
+
+    fn message(self) -> &'static str {
+        match self {
+            ReadAborted::TooLarge => "Read exceeded the maximum allowed bytes.",
+            ReadAborted::HashMismatch => "Calculated hash did not match the required hash.",
+            ReadAborted::TooSlow => "Read aborted. Bitrate too low.",
+            ReadAborted::TookTooLong => "Read aborted. Took too long.",
+        }
+    }
+}
+
+// This is what `io::Error` shows for the inner error, so it is the message rather than the name.
+impl Debug for ReadAborted {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl fmt::Display for ReadAborted {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl ::std::error::Error for ReadAborted {
+    fn description(&self) -> &str {
+        self.message()
+    }
+}
+
+impl From<ReadAborted> for io::Error {
+    fn from(reason: ReadAborted) -> io::Error {
+        let kind = match reason {
+            ReadAborted::TooLarge | ReadAborted::HashMismatch => ErrorKind::InvalidData,
+            ReadAborted::TooSlow | ReadAborted::TookTooLong => ErrorKind::TimedOut,
+        };
+        io::Error::new(kind, reason)
+    }
+}
+
 /// Wrapper to verify a byte stream as it is read.
 ///
 /// Wraps a `Read` to ensure that the consumer can't read more than a capped maximum number of
-/// bytes. Also, this ensures that a minimum bitrate and returns an `Err` if it is not. Finally,
-/// when the underlying `Read` is fully consumed, the hash of the data is optionally calculated. If
-/// the calculated hash does not match the given hash, it will return an `Err`. Consumers of a
-/// `SafeReader` should purge and untrust all read bytes if this ever returns an `Err`.
+/// bytes. Also, this enforces a `SlowRetrievalPolicy` and returns an `Err` if it is broken.
+/// Finally, when the underlying `Read` is fully consumed, the hashes of the data are optionally
+/// calculated. If any calculated hash does not match the given hash, it will return an `Err`.
+/// Consumers of a `SafeReader` should purge and untrust all read bytes if this ever returns an
+/// `Err`.
 ///
 /// It is **critical** that none of the bytes from this struct are used until it has been fully
 /// consumed as the data is untrusted.
 pub struct SafeReader<R: AsyncRead> {
     inner: R,
     max_size: u64,
-    min_bytes_per_second: u32,
-    hasher: Option<(digest::Context, HashValue)>,
-    start_time: Option<DateTime<Utc>>,
+    policy: SlowRetrievalPolicy,
+    hashers: Vec<(Box<dyn DigestContext>, HashValue)>,
+    start_time: Option<Instant>,
     bytes_read: u64,
+    /// When reads finished and how many bytes had been read by then, oldest first. Only the
+    /// newest sample older than the window is kept, as the baseline for the rate.
+    samples: VecDeque<(Instant, u64)>,
+    /// Fires when the policy should be checked again while waiting for data.
+    timer: Option<Box<dyn Future<Output = ()> + Send + Unpin>>,
 }
 
 impl<R: AsyncRead> SafeReader<R> {
@@ -39,53 +231,155 @@ impl<R: AsyncRead> SafeReader<R> {
     pub fn new(
         read: R,
         max_size: u64,
-        min_bytes_per_second: u32,
+        policy: SlowRetrievalPolicy,
         hash_data: Option<(&HashAlgorithm, HashValue)>,
     ) -> Result<Self> {
-        let hasher = match hash_data {
-            Some((alg, value)) => {
-                let ctx = match *alg {
-                    HashAlgorithm::Sha256 => digest::Context::new(&SHA256),
-                    HashAlgorithm::Sha512 => digest::Context::new(&SHA512),
-                    HashAlgorithm::Unknown(ref s) => {
-                        return Err(Error::IllegalArgument(format!(
-                            "Unknown hash algorithm: {}",
-                            s
-                        )));
-                    }
-                };
-                Some((ctx, value))
-            }
-            None => None,
+        let hashers = match hash_data {
+            Some((alg, value)) => vec![(default_provider().digest(alg)?, value)],
+            None => Vec::new(),
         };
 
         Ok(SafeReader {
             inner: read,
             max_size,
-            min_bytes_per_second,
-            hasher,
+            policy,
+            hashers,
             start_time: None,
             bytes_read: 0,
+            samples: VecDeque::new(),
+            timer: None,
         })
     }
+
+    /// Create a new `SafeReader` that checks every hash in `hashes` in a single pass over the data.
+    ///
+    /// Algorithms that are not supported are skipped, but at least one algorithm must be
+    /// supported.
+    pub fn new_with_hashes(
+        read: R,
+        max_size: u64,
+        policy: SlowRetrievalPolicy,
+        hashes: &HashMap<HashAlgorithm, HashValue>,
+    ) -> Result<Self> {
+        let hashers = hashes
+            .iter()
+            .filter_map(|(alg, value)| match default_provider().digest(alg) {
+                Ok(context) => Some((context, value.clone())),
+                Err(_) => None,
+            })
+            .collect::<Vec<_>>();
+
+        if hashers.is_empty() {
+            return Err(Error::NoSupportedHashAlgorithm);
+        }
+
+        Ok(SafeReader {
+            inner: read,
+            max_size,
+            policy,
+            hashers,
+            start_time: None,
+            bytes_read: 0,
+            samples: VecDeque::new(),
+            timer: None,
+        })
+    }
+
+    /// Check that the download is not breaking the `SlowRetrievalPolicy`, now that `bytes_read`
+    /// bytes have been read.
+    fn check_rate(&mut self) -> io::Result<()> {
+        let now = self.policy.time_source.now();
+        let start_time = self.start_time.unwrap_or(now);
+        let elapsed = now - start_time;
+
+        if let Some(max_duration) = self.policy.max_duration {
+            if elapsed > max_duration {
+                return Err(ReadAborted::TookTooLong.into());
+            }
+        }
+
+        self.samples.push_back((now, self.bytes_read));
+        while self.samples.len() > 1 && now - self.samples[1].0 >= self.policy.window {
+            let _ = self.samples.pop_front();
+        }
+
+        if self.policy.min_bytes_per_second == 0 || elapsed < self.policy.grace_period {
+            return Ok(());
+        }
+
+        // Until a whole window has passed, the rate is measured from the start.
+        let (window, window_start_bytes) = if elapsed < self.policy.window {
+            (elapsed, 0)
+        } else {
+            (self.policy.window, self.samples[0].1)
+        };
+        let window_secs = window.as_secs() as f64 + f64::from(window.subsec_nanos()) / 1e9;
+        if window_secs > 0.0
+            && ((self.bytes_read - window_start_bytes) as f64) / window_secs
+                < f64::from(self.policy.min_bytes_per_second)
+        {
+            return Err(ReadAborted::TooSlow.into());
+        }
+
+        Ok(())
+    }
+
+    /// Check the policy whenever the timer fires while waiting for data, so that a download that
+    /// stalls completely still breaks it.
+    fn poll_timer(&mut self, lw: &LocalWaker) -> io::Result<()> {
+        if self.policy.min_bytes_per_second == 0 && self.policy.max_duration.is_none() {
+            return Ok(());
+        }
+
+        loop {
+            if self.timer.is_none() {
+                let mut interval = Duration::from_secs(1);
+                if let (Some(max_duration), Some(start_time)) =
+                    (self.policy.max_duration, self.start_time)
+                {
+                    let elapsed = self.policy.time_source.now() - start_time;
+                    if max_duration > elapsed {
+                        interval = interval.min(max_duration - elapsed);
+                    }
+                }
+                self.timer = Some(Box::new(sleep(interval)));
+            }
+
+            if let Some(ref mut timer) = self.timer {
+                if let Poll::Pending = Pin::new(&mut **timer).poll(lw) {
+                    return Ok(());
+                }
+            }
+            self.timer = None;
+            self.check_rate()?;
+        }
+    }
 }
 
 impl<R: AsyncRead> AsyncRead for SafeReader<R> {
     fn poll_read(&mut self, lw: &LocalWaker, buf: &mut [u8]) -> Poll<io::Result<usize>> {
-        let read_bytes = try_ready!(self.inner.poll_read(lw, buf));
-
         if self.start_time.is_none() {
-            self.start_time = Some(Utc::now())
+            let now = self.policy.time_source.now();
+            self.start_time = Some(now);
+            self.samples.push_back((now, 0));
         }
 
+        let read_bytes = match self.inner.poll_read(lw, buf) {
+            Poll::Ready(Ok(read_bytes)) => read_bytes,
+            Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+            Poll::Pending => {
+                return match self.poll_timer(lw) {
+                    Ok(()) => Poll::Pending,
+                    Err(err) => Poll::Ready(Err(err)),
+                };
+            }
+        };
+
         if read_bytes == 0 {
-            if let Some((context, expected_hash)) = self.hasher.take() {
+            for (context, expected_hash) in self.hashers.drain(..) {
                 let generated_hash = context.finish();
-                if generated_hash.as_ref() != expected_hash.value() {
-                    return Poll::Ready(Err(io::Error::new(
-                        ErrorKind::InvalidData,
-                        "Calculated hash did not match the required hash.",
-                    )));
+                if generated_hash != expected_hash.value() {
+                    return Poll::Ready(Err(ReadAborted::HashMismatch.into()));
                 }
             }
 
@@ -95,27 +389,15 @@ impl<R: AsyncRead> AsyncRead for SafeReader<R> {
         match self.bytes_read.checked_add(read_bytes as u64) {
             Some(sum) if sum <= self.max_size => self.bytes_read = sum,
             _ => {
-                return Poll::Ready(Err(io::Error::new(
-                    ErrorKind::InvalidData,
-                    "Read exceeded the maximum allowed bytes.",
-                )));
+                return Poll::Ready(Err(ReadAborted::TooLarge.into()));
             }
         }
 
-        let duration = Utc::now().signed_duration_since(self.start_time.unwrap());
-        // 30 second grace period before we start checking the bitrate
-        if duration.num_seconds() >= 30 {
-            if self.bytes_read as f32 / (duration.num_seconds() as f32)
-                < self.min_bytes_per_second as f32
-            {
-                return Poll::Ready(Err(io::Error::new(
-                    ErrorKind::TimedOut,
-                    "Read aborted. Bitrate too low.",
-                )));
-            }
+        if let Err(err) = self.check_rate() {
+            return Poll::Ready(Err(err));
         }
 
-        if let Some((ref mut context, _)) = self.hasher {
+        for (context, _) in self.hashers.iter_mut() {
             context.update(&buf[..(read_bytes)]);
         }
 
@@ -123,18 +405,97 @@ impl<R: AsyncRead> AsyncRead for SafeReader<R> {
     }
 }
 
+/// A future that finishes after `duration`. The timer only fails if its background thread has
+/// gone away, in which case the future finishes early.
+pub(crate) fn sleep(duration: Duration) -> impl Future<Output = ()> + Unpin {
+    Delay::new(duration).compat().map(|_| ())
+}
+
+/// Wait at most `duration` for `fut`, returning `None` if the time runs out first.
+pub(crate) fn timeout<F>(mut fut: F, duration: Duration) -> impl Future<Output = Option<F::Output>>
+where
+    F: Future + Unpin,
+{
+    let mut timer = sleep(duration);
+    future::poll_fn(move |lw| {
+        if let Poll::Ready(output) = Pin::new(&mut fut).poll(lw) {
+            return Poll::Ready(Some(output));
+        }
+        match Pin::new(&mut timer).poll(lw) {
+            Poll::Ready(()) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    })
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use futures::executor::block_on;
     use futures::io::AsyncReadExt;
+    use std::sync::Mutex;
+
+    /// A `TimeSource` that only moves when it is told to.
+    struct FakeTime(Mutex<Instant>);
+
+    impl TimeSource for FakeTime {
+        fn now(&self) -> Instant {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    /// Yields the given chunks, advancing `time` by the paired delay before each one arrives.
+    struct TrickleReader {
+        time: Arc<FakeTime>,
+        chunks: VecDeque<(Duration, Vec<u8>)>,
+    }
+
+    impl TrickleReader {
+        fn new(time: &Arc<FakeTime>, chunks: Vec<(Duration, Vec<u8>)>) -> Self {
+            TrickleReader {
+                time: time.clone(),
+                chunks: chunks.into_iter().collect(),
+            }
+        }
+    }
+
+    impl AsyncRead for TrickleReader {
+        fn poll_read(&mut self, _: &LocalWaker, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+            let (delay, mut chunk) = match self.chunks.pop_front() {
+                Some(next) => next,
+                None => return Poll::Ready(Ok(0)),
+            };
+            *self.time.0.lock().unwrap() += delay;
+
+            let len = chunk.len().min(buf.len());
+            buf[..len].copy_from_slice(&chunk[..len]);
+            if len < chunk.len() {
+                self.chunks
+                    .push_front((Duration::from_secs(0), chunk.split_off(len)));
+            }
+            Poll::Ready(Ok(len))
+        }
+    }
+
+    fn read_trickle(policy: SlowRetrievalPolicy, reader: TrickleReader) -> io::Result<Vec<u8>> {
+        block_on(
+            async {
+                let mut reader = SafeReader::new(reader, ::std::u64::MAX, policy, None).unwrap();
+                let mut buf = Vec::new();
+                await!(reader.read_to_end(&mut buf))?;
+                Ok(buf)
+            },
+        )
+    }
 
     #[test]
     fn valid_read() {
         block_on(
             async {
                 let bytes: &[u8] = &[0x00, 0x01, 0x02, 0x03];
-                let mut reader = SafeReader::new(bytes, bytes.len() as u64, 0, None).unwrap();
+                let mut reader =
+                    SafeReader::new(bytes, bytes.len() as u64, SlowRetrievalPolicy::new(0), None)
+                        .unwrap();
                 let mut buf = Vec::new();
                 assert!(await!(reader.read_to_end(&mut buf)).is_ok());
                 assert_eq!(buf, bytes);
@@ -147,7 +508,9 @@ mod test {
         block_on(
             async {
                 let bytes: &[u8] = &[0x00; 64 * 1024];
-                let mut reader = SafeReader::new(bytes, bytes.len() as u64, 0, None).unwrap();
+                let mut reader =
+                    SafeReader::new(bytes, bytes.len() as u64, SlowRetrievalPolicy::new(0), None)
+                        .unwrap();
                 let mut buf = Vec::new();
                 assert!(await!(reader.read_to_end(&mut buf)).is_ok());
                 assert_eq!(buf, bytes);
@@ -160,7 +523,13 @@ mod test {
         block_on(
             async {
                 let bytes: &[u8] = &[0x00, 0x01, 0x02, 0x03];
-                let mut reader = SafeReader::new(bytes, (bytes.len() as u64) + 1, 0, None).unwrap();
+                let mut reader = SafeReader::new(
+                    bytes,
+                    (bytes.len() as u64) + 1,
+                    SlowRetrievalPolicy::new(0),
+                    None,
+                )
+                .unwrap();
                 let mut buf = Vec::new();
                 assert!(await!(reader.read_to_end(&mut buf)).is_ok());
                 assert_eq!(buf, bytes);
@@ -173,7 +542,13 @@ mod test {
         block_on(
             async {
                 let bytes: &[u8] = &[0x00, 0x01, 0x02, 0x03];
-                let mut reader = SafeReader::new(bytes, (bytes.len() as u64) - 1, 0, None).unwrap();
+                let mut reader = SafeReader::new(
+                    bytes,
+                    (bytes.len() as u64) - 1,
+                    SlowRetrievalPolicy::new(0),
+                    None,
+                )
+                .unwrap();
                 let mut buf = Vec::new();
                 assert!(await!(reader.read_to_end(&mut buf)).is_err());
             },
@@ -185,7 +560,13 @@ mod test {
         block_on(
             async {
                 let bytes: &[u8] = &[0x00; 64 * 1024];
-                let mut reader = SafeReader::new(bytes, (bytes.len() as u64) - 1, 0, None).unwrap();
+                let mut reader = SafeReader::new(
+                    bytes,
+                    (bytes.len() as u64) - 1,
+                    SlowRetrievalPolicy::new(0),
+                    None,
+                )
+                .unwrap();
                 let mut buf = Vec::new();
                 assert!(await!(reader.read_to_end(&mut buf)).is_err());
             },
@@ -197,13 +578,13 @@ mod test {
         block_on(
             async {
                 let bytes: &[u8] = &[0x00, 0x01, 0x02, 0x03];
-                let mut context = digest::Context::new(&SHA256);
+                let mut context = default_provider().digest(&HashAlgorithm::Sha256).unwrap();
                 context.update(&bytes);
-                let hash_value = HashValue::new(context.finish().as_ref().to_vec());
+                let hash_value = HashValue::new(context.finish());
                 let mut reader = SafeReader::new(
                     bytes,
                     bytes.len() as u64,
-                    0,
+                    SlowRetrievalPolicy::new(0),
                     Some((&HashAlgorithm::Sha256, hash_value)),
                 )
                 .unwrap();
@@ -219,14 +600,14 @@ mod test {
         block_on(
             async {
                 let bytes: &[u8] = &[0x00, 0x01, 0x02, 0x03];
-                let mut context = digest::Context::new(&SHA256);
+                let mut context = default_provider().digest(&HashAlgorithm::Sha256).unwrap();
                 context.update(&bytes);
                 context.update(&[0xFF]); // evil bytes
-                let hash_value = HashValue::new(context.finish().as_ref().to_vec());
+                let hash_value = HashValue::new(context.finish());
                 let mut reader = SafeReader::new(
                     bytes,
                     bytes.len() as u64,
-                    0,
+                    SlowRetrievalPolicy::new(0),
                     Some((&HashAlgorithm::Sha256, hash_value)),
                 )
                 .unwrap();
@@ -241,13 +622,13 @@ mod test {
         block_on(
             async {
                 let bytes: &[u8] = &[0x00; 64 * 1024];
-                let mut context = digest::Context::new(&SHA256);
+                let mut context = default_provider().digest(&HashAlgorithm::Sha256).unwrap();
                 context.update(&bytes);
-                let hash_value = HashValue::new(context.finish().as_ref().to_vec());
+                let hash_value = HashValue::new(context.finish());
                 let mut reader = SafeReader::new(
                     bytes,
                     bytes.len() as u64,
-                    0,
+                    SlowRetrievalPolicy::new(0),
                     Some((&HashAlgorithm::Sha256, hash_value)),
                 )
                 .unwrap();
@@ -263,14 +644,14 @@ mod test {
         block_on(
             async {
                 let bytes: &[u8] = &[0x00; 64 * 1024];
-                let mut context = digest::Context::new(&SHA256);
+                let mut context = default_provider().digest(&HashAlgorithm::Sha256).unwrap();
                 context.update(&bytes);
                 context.update(&[0xFF]); // evil bytes
-                let hash_value = HashValue::new(context.finish().as_ref().to_vec());
+                let hash_value = HashValue::new(context.finish());
                 let mut reader = SafeReader::new(
                     bytes,
                     bytes.len() as u64,
-                    0,
+                    SlowRetrievalPolicy::new(0),
                     Some((&HashAlgorithm::Sha256, hash_value)),
                 )
                 .unwrap();
@@ -279,4 +660,176 @@ mod test {
             },
         )
     }
+
+    #[test]
+    fn read_checks_all_hashes() {
+        block_on(
+            async {
+                let bytes: &[u8] = &[0x00, 0x01, 0x02, 0x03];
+                let mut hashes = HashMap::new();
+                for alg in &[HashAlgorithm::Sha256, HashAlgorithm::Sha512] {
+                    let mut context = default_provider().digest(alg).unwrap();
+                    context.update(&bytes);
+                    let _ = hashes.insert(alg.clone(), HashValue::new(context.finish()));
+                }
+                let _ = hashes.insert(
+                    HashAlgorithm::Unknown("md5".into()),
+                    HashValue::new(vec![0x00]),
+                );
+
+                let mut reader = SafeReader::new_with_hashes(
+                    bytes,
+                    bytes.len() as u64,
+                    SlowRetrievalPolicy::new(0),
+                    &hashes,
+                )
+                .unwrap();
+                let mut buf = Vec::new();
+                assert!(await!(reader.read_to_end(&mut buf)).is_ok());
+                assert_eq!(buf, bytes);
+
+                // a single bad hash is enough to reject the data
+                let _ = hashes.insert(HashAlgorithm::Sha512, HashValue::new(vec![0x00; 64]));
+                let mut reader = SafeReader::new_with_hashes(
+                    bytes,
+                    bytes.len() as u64,
+                    SlowRetrievalPolicy::new(0),
+                    &hashes,
+                )
+                .unwrap();
+                let mut buf = Vec::new();
+                assert!(await!(reader.read_to_end(&mut buf)).is_err());
+
+                let mut unsupported = HashMap::new();
+                let _ = unsupported.insert(
+                    HashAlgorithm::Unknown("md5".into()),
+                    HashValue::new(vec![0x00]),
+                );
+                assert!(SafeReader::new_with_hashes(
+                    bytes,
+                    bytes.len() as u64,
+                    SlowRetrievalPolicy::new(0),
+                    &unsupported
+                )
+                .is_err());
+            },
+        )
+    }
+
+    #[test]
+    fn steady_rate_is_allowed() {
+        let time = Arc::new(FakeTime(Mutex::new(Instant::now())));
+        let policy = SlowRetrievalPolicy::new(10)
+            .grace_period(Duration::from_secs(10))
+            .window(Duration::from_secs(10))
+            .time_source(time.clone());
+
+        // 20 bytes per second for 50 seconds
+        let chunks = vec![(Duration::from_millis(500), vec![0x00; 10]); 100];
+        let buf = read_trickle(policy, TrickleReader::new(&time, chunks)).unwrap();
+        assert_eq!(buf.len(), 1000);
+    }
+
+    #[test]
+    fn trickle_after_fast_start_is_caught() {
+        let time = Arc::new(FakeTime(Mutex::new(Instant::now())));
+        let policy = SlowRetrievalPolicy::new(10)
+            .grace_period(Duration::from_secs(10))
+            .window(Duration::from_secs(10))
+            .time_source(time.clone());
+
+        // The average since the start stays above 10 bytes per second for the whole read, but the
+        // rate over the last 10 seconds drops far below it.
+        let mut chunks = vec![(Duration::from_millis(10), vec![0x00; 10]); 100];
+        chunks.extend(vec![(Duration::from_secs(5), vec![0x00]); 20]);
+        let err = read_trickle(policy, TrickleReader::new(&time, chunks)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+    }
+
+    #[test]
+    fn slow_start_is_allowed_during_grace_period() {
+        let time = Arc::new(FakeTime(Mutex::new(Instant::now())));
+        let policy = SlowRetrievalPolicy::new(10)
+            .grace_period(Duration::from_secs(10))
+            .window(Duration::from_secs(10))
+            .time_source(time.clone());
+
+        let mut chunks = vec![(Duration::from_secs(9), vec![0x00])];
+        chunks.extend(vec![(Duration::from_millis(10), vec![0x00; 10]); 100]);
+        let buf = read_trickle(policy, TrickleReader::new(&time, chunks)).unwrap();
+        assert_eq!(buf.len(), 1001);
+    }
+
+    #[test]
+    fn read_over_max_duration_is_caught() {
+        let time = Arc::new(FakeTime(Mutex::new(Instant::now())));
+        let policy = SlowRetrievalPolicy::new(10)
+            .max_duration(Duration::from_secs(60))
+            .time_source(time.clone());
+
+        // 1000 bytes per second, for too long
+        let chunks = vec![(Duration::from_secs(1), vec![0x00; 1000]); 61];
+        let err = read_trickle(policy.clone(), TrickleReader::new(&time, chunks)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+
+        let chunks = vec![(Duration::from_secs(1), vec![0x00; 1000]); 60];
+        assert!(read_trickle(policy, TrickleReader::new(&time, chunks)).is_ok());
+    }
+
+    /// Never has any data, nor says when it will.
+    struct StalledReader;
+
+    impl AsyncRead for StalledReader {
+        fn poll_read(&mut self, _: &LocalWaker, _: &mut [u8]) -> Poll<io::Result<usize>> {
+            Poll::Pending
+        }
+    }
+
+    #[test]
+    fn stalled_read_is_caught() {
+        let policy = SlowRetrievalPolicy::new(0).max_duration(Duration::from_millis(50));
+        let err = block_on(
+            async {
+                let mut reader =
+                    SafeReader::new(StalledReader, ::std::u64::MAX, policy, None).unwrap();
+                let mut buf = Vec::new();
+                await!(reader.read_to_end(&mut buf))
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+    }
+
+    #[test]
+    fn timeout_races_the_timer() {
+        let finished = block_on(timeout(future::ready(1), Duration::from_secs(10)));
+        assert_eq!(finished, Some(1));
+
+        let stalled = block_on(timeout(future::empty::<()>(), Duration::from_millis(10)));
+        assert_eq!(stalled, None);
+    }
+
+    #[test]
+    fn time_limit() {
+        let policy = SlowRetrievalPolicy::new(10).grace_period(Duration::from_secs(5));
+        assert_eq!(policy.time_limit(None), None);
+        assert_eq!(policy.time_limit(Some(101)), Some(Duration::from_secs(16)));
+
+        let policy = policy.max_duration(Duration::from_secs(10));
+        assert_eq!(policy.time_limit(None), Some(Duration::from_secs(10)));
+        assert_eq!(policy.time_limit(Some(101)), Some(Duration::from_secs(10)));
+        assert_eq!(policy.time_limit(Some(10)), Some(Duration::from_secs(6)));
+
+        assert_eq!(SlowRetrievalPolicy::new(0).time_limit(Some(101)), None);
+    }
+
+    #[test]
+    fn policies_are_equal_whatever_their_time_source() {
+        let time = Arc::new(FakeTime(Mutex::new(Instant::now())));
+        assert_eq!(
+            SlowRetrievalPolicy::new(10).time_source(time),
+            SlowRetrievalPolicy::new(10)
+        );
+        assert_ne!(SlowRetrievalPolicy::new(10), SlowRetrievalPolicy::new(20));
+    }
 }
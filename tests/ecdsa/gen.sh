@@ -0,0 +1,36 @@
+#!/bin/bash
+set -eux
+
+cd "$(dirname "$0")"
+
+for curve in P-256 P-384; do
+    case "$curve" in
+        P-256) key="ecdsa-p256" ;;
+        P-384) key="ecdsa-p384" ;;
+    esac
+
+    pk8="$key.pk8.der"
+    spki="$key.spki.der"
+    key="$key.der"
+
+    if [ ! -f "$key" ]; then
+        openssl genpkey -algorithm EC \
+                        -pkeyopt "ec_paramgen_curve:$curve" \
+                        -pkeyopt ec_param_enc:named_curve \
+                        -outform der \
+                        -out "$key"
+    fi
+
+    openssl pkey -in "$key" \
+                 -inform der \
+                 -pubout \
+                 -outform der \
+                 -out "$spki"
+
+    openssl pkcs8 -topk8 \
+                  -inform der \
+                  -in "$key" \
+                  -outform der \
+                  -out "$pk8" \
+                  -nocrypt
+done
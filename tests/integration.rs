@@ -1,3 +1,4 @@
+use futures::executor::block_on;
 use maplit::hashset;
 use tuf::crypto::{HashAlgorithm, PrivateKey, SignatureScheme};
 use tuf::interchange::Json;
@@ -24,34 +25,40 @@ fn simple_delegation() {
 
     //// build the root ////
 
-    let root = RootMetadataBuilder::new()
-        .root_key(root_key.public().clone())
-        .snapshot_key(snapshot_key.public().clone())
-        .targets_key(targets_key.public().clone())
-        .timestamp_key(timestamp_key.public().clone())
-        .signed::<Json>(&root_key)
-        .unwrap();
+    let root = block_on(
+        RootMetadataBuilder::new()
+            .root_key(root_key.public().clone())
+            .snapshot_key(snapshot_key.public().clone())
+            .targets_key(targets_key.public().clone())
+            .timestamp_key(timestamp_key.public().clone())
+            .signed::<Json>(&root_key),
+    )
+    .unwrap();
 
     let mut tuf = Tuf::<Json>::from_root_pinned(root, &[root_key.key_id().clone()]).unwrap();
 
     //// build the snapshot and timestamp ////
 
-    let snapshot = SnapshotMetadataBuilder::new()
-        .insert_metadata_description(
-            MetadataPath::new("targets".into()).unwrap(),
-            MetadataDescription::from_reader(&*vec![0u8], 1, &[HashAlgorithm::Sha256]).unwrap(),
-        )
-        .insert_metadata_description(
-            MetadataPath::new("delegation".into()).unwrap(),
-            MetadataDescription::from_reader(&*vec![0u8], 1, &[HashAlgorithm::Sha256]).unwrap(),
-        )
-        .signed::<Json>(&snapshot_key)
-        .unwrap();
+    let snapshot = block_on(
+        SnapshotMetadataBuilder::new()
+            .insert_metadata_description(
+                MetadataPath::new("targets".into()).unwrap(),
+                MetadataDescription::from_reader(&*vec![0u8], 1, &[HashAlgorithm::Sha256]).unwrap(),
+            )
+            .insert_metadata_description(
+                MetadataPath::new("delegation".into()).unwrap(),
+                MetadataDescription::from_reader(&*vec![0u8], 1, &[HashAlgorithm::Sha256]).unwrap(),
+            )
+            .signed::<Json>(&snapshot_key),
+    )
+    .unwrap();
 
-    let timestamp = TimestampMetadataBuilder::from_snapshot(&snapshot, &[HashAlgorithm::Sha256])
-        .unwrap()
-        .signed::<Json>(&timestamp_key)
-        .unwrap();
+    let timestamp = block_on(
+        TimestampMetadataBuilder::from_snapshot(&snapshot, &[HashAlgorithm::Sha256])
+            .unwrap()
+            .signed::<Json>(&timestamp_key),
+    )
+    .unwrap();
 
     tuf.update_timestamp(timestamp).unwrap();
     tuf.update_snapshot(snapshot).unwrap();
@@ -75,24 +82,28 @@ fn simple_delegation() {
         .unwrap()],
     )
     .unwrap();
-    let targets = TargetsMetadataBuilder::new()
-        .delegations(delegations)
-        .signed::<Json>(&targets_key)
-        .unwrap();
+    let targets = block_on(
+        TargetsMetadataBuilder::new()
+            .delegations(delegations)
+            .signed::<Json>(&targets_key),
+    )
+    .unwrap();
 
     tuf.update_targets(targets).unwrap();
 
     //// build the delegation ////
     let target_file: &[u8] = b"bar";
-    let delegation = TargetsMetadataBuilder::new()
-        .insert_target_from_reader(
-            VirtualTargetPath::new("foo".into()).unwrap(),
-            target_file,
-            &[HashAlgorithm::Sha256],
-        )
-        .unwrap()
-        .signed::<Json>(&delegation_key)
-        .unwrap();
+    let delegation = block_on(
+        TargetsMetadataBuilder::new()
+            .insert_target_from_reader(
+                VirtualTargetPath::new("foo".into()).unwrap(),
+                target_file,
+                &[HashAlgorithm::Sha256],
+            )
+            .unwrap()
+            .signed::<Json>(&delegation_key),
+    )
+    .unwrap();
 
     tuf.update_delegation(&MetadataPath::new("delegation".into()).unwrap(), delegation)
         .unwrap();
@@ -113,38 +124,44 @@ fn nested_delegation() {
 
     //// build the root ////
 
-    let root = RootMetadataBuilder::new()
-        .root_key(root_key.public().clone())
-        .snapshot_key(snapshot_key.public().clone())
-        .targets_key(targets_key.public().clone())
-        .timestamp_key(timestamp_key.public().clone())
-        .signed::<Json>(&root_key)
-        .unwrap();
+    let root = block_on(
+        RootMetadataBuilder::new()
+            .root_key(root_key.public().clone())
+            .snapshot_key(snapshot_key.public().clone())
+            .targets_key(targets_key.public().clone())
+            .timestamp_key(timestamp_key.public().clone())
+            .signed::<Json>(&root_key),
+    )
+    .unwrap();
 
     let mut tuf = Tuf::<Json>::from_root_pinned(root, &[root_key.key_id().clone()]).unwrap();
 
     //// build the snapshot and timestamp ////
 
-    let snapshot = SnapshotMetadataBuilder::new()
-        .insert_metadata_description(
-            MetadataPath::new("targets".into()).unwrap(),
-            MetadataDescription::from_reader(&*vec![0u8], 1, &[HashAlgorithm::Sha256]).unwrap(),
-        )
-        .insert_metadata_description(
-            MetadataPath::new("delegation-a".into()).unwrap(),
-            MetadataDescription::from_reader(&*vec![0u8], 1, &[HashAlgorithm::Sha256]).unwrap(),
-        )
-        .insert_metadata_description(
-            MetadataPath::new("delegation-b".into()).unwrap(),
-            MetadataDescription::from_reader(&*vec![0u8], 1, &[HashAlgorithm::Sha256]).unwrap(),
-        )
-        .signed::<Json>(&snapshot_key)
-        .unwrap();
+    let snapshot = block_on(
+        SnapshotMetadataBuilder::new()
+            .insert_metadata_description(
+                MetadataPath::new("targets".into()).unwrap(),
+                MetadataDescription::from_reader(&*vec![0u8], 1, &[HashAlgorithm::Sha256]).unwrap(),
+            )
+            .insert_metadata_description(
+                MetadataPath::new("delegation-a".into()).unwrap(),
+                MetadataDescription::from_reader(&*vec![0u8], 1, &[HashAlgorithm::Sha256]).unwrap(),
+            )
+            .insert_metadata_description(
+                MetadataPath::new("delegation-b".into()).unwrap(),
+                MetadataDescription::from_reader(&*vec![0u8], 1, &[HashAlgorithm::Sha256]).unwrap(),
+            )
+            .signed::<Json>(&snapshot_key),
+    )
+    .unwrap();
 
-    let timestamp = TimestampMetadataBuilder::from_snapshot(&snapshot, &[HashAlgorithm::Sha256])
-        .unwrap()
-        .signed::<Json>(&timestamp_key)
-        .unwrap();
+    let timestamp = block_on(
+        TimestampMetadataBuilder::from_snapshot(&snapshot, &[HashAlgorithm::Sha256])
+            .unwrap()
+            .signed::<Json>(&timestamp_key),
+    )
+    .unwrap();
 
     tuf.update_timestamp(timestamp).unwrap();
     tuf.update_snapshot(snapshot).unwrap();
@@ -169,10 +186,12 @@ fn nested_delegation() {
         .unwrap()],
     )
     .unwrap();
-    let targets = TargetsMetadataBuilder::new()
-        .delegations(delegations)
-        .signed::<Json>(&targets_key)
-        .unwrap();
+    let targets = block_on(
+        TargetsMetadataBuilder::new()
+            .delegations(delegations)
+            .signed::<Json>(&targets_key),
+    )
+    .unwrap();
 
     tuf.update_targets(targets).unwrap();
 
@@ -197,10 +216,12 @@ fn nested_delegation() {
     )
     .unwrap();
 
-    let delegation = TargetsMetadataBuilder::new()
-        .delegations(delegations)
-        .signed::<Json>(&delegation_a_key)
-        .unwrap();
+    let delegation = block_on(
+        TargetsMetadataBuilder::new()
+            .delegations(delegations)
+            .signed::<Json>(&delegation_a_key),
+    )
+    .unwrap();
 
     tuf.update_delegation(
         &MetadataPath::new("delegation-a".into()).unwrap(),
@@ -212,15 +233,17 @@ fn nested_delegation() {
 
     let target_file: &[u8] = b"bar";
 
-    let delegation = TargetsMetadataBuilder::new()
-        .insert_target_from_reader(
-            VirtualTargetPath::new("foo".into()).unwrap(),
-            target_file,
-            &[HashAlgorithm::Sha256],
-        )
-        .unwrap()
-        .signed::<Json>(&delegation_b_key)
-        .unwrap();
+    let delegation = block_on(
+        TargetsMetadataBuilder::new()
+            .insert_target_from_reader(
+                VirtualTargetPath::new("foo".into()).unwrap(),
+                target_file,
+                &[HashAlgorithm::Sha256],
+            )
+            .unwrap()
+            .signed::<Json>(&delegation_b_key),
+    )
+    .unwrap();
 
     tuf.update_delegation(
         &MetadataPath::new("delegation-b".into()).unwrap(),
@@ -0,0 +1,42 @@
+#!/bin/bash
+set -eux
+
+cd "$(dirname "$0")"
+
+passphrase="correct horse battery staple"
+
+for key in ../ed25519/ed25519-1 ../rsa/rsa-2048 ../ecdsa/ecdsa-p256; do
+    name="$(basename "$key")"
+
+    openssl pkey -in "$key.pk8.der" \
+                 -inform der \
+                 -pubout \
+                 -outform pem \
+                 -out "$name.spki.pem"
+
+    openssl pkcs8 -topk8 \
+                  -inform der \
+                  -in "$key.pk8.der" \
+                  -outform pem \
+                  -out "$name.pk8.pem" \
+                  -nocrypt
+
+    openssl pkcs8 -topk8 \
+                  -inform der \
+                  -in "$key.pk8.der" \
+                  -outform pem \
+                  -out "$name.pk8.enc.pem" \
+                  -v2 aes-256-cbc \
+                  -v2prf hmacWithSHA256 \
+                  -passout "pass:$passphrase"
+done
+
+# older openssl defaults
+openssl pkcs8 -topk8 \
+              -inform der \
+              -in ../rsa/rsa-2048.pk8.der \
+              -outform pem \
+              -out rsa-2048.pk8.enc-aes128-sha1.pem \
+              -v2 aes-128-cbc \
+              -v2prf hmacWithSHA1 \
+              -passout "pass:$passphrase"
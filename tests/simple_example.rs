@@ -1,15 +1,17 @@
 #![feature(async_await, await_macro, futures_api)]
 
 use futures::executor::block_on;
-use tuf::client::{Client, Config, PathTranslator};
+use std::fs;
+use std::sync::{Arc, Mutex};
+use tuf::client::{Client, Config, Event, Observer, PathTranslator};
 use tuf::crypto::{HashAlgorithm, KeyId, PrivateKey, SignatureScheme};
 use tuf::interchange::Json;
 use tuf::metadata::{
-    MetadataPath, MetadataVersion, RootMetadataBuilder, SnapshotMetadataBuilder, TargetPath,
-    TargetsMetadataBuilder, TimestampMetadataBuilder, VirtualTargetPath,
+    MetadataPath, MetadataVersion, RootMetadataBuilder, SnapshotMetadataBuilder, TargetDescription,
+    TargetPath, TargetsMetadataBuilder, TimestampMetadataBuilder, VirtualTargetPath,
 };
-use tuf::repository::{EphemeralRepository, Repository};
-use tuf::Result;
+use tuf::repository::{EphemeralRepository, FileSystemRepository, Repository};
+use tuf::{Error, Result};
 
 // Ironically, this is far from simple, but it's as simple as it can be made.
 
@@ -18,6 +20,8 @@ const ED25519_2_PK8: &'static [u8] = include_bytes!("./ed25519/ed25519-2.pk8.der
 const ED25519_3_PK8: &'static [u8] = include_bytes!("./ed25519/ed25519-3.pk8.der");
 const ED25519_4_PK8: &'static [u8] = include_bytes!("./ed25519/ed25519-4.pk8.der");
 
+const TARGET_FILE: &'static [u8] = b"things fade, alternatives exclude";
+
 struct MyPathTranslator {}
 
 impl PathTranslator for MyPathTranslator {
@@ -30,6 +34,28 @@ impl PathTranslator for MyPathTranslator {
     }
 }
 
+/// Records a summary of the events it is told about.
+#[derive(Default)]
+struct RecordingObserver(Mutex<Vec<String>>);
+
+impl Observer for RecordingObserver {
+    fn notify(&self, event: &Event) {
+        let event = match *event {
+            Event::MetadataFetchFinished { role, updated } => {
+                format!("{:?} updated: {}", role, updated)
+            }
+            Event::RootRotated { to_version, .. } => format!("root rotated to {}", to_version),
+            Event::TargetBytesReceived {
+                received, total, ..
+            } => format!("received {}/{}", received, total),
+            Event::TargetFetchFinished { target } => format!("fetched {}", target.value()),
+            Event::TargetFetchFailed { target, .. } => format!("failed {}", target.value()),
+            _ => return,
+        };
+        self.0.lock().unwrap().push(event);
+    }
+}
+
 #[test]
 fn with_translator() {
     let mut remote = EphemeralRepository::<Json>::new();
@@ -58,13 +84,170 @@ fn without_translator() {
     )
 }
 
-async fn init_client<T: 'static>(
+#[test]
+fn resume_partial_download() {
+    let temp_dir = tempfile::Builder::new()
+        .prefix("rust-tuf")
+        .tempdir()
+        .unwrap();
+    let partial_dir = temp_dir.path().join("partial");
+    let config = Config::build()
+        .partial_download_dir(partial_dir.clone())
+        .finish()
+        .unwrap();
+
+    let description =
+        TargetDescription::from_reader(TARGET_FILE, &[HashAlgorithm::Sha256]).unwrap();
+    let partial_path = partial_dir.join(description.hashes()[&HashAlgorithm::Sha256].to_string());
+    fs::create_dir_all(&partial_dir).unwrap();
+
+    let mut remote = FileSystemRepository::<Json>::new(temp_dir.path().join("remote")).unwrap();
+    block_on(
+        async {
+            let root_key_ids = await!(init_server(&mut remote, &config)).unwrap();
+            let mut client = await!(Client::with_root_pinned(
+                &root_key_ids,
+                config,
+                EphemeralRepository::<Json>::new(),
+                remote
+            ))
+            .unwrap();
+            let _ = await!(client.update()).unwrap();
+            let target_path = TargetPath::new("foo-bar".into()).unwrap();
+
+            // the start of the target is taken from the partial file, so a corrupted one is found
+            // out and not trusted again
+            fs::write(&partial_path, b"THINGS FADE").unwrap();
+            assert!(await!(client.fetch_target(&target_path)).is_err());
+            assert!(!partial_path.exists());
+
+            // only the rest of a truncated download is fetched
+            fs::write(&partial_path, &TARGET_FILE[..11]).unwrap();
+            await!(client.fetch_target(&target_path)).unwrap();
+            assert!(!partial_path.exists());
+        },
+    );
+}
+
+#[test]
+fn fetch_multiple_targets() {
+    let config = Config::build()
+        .max_concurrent_downloads(2)
+        .max_download_bytes_per_second(Some(1024 * 1024))
+        .finish()
+        .unwrap();
+
+    let mut remote = EphemeralRepository::<Json>::new();
+    block_on(
+        async {
+            let root_key_ids = await!(init_server(&mut remote, &config)).unwrap();
+            let mut client = await!(Client::with_root_pinned(
+                &root_key_ids,
+                config,
+                EphemeralRepository::<Json>::new(),
+                remote
+            ))
+            .unwrap();
+            let _ = await!(client.update()).unwrap();
+
+            let targets = vec![
+                TargetPath::new("foo-bar".into()).unwrap(),
+                TargetPath::new("missing".into()).unwrap(),
+                TargetPath::new("foo-bar".into()).unwrap(),
+            ];
+            let results = await!(client.fetch_targets(&targets));
+            assert_eq!(results.len(), 3);
+            assert_eq!(results[0], Ok(()));
+            assert_eq!(results[1], Err(Error::NotFound));
+            assert_eq!(results[2], Ok(()));
+        },
+    )
+}
+
+#[test]
+fn fetch_multiple_targets_resumable() {
+    let temp_dir = tempfile::Builder::new()
+        .prefix("rust-tuf")
+        .tempdir()
+        .unwrap();
+    let partial_dir = temp_dir.path().join("partial");
+    let config = Config::build()
+        .partial_download_dir(partial_dir.clone())
+        .max_concurrent_downloads(2)
+        .max_download_bytes_per_second(Some(1024 * 1024))
+        .finish()
+        .unwrap();
+
+    let mut remote = EphemeralRepository::<Json>::new();
+    block_on(
+        async {
+            let root_key_ids = await!(init_server(&mut remote, &config)).unwrap();
+            let mut client = await!(Client::with_root_pinned(
+                &root_key_ids,
+                config,
+                EphemeralRepository::<Json>::new(),
+                remote
+            ))
+            .unwrap();
+            let _ = await!(client.update()).unwrap();
+
+            // both downloads are of the same contents, so they would share a partial file
+            let targets = vec![
+                TargetPath::new("foo-bar".into()).unwrap(),
+                TargetPath::new("foo-bar".into()).unwrap(),
+            ];
+            let results = await!(client.fetch_targets(&targets));
+            assert_eq!(results, vec![Ok(()), Ok(())]);
+        },
+    );
+    assert_eq!(fs::read_dir(&partial_dir).unwrap().count(), 0);
+}
+
+#[test]
+fn observe_update_and_fetch() {
+    let observer = Arc::new(RecordingObserver::default());
+    let config = Config::build().observer(observer.clone()).finish().unwrap();
+
+    let mut remote = EphemeralRepository::<Json>::new();
+    block_on(
+        async {
+            let root_key_ids = await!(init_server(&mut remote, &config)).unwrap();
+            await!(init_client(&root_key_ids, remote, config)).unwrap();
+        },
+    );
+
+    let mut events = observer.0.lock().unwrap().clone();
+    let received = events
+        .iter()
+        .filter(|event| event.starts_with("received "))
+        .cloned()
+        .collect::<Vec<_>>();
+    events.retain(|event| !event.starts_with("received "));
+
+    assert_eq!(
+        events,
+        vec![
+            "Root updated: false",
+            "Timestamp updated: true",
+            "Snapshot updated: true",
+            "Targets updated: true",
+            "fetched foo-bar",
+        ]
+    );
+    assert_eq!(
+        received.last().map(|event| event.as_str()),
+        Some(format!("received {}/{}", TARGET_FILE.len(), TARGET_FILE.len()).as_str())
+    );
+}
+
+async fn init_client<T: 'static, R>(
     root_key_ids: &[KeyId],
-    remote: EphemeralRepository<Json>,
+    remote: R,
     config: Config<T>,
 ) -> Result<()>
 where
     T: PathTranslator,
+    R: Repository<Json> + 'static,
 {
     let local = EphemeralRepository::<Json>::new();
     let mut client = await!(Client::with_root_pinned(
@@ -78,12 +261,10 @@ where
     await!(client.fetch_target(&target_path))
 }
 
-async fn init_server<'a, T>(
-    remote: &'a mut EphemeralRepository<Json>,
-    config: &'a Config<T>,
-) -> Result<Vec<KeyId>>
+async fn init_server<'a, T, R>(remote: &'a mut R, config: &'a Config<T>) -> Result<Vec<KeyId>>
 where
     T: PathTranslator,
+    R: Repository<Json>,
 {
     // in real life, you wouldn't want these keys on the same machine ever
     let root_key = PrivateKey::from_pkcs8(ED25519_1_PK8, SignatureScheme::Ed25519)?;
@@ -93,12 +274,12 @@ where
 
     //// build the root ////
 
-    let signed = RootMetadataBuilder::new()
+    let signed = await!(RootMetadataBuilder::new()
         .root_key(root_key.public().clone())
         .snapshot_key(snapshot_key.public().clone())
         .targets_key(targets_key.public().clone())
         .timestamp_key(timestamp_key.public().clone())
-        .signed::<Json>(&root_key)?;
+        .signed::<Json>(&root_key))?;
 
     let root_path = MetadataPath::new("root".into())?;
     await!(remote.store_metadata(&root_path, &MetadataVersion::Number(1), &signed,))?;
@@ -106,18 +287,16 @@ where
 
     //// build the targets ////
 
-    let target_file: &[u8] = b"things fade, alternatives exclude";
-
     let target_path = TargetPath::new("foo-bar".into())?;
-    let _ = await!(remote.store_target(target_file, &target_path));
+    let _ = await!(remote.store_target(TARGET_FILE, &target_path));
 
-    let targets = TargetsMetadataBuilder::new()
+    let targets = await!(TargetsMetadataBuilder::new()
         .insert_target_from_reader(
             config.path_translator().real_to_virtual(&target_path)?,
-            target_file,
+            TARGET_FILE,
             &[HashAlgorithm::Sha256],
         )?
-        .signed::<Json>(&targets_key)?;
+        .signed::<Json>(&targets_key))?;
 
     let targets_path = &MetadataPath::new("targets".into())?;
     await!(remote.store_metadata(&targets_path, &MetadataVersion::Number(1), &targets,))?;
@@ -125,9 +304,9 @@ where
 
     //// build the snapshot ////
 
-    let snapshot = SnapshotMetadataBuilder::new()
+    let snapshot = await!(SnapshotMetadataBuilder::new()
         .insert_metadata(&targets, &[HashAlgorithm::Sha256])?
-        .signed::<Json>(&snapshot_key)?;
+        .signed::<Json>(&snapshot_key))?;
 
     let snapshot_path = MetadataPath::new("snapshot".into())?;
     await!(remote.store_metadata(&snapshot_path, &MetadataVersion::Number(1), &snapshot,))?;
@@ -135,8 +314,8 @@ where
 
     //// build the timestamp ////
 
-    let timestamp = TimestampMetadataBuilder::from_snapshot(&snapshot, &[HashAlgorithm::Sha256])?
-        .signed::<Json>(&timestamp_key)?;
+    let timestamp = TimestampMetadataBuilder::from_snapshot(&snapshot, &[HashAlgorithm::Sha256])?;
+    let timestamp = await!(timestamp.signed::<Json>(&timestamp_key))?;
 
     let timestamp_path = MetadataPath::new("timestamp".into())?;
     await!(remote.store_metadata(&timestamp_path, &MetadataVersion::Number(1), &timestamp,))?;